@@ -0,0 +1,73 @@
+//! Completion CLI module.
+//!
+//! This module provides subcommands and a command matcher related to shell completion.
+
+use anyhow::Result;
+use clap::Shell;
+use log::{debug, trace};
+
+type ShellName<'a> = &'a str;
+
+/// Represents the values completed dynamically by the shell scripts.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Values {
+    /// Represents the account names of the config.
+    Accounts,
+    /// Represents the card ids of the selected account.
+    Ids,
+}
+
+/// Represents the completion commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the generate completion script command.
+    Generate(ShellName<'a>),
+    /// Represents the hidden command listing values for the completion scripts.
+    Values(Values),
+}
+
+/// Defines the completion command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    if let Some(m) = m.subcommand_matches("completion") {
+        debug!("completion subcommand matched");
+        let shell = m.value_of("shell").unwrap();
+        trace!("shell: {}", shell);
+        return Ok(Some(Cmd::Generate(shell)));
+    }
+
+    if let Some(m) = m.subcommand_matches("complete-values") {
+        debug!("complete values subcommand matched");
+        let values = match m.value_of("values") {
+            Some("ids") => Values::Ids,
+            _ => Values::Accounts,
+        };
+        trace!("values: {:?}", values);
+        return Ok(Some(Cmd::Values(values)));
+    }
+
+    Ok(None)
+}
+
+/// Contains completion subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![
+        clap::SubCommand::with_name("completion")
+            .aliases(&["completions", "compl"])
+            .about("Generates the completion script for the given shell")
+            .after_help(
+                "Account names and card ids are completed dynamically by bash, zsh and fish.",
+            )
+            .arg(
+                clap::Arg::with_name("shell")
+                    .possible_values(&Shell::variants()[..])
+                    .required(true),
+            ),
+        clap::SubCommand::with_name("complete-values")
+            .setting(clap::AppSettings::Hidden)
+            .arg(
+                clap::Arg::with_name("values")
+                    .possible_values(&["accounts", "ids"])
+                    .required(true),
+            ),
+    ]
+}
@@ -0,0 +1,175 @@
+//! Completion handling module.
+//!
+//! This module gathers completion actions triggered by the CLI. The scripts generated by clap
+//! are extended so that account names and card ids are asked to cardamom itself, through the
+//! hidden `complete-values` subcommand.
+
+use anyhow::{anyhow, Result};
+use clap::{App, Shell};
+use std::io::Write;
+
+use crate::config::{Account, Config};
+
+/// Subcommands taking a card id as first argument.
+const ID_SUBCMDS: [&str; 3] = ["read", "update", "delete"];
+
+fn bash_script(bin: &str) -> String {
+    format!(
+        r#"
+_{bin}_dynamic() {{
+    local cur prev i
+    local opts=()
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    for ((i = 1; i < COMP_CWORD - 1; i++)); do
+        case "${{COMP_WORDS[i]}}" in
+            -c|--config|-a|--account) opts+=("${{COMP_WORDS[i]}}" "${{COMP_WORDS[i+1]}}") ;;
+        esac
+    done
+    case "${{prev}}" in
+        -a|--account)
+            COMPREPLY=($(compgen -W "$({bin} "${{opts[@]}}" complete-values accounts 2>/dev/null)" -- "${{cur}}"))
+            return 0
+            ;;
+        {subcmds})
+            if [[ ${{cur}} != -* ]]; then
+                COMPREPLY=($(compgen -W "$({bin} "${{opts[@]}}" complete-values ids 2>/dev/null)" -- "${{cur}}"))
+                return 0
+            fi
+            ;;
+    esac
+    _{bin} "$@"
+}}
+
+complete -F _{bin}_dynamic -o bashdefault -o default {bin}
+"#,
+        bin = bin,
+        subcmds = ID_SUBCMDS.join("|"),
+    )
+}
+
+fn zsh_functions(bin: &str) -> String {
+    format!(
+        r#"
+_{bin}_opts() {{
+    local -a tokens
+    local i
+    tokens=(${{(z)BUFFER}})
+    reply=()
+    for (( i = 2; i < ${{#tokens}}; i++ )); do
+        case $tokens[i] in
+            (-c|--config|-a|--account) reply+=($tokens[i] ${{(Q)tokens[i+1]}}) ;;
+        esac
+    done
+}}
+
+_{bin}_values() {{
+    local -a reply values
+    _{bin}_opts
+    values=(${{(f)"$({bin} $reply complete-values $1 2>/dev/null)"}})
+    compadd -a values
+}}
+"#,
+        bin = bin,
+    )
+}
+
+fn fish_script(bin: &str) -> String {
+    format!(
+        r#"
+function __{bin}_opts
+    set -l tokens (commandline -opc)
+    set -e tokens[1]
+    while set -q tokens[2]
+        switch $tokens[1]
+            case -c --config -a --account
+                echo $tokens[1]
+                echo $tokens[2]
+        end
+        set -e tokens[1]
+    end
+end
+
+complete -c {bin} -s a -l account -x -a '({bin} (__{bin}_opts) complete-values accounts 2>/dev/null)'
+complete -c {bin} -n '__fish_seen_subcommand_from {subcmds}' -f -a '({bin} (__{bin}_opts) complete-values ids 2>/dev/null)'
+"#,
+        bin = bin,
+        subcmds = ID_SUBCMDS.join(" "),
+    )
+}
+
+/// Plugs the dynamic completion functions into the zsh script generated by clap.
+fn patch_zsh_script(bin: &str, script: &str) -> String {
+    let script = script
+        .lines()
+        .map(|line| {
+            if line.starts_with("'-a+[") || line.starts_with("'--account=[") {
+                line.replacen(
+                    "]' \\",
+                    &format!("]:account:_{}_values accounts' \\", bin),
+                    1,
+                )
+            } else if line.starts_with("':id") {
+                line.replacen(":_files' \\", &format!(":_{}_values ids' \\", bin), 1)
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // The functions need to be defined before the main one gets called, at the end of the
+    // script.
+    match script.split_once('\n') {
+        Some((compdef, rest)) => format!("{}\n{}\n{}\n", compdef, zsh_functions(bin), rest),
+        None => script,
+    }
+}
+
+/// Generates the completion script of the given shell.
+pub fn generate<W: Write>(mut app: App, shell: &str, output: &mut W) -> Result<()> {
+    let shell: Shell = shell.parse().map_err(|err: String| anyhow!(err))?;
+    let bin = app.get_name().to_owned();
+
+    let mut script = vec![];
+    app.gen_completions_to(&bin, shell, &mut script);
+    let script = String::from_utf8(script)?;
+
+    let script = match shell {
+        Shell::Bash => format!("{}{}", script, bash_script(&bin)),
+        Shell::Zsh => patch_zsh_script(&bin, &script),
+        Shell::Fish => format!("{}{}", script, fish_script(&bin)),
+        Shell::PowerShell | Shell::Elvish => script,
+    };
+
+    output.write_all(script.as_bytes())?;
+    Ok(())
+}
+
+/// Lists the account names of the config, one per line.
+pub fn accounts(config: &Config) -> Result<()> {
+    let mut names: Vec<&String> = config.accounts.keys().collect();
+    names.sort();
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+/// Lists the card ids of the account, one per line.
+pub fn ids(account: &Account) -> Result<()> {
+    if !matches!(account, Account::Local(_)) {
+        let mut ids: Vec<String> = account.with_repository(|repository| {
+            Ok(repository
+                .read_all()?
+                .into_iter()
+                .map(|card| card.id)
+                .collect())
+        })?;
+        ids.sort();
+        for id in ids {
+            println!("{}", id);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,4 @@
+//! Module related to shell completion.
+
+pub mod compl_arg;
+pub mod compl_handler;
@@ -0,0 +1,92 @@
+//! Account CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to the account
+//! management.
+
+use anyhow::Result;
+use log::{debug, trace};
+
+type Backend<'a> = Option<&'a str>;
+type Name<'a> = &'a str;
+
+/// Represents the account commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the list accounts command.
+    List,
+    /// Represents the show account command.
+    Show(Name<'a>),
+    /// Represents the set password command.
+    SetPasswd(Backend<'a>),
+    /// Represents the doctor command.
+    Doctor,
+}
+
+/// Defines the account command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    if let Some(m) = m.subcommand_matches("accounts") {
+        if let Some(m) = m.subcommand_matches("show") {
+            debug!("show account subcommand matched");
+            let name = m.value_of("name").unwrap();
+            trace!("name: {}", name);
+            return Ok(Some(Cmd::Show(name)));
+        }
+        debug!("list accounts subcommand matched");
+        return Ok(Some(Cmd::List));
+    }
+
+    if let Some(m) = m.subcommand_matches("account") {
+        if let Some(m) = m.subcommand_matches("set-password") {
+            debug!("set password subcommand matched");
+            let backend = m.value_of("backend");
+            trace!("backend: {:?}", backend);
+            return Ok(Some(Cmd::SetPasswd(backend)));
+        }
+    }
+
+    if m.is_present("doctor") {
+        debug!("doctor subcommand matched");
+        return Ok(Some(Cmd::Doctor));
+    }
+
+    Ok(None)
+}
+
+/// Contains account subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![
+        clap::SubCommand::with_name("accounts")
+            .about("Lists the accounts of the config")
+            .subcommand(
+                clap::SubCommand::with_name("show")
+                    .about("Shows the resolved settings of an account, secrets redacted")
+                    .arg(
+                        clap::Arg::with_name("name")
+                            .help("Specifies the account name")
+                            .value_name("NAME")
+                            .required(true),
+                    ),
+            ),
+        clap::SubCommand::with_name("account")
+            .about("Manages the selected account")
+            .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                clap::SubCommand::with_name("set-password")
+                    .aliases(&["set-passwd"])
+                    .about("Stores the password of the account, read from the standard input")
+                    .arg(backend_arg()),
+            ),
+        clap::SubCommand::with_name("doctor")
+            .about("Diagnoses the connection to the server of the selected account, step by step"),
+    ]
+}
+
+/// Defines the password backend argument.
+pub fn backend_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("backend")
+        .long("backend")
+        .short("b")
+        .help("Stores the password in this backend instead of the configured one")
+        .value_name("BACKEND")
+        .possible_values(&["keyring", "netrc"])
+}
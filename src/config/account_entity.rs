@@ -1,14 +1,34 @@
 use anyhow::{anyhow, Context, Error, Result};
 use log::{debug, trace};
-use std::{convert::TryFrom, process::Command};
+use reqwest::{
+    blocking::{Client, ClientBuilder},
+    tls::TlsInfo,
+    Proxy, Url,
+};
+use std::{convert::TryFrom, path::PathBuf, time::Duration};
 
-use crate::config::{Config, ConfigAccountEntry};
+use crate::{
+    config::{
+        AuthConfig, Cmd, Config, ConfigAccountEntry, Keyring, Netrc, OAuth2Config,
+        OAuth2TokenProvider, TlsConfig,
+    },
+    domain::{
+        card_repositories::{
+            Auth, CachingCardRepository, DigestAuth, QueueingCardRepository, RemoteCardRepository,
+            RetryPolicy, SqliteCardRepository, TelIndexInvalidatingCardRepository,
+            TelNormalizingCardRepository, CARD_CACHE_FILE,
+        },
+        parse_region, CardError, CardRepository, Region, TEL_INDEX_FILE, WRITE_QUEUE_FILE,
+    },
+};
 
 /// Represents a user account.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Account {
     Local(LocalAccount),
     Remote(RemoteAccount),
+    Sqlite(SqliteAccount),
 }
 
 /// Represents a local user account.
@@ -16,6 +36,72 @@ pub enum Account {
 pub struct LocalAccount {
     pub name: String,
     pub path: String,
+    pub default_region: Option<Region>,
+    pub normalize_tel: bool,
+}
+
+/// Represents a user account storing its cards in a SQLite database.
+#[derive(Debug, Default)]
+pub struct SqliteAccount {
+    pub name: String,
+    /// Path of the database, shell variables and `~` expanded.
+    pub path: PathBuf,
+    pub default_region: Option<Region>,
+    pub normalize_tel: bool,
+}
+
+impl SqliteAccount {
+    /// Opens the card repository of the account, creating the database if needed.
+    pub fn repository(&self) -> Result<SqliteCardRepository> {
+        SqliteCardRepository::open(&self.path).with_context(|| {
+            format!(
+                r#"cannot open database {:?} of account "{}""#,
+                self.path, self.name
+            )
+        })
+    }
+}
+
+/// Represents the password authentication scheme of a remote account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PasswdScheme {
+    /// Basic or digest, depending on the challenge of the server.
+    #[default]
+    Auto,
+    Basic,
+    Digest,
+}
+
+/// Represents where the password of a remote account comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswdSource {
+    /// Output of a command.
+    Cmd(Cmd),
+    /// Entry of the system keyring.
+    Keyring(String),
+    /// Entry of the netrc file matching the host of the account URL.
+    Netrc,
+}
+
+impl Default for PasswdSource {
+    fn default() -> Self {
+        Self::Cmd(Cmd::default())
+    }
+}
+
+impl PasswdSource {
+    /// Builds the password source from the `passwd-cmd`, `passwd-keyring` and `netrc` entries,
+    /// only one of them being allowed.
+    pub fn new(cmd: &Cmd, keyring: Option<&str>, netrc: Option<bool>) -> Result<Self> {
+        match (cmd.is_empty(), keyring, netrc.unwrap_or_default()) {
+            (_, None, false) => Ok(Self::Cmd(cmd.clone())),
+            (true, Some(entry), false) => Ok(Self::Keyring(entry.to_owned())),
+            (true, None, true) => Ok(Self::Netrc),
+            _ => Err(anyhow!(
+                "cannot use more than one of passwd-cmd, passwd-keyring and netrc"
+            )),
+        }
+    }
 }
 
 /// Represents a remote user account.
@@ -24,16 +110,322 @@ pub struct RemoteAccount {
     pub name: String,
     pub url: String,
     pub login: String,
-    pub passwd_cmd: String,
+    pub passwd_source: PasswdSource,
+    pub passwd_scheme: PasswdScheme,
+    pub oauth2: Option<OAuth2Config>,
+    pub tls: TlsConfig,
+    pub proxy: Option<String>,
+    pub connect_timeout: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub retry: RetryPolicy,
+    pub default_region: Option<Region>,
+    pub normalize_tel: bool,
+    pub cache: bool,
+    pub offline_queue: bool,
 }
 
 impl RemoteAccount {
-    pub fn passwd(&self) -> Result<String> {
-        let passwd = run_cmd(&self.passwd_cmd).context("cannot run passwd cmd")?;
-        let passwd = passwd
-            .trim_end_matches(|c| c == '\r' || c == '\n')
-            .to_owned();
-        Ok(passwd)
+    /// Returns the host of the account URL.
+    pub fn host(&self) -> Result<String> {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(ToOwned::to_owned))
+            .ok_or_else(|| anyhow!(r#"cannot find host of url "{}""#, self.url))
+    }
+
+    /// Returns the login and the password of the account, from its password source. The netrc
+    /// login is used when the account has none.
+    pub fn credentials(&self) -> Result<(String, String)> {
+        match &self.passwd_source {
+            PasswdSource::Cmd(cmd) => {
+                let passwd = cmd.run().context("cannot run passwd cmd")?;
+                let passwd = passwd.trim_end_matches(['\r', '\n']).to_owned();
+                Ok((self.login.clone(), passwd))
+            }
+            PasswdSource::Keyring(entry) => {
+                let passwd = Keyring::new(entry).get_passwd()?;
+                Ok((self.login.clone(), passwd))
+            }
+            PasswdSource::Netrc => {
+                let host = self.host()?;
+                let netrc = Netrc::read()?;
+                let machine = netrc
+                    .find(&host)
+                    .ok_or_else(|| anyhow!(r#"cannot find netrc entry for "{}""#, host))?;
+                let login = match self.login.as_str() {
+                    "" => machine.login.clone().unwrap_or_default(),
+                    login => login.to_owned(),
+                };
+                Ok((login, machine.passwd.clone().unwrap_or_default()))
+            }
+        }
+    }
+
+    fn client_builder(&self) -> Result<ClientBuilder> {
+        let mut builder = Client::builder();
+
+        if let Some(proxy) = self.proxy.as_deref() {
+            let proxy =
+                Proxy::all(proxy).with_context(|| format!(r#"cannot parse proxy "{}""#, proxy))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        // Passing `None` would disable the default timeout of the client.
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        Ok(builder)
+    }
+
+    /// Fetches the DER certificate presented by the server, through the proxy of the account.
+    /// The certificate is not verified and no credential is sent.
+    pub fn server_certificate(&self) -> Result<Vec<u8>> {
+        let url =
+            Url::parse(&self.url).with_context(|| format!(r#"cannot parse url "{}""#, self.url))?;
+        if url.scheme() != "https" {
+            return Err(anyhow!(
+                r#"cannot get certificate of non-https url "{}""#,
+                url
+            ));
+        }
+        let client = self
+            .client_builder()?
+            .use_preconfigured_tls(self.tls.probe_connector()?)
+            .tls_info(true)
+            .build()
+            .context("cannot build http client")?;
+        let res = client
+            .head(url)
+            .send()
+            .map_err(CardError::from)
+            .context("cannot connect to server")?;
+        res.extensions()
+            .get::<TlsInfo>()
+            .and_then(TlsInfo::peer_certificate)
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| anyhow!("cannot find server certificate"))
+    }
+
+    /// Builds the HTTP client of the account, applying its TLS, proxy and timeout options. When
+    /// the server certificate is pinned, it is fetched first, then trusted by the client once
+    /// checked, so that a self-signed one passes the handshake.
+    pub fn client(&self) -> Result<Client> {
+        let connector = if self.tls.is_pinned() {
+            let cert = self.server_certificate()?;
+            self.tls.pinned_connector(&cert).with_context(|| {
+                format!(r#"cannot verify certificate of account "{}""#, self.name)
+            })?
+        } else {
+            self.tls.connector()?
+        };
+
+        self.client_builder()?
+            .use_preconfigured_tls(connector)
+            .tls_info(self.tls.is_pinned())
+            .build()
+            .context("cannot build http client")
+    }
+
+    /// Builds the authentication of the account. The HTTP client is used by OAuth2 to request
+    /// tokens.
+    pub fn auth(&self, client: &Client) -> Result<Auth> {
+        match self.oauth2.as_ref() {
+            Some(config) => Ok(Auth::Bearer(Box::new(OAuth2TokenProvider::new(
+                config.clone(),
+                client.clone(),
+            )))),
+            None => {
+                let (login, passwd) = self.credentials()?;
+                Ok(match self.passwd_scheme {
+                    PasswdScheme::Auto => Auth::Digest(DigestAuth::negotiate(login, passwd)),
+                    PasswdScheme::Basic => Auth::Basic { login, passwd },
+                    PasswdScheme::Digest => Auth::Digest(DigestAuth::new(login, passwd)),
+                })
+            }
+        }
+    }
+
+    /// Builds the card repository of the account, using the given client.
+    pub fn repository<'a>(&self, client: &'a Client) -> Result<RemoteCardRepository<'a>> {
+        let auth = self.auth(client)?;
+        let repository =
+            RemoteCardRepository::undiscovered(&self.url, client, auth, self.retry.clone())
+                .with_pinned_fingerprints(self.tls.fingerprints())
+                .discover()
+                .with_context(|| {
+                    format!(r#"cannot find addressbook of account "{}""#, self.name)
+                })?;
+        Ok(repository)
+    }
+}
+
+/// Checks if the error comes from a server that cannot be reached.
+fn is_offline(err: &Error) -> bool {
+    err.chain()
+        .filter_map(|err| err.downcast_ref::<CardError>())
+        .any(CardError::is_offline)
+}
+
+impl Account {
+    /// Returns the name of the account.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Local(account) => &account.name,
+            Self::Remote(account) => &account.name,
+            Self::Sqlite(account) => &account.name,
+        }
+    }
+
+    /// Returns the cache directory of the account.
+    pub fn cache_dir(&self) -> Result<PathBuf> {
+        let mut path = Config::cache_dir()?;
+        path.push(self.name());
+        Ok(path)
+    }
+
+    /// Returns the region of the national phone numbers of the account.
+    pub fn default_region(&self) -> Option<Region> {
+        match self {
+            Self::Local(account) => account.default_region,
+            Self::Remote(account) => account.default_region,
+            Self::Sqlite(account) => account.default_region,
+        }
+    }
+
+    /// Checks if phone numbers are normalized when cards are created or updated.
+    pub fn normalize_tel(&self) -> bool {
+        match self {
+            Self::Local(account) => account.normalize_tel,
+            Self::Remote(account) => account.normalize_tel,
+            Self::Sqlite(account) => account.normalize_tel,
+        }
+    }
+
+    /// Runs the given action against the given repository, decorated according to the options
+    /// of the account.
+    fn with_decorators<T, F>(&self, repository: &dyn CardRepository, f: F) -> Result<T>
+    where
+        F: FnOnce(&dyn CardRepository) -> Result<T>,
+    {
+        if self.normalize_tel() {
+            f(&TelNormalizingCardRepository::new(
+                repository,
+                self.default_region(),
+            ))
+        } else {
+            f(repository)
+        }
+    }
+
+    /// Runs the given action against the remote repository of the account, behind the card
+    /// cache when enabled. The repository is missing when the server cannot be reached and the
+    /// account can work offline.
+    fn with_remote_repository<T, F>(&self, account: &RemoteAccount, f: F) -> Result<T>
+    where
+        F: FnOnce(Option<&dyn CardRepository>) -> Result<T>,
+    {
+        let client = account.client()?;
+        let repository = match account.repository(&client) {
+            Ok(repository) => Some(repository),
+            Err(err) if (account.cache || account.offline_queue) && is_offline(&err) => {
+                debug!("cannot discover addressbook: {:?}", err);
+                None
+            }
+            Err(err) => return Err(err),
+        };
+        let repository = repository
+            .as_ref()
+            .map(|repository| repository as &dyn CardRepository);
+
+        if account.cache {
+            let path = self.cache_dir()?.join(CARD_CACHE_FILE);
+            let cache = CachingCardRepository::new(repository, path);
+            f(Some(&cache))
+        } else {
+            f(repository)
+        }
+    }
+
+    /// Runs the given action against the write queue of the account.
+    pub fn with_write_queue<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&QueueingCardRepository) -> Result<T>,
+    {
+        match self {
+            Self::Remote(account) if account.offline_queue => {
+                let path = self.cache_dir()?.join(WRITE_QUEUE_FILE);
+                self.with_remote_repository(account, |repository| {
+                    f(&QueueingCardRepository::new(repository, path))
+                })
+            }
+            _ => Err(anyhow!(
+                r#"cannot access write queue of account "{}": offline-queue not enabled"#,
+                self.name()
+            )),
+        }
+    }
+
+    /// Runs the given action against the card repository of the account.
+    pub fn with_repository<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&dyn CardRepository) -> Result<T>,
+    {
+        self.with_repository_replaying(false, f)
+    }
+
+    /// Runs the given write action against the card repository of the account. The writes
+    /// queued by previous commands are replayed first, in case the server can be reached again.
+    pub fn with_writable_repository<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&dyn CardRepository) -> Result<T>,
+    {
+        self.with_repository_replaying(true, f)
+    }
+
+    fn with_repository_replaying<T, F>(&self, replay: bool, f: F) -> Result<T>
+    where
+        F: FnOnce(&dyn CardRepository) -> Result<T>,
+    {
+        // Writes make the phone number index outdated.
+        let index_path = self.cache_dir()?.join(TEL_INDEX_FILE);
+        let f = |repository: &dyn CardRepository| {
+            f(&TelIndexInvalidatingCardRepository::new(
+                repository, index_path,
+            ))
+        };
+
+        match self {
+            Self::Remote(account) if account.offline_queue => self.with_write_queue(|queue| {
+                if replay && !queue.pending()?.is_empty() {
+                    match queue.flush() {
+                        Ok(report) => {
+                            debug!("replayed {} queued write(s)", report.flushed.len());
+                            for conflict in report.conflicts {
+                                eprintln!(
+                                    r#"Warning: cannot replay {} of card "{}": {}"#,
+                                    conflict.write.op, conflict.write.card_id, conflict.reason
+                                );
+                            }
+                        }
+                        Err(err) => debug!("cannot replay queued writes: {:?}", err),
+                    }
+                }
+                self.with_decorators(queue, f)
+            }),
+            Self::Remote(account) => self.with_remote_repository(account, |repository| {
+                self.with_decorators(repository.ok_or(CardError::Offline)?, f)
+            }),
+            Self::Sqlite(account) => self.with_decorators(&account.repository()?, f),
+            Self::Local(account) => Err(anyhow!(
+                r#"cannot access cards of local account "{}": local repository not available yet"#,
+                account.name
+            )),
+        }
     }
 }
 
@@ -47,10 +439,7 @@ impl<'a> TryFrom<(&'a Config, Option<&str>)> for Account {
             Some("default") | Some("") | None => config
                 .accounts
                 .iter()
-                .find(|(_, entry)| match entry {
-                    ConfigAccountEntry::Local(entry) => entry.default.unwrap_or_default(),
-                    ConfigAccountEntry::Remote(entry) => entry.default.unwrap_or_default(),
-                })
+                .find(|(_, entry)| entry.is_default() || config.accounts.len() == 1)
                 .map(|(name, account)| (name.to_owned(), account))
                 .ok_or_else(|| anyhow!("cannot find default account")),
             Some(name) => config
@@ -60,30 +449,85 @@ impl<'a> TryFrom<(&'a Config, Option<&str>)> for Account {
                 .ok_or_else(|| anyhow!(r#"cannot find account "{}""#, name)),
         }?;
 
+        let default_region = entry
+            .default_region()
+            .map(parse_region)
+            .transpose()
+            .with_context(|| format!(r#"invalid account "{}""#, name))?;
+        let normalize_tel = entry.normalize_tel();
+
         let account = match entry {
             ConfigAccountEntry::Local(entry) => Account::Local(LocalAccount {
                 name,
                 path: entry.path.clone(),
+                default_region,
+                normalize_tel,
             }),
-            ConfigAccountEntry::Remote(entry) => Account::Remote(RemoteAccount {
-                name,
-                url: entry.url.clone(),
-                login: entry.login.clone(),
-                passwd_cmd: entry.passwd_cmd.clone(),
-            }),
+            ConfigAccountEntry::Sqlite(entry) => {
+                let path = shellexpand::full(&entry.path)
+                    .with_context(|| format!(r#"cannot expand path "{}""#, entry.path))?;
+                Account::Sqlite(SqliteAccount {
+                    name,
+                    path: PathBuf::from(path.as_ref()),
+                    default_region,
+                    normalize_tel,
+                })
+            }
+            ConfigAccountEntry::Remote(entry) => {
+                let (login, passwd_source, passwd_scheme, oauth2) = match entry.auth.as_ref() {
+                    Some(AuthConfig::Basic(auth)) => (
+                        auth.login.clone(),
+                        auth.passwd_source()?,
+                        PasswdScheme::Basic,
+                        None,
+                    ),
+                    Some(AuthConfig::Digest(auth)) => (
+                        auth.login.clone(),
+                        auth.passwd_source()?,
+                        PasswdScheme::Digest,
+                        None,
+                    ),
+                    Some(AuthConfig::OAuth2(auth)) => (
+                        String::new(),
+                        PasswdSource::default(),
+                        PasswdScheme::default(),
+                        Some(auth.clone()),
+                    ),
+                    None => (
+                        entry.login.clone(),
+                        PasswdSource::new(
+                            &entry.passwd_cmd,
+                            entry.passwd_keyring.as_deref(),
+                            entry.netrc,
+                        )?,
+                        PasswdScheme::Auto,
+                        None,
+                    ),
+                };
+                Account::Remote(RemoteAccount {
+                    name,
+                    url: entry.url.clone(),
+                    login,
+                    passwd_source,
+                    passwd_scheme,
+                    oauth2,
+                    tls: entry.tls.clone().unwrap_or_default(),
+                    proxy: entry.proxy.clone(),
+                    connect_timeout: entry.connect_timeout.map(Duration::from_secs),
+                    timeout: entry.timeout.map(Duration::from_secs),
+                    retry: entry
+                        .retry
+                        .as_ref()
+                        .map(RetryPolicy::from)
+                        .unwrap_or_default(),
+                    default_region,
+                    normalize_tel,
+                    cache: entry.cache.unwrap_or_default(),
+                    offline_queue: entry.offline_queue.unwrap_or_default(),
+                })
+            }
         };
         trace!("account: {:#?}", account);
         Ok(account)
     }
 }
-
-// TODO: move me
-pub fn run_cmd(cmd: &str) -> Result<String> {
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd").args(&["/C", cmd]).output()
-    } else {
-        Command::new("sh").arg("-c").arg(cmd).output()
-    }?;
-
-    Ok(String::from_utf8(output.stdout)?)
-}
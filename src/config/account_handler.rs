@@ -0,0 +1,331 @@
+//! Account handling module.
+//!
+//! This module gathers all account actions triggered by the CLI.
+
+use anyhow::{anyhow, Context, Result};
+use atty::Stream;
+use reqwest::Url;
+use serde_json::{json, Map, Value};
+use std::{
+    convert::TryFrom,
+    io::{self, BufRead},
+};
+
+use crate::{
+    config::{Account, Config, ConfigAccountEntry, Keyring, Netrc, PasswdScheme, PasswdSource},
+    output::OutputFmt,
+};
+
+/// Redacts the password of the given URL, if any.
+pub fn redact_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            parsed.set_password(Some("redacted")).ok();
+            parsed.to_string()
+        }
+        _ => url.to_owned(),
+    }
+}
+
+/// Lists the accounts of the config, with their type, default marker and location.
+pub fn list(config: &Config, output_fmt: OutputFmt) -> Result<()> {
+    let mut names: Vec<&String> = config.accounts.keys().collect();
+    names.sort();
+    let rows: Vec<(&str, &str, bool, String)> = names
+        .into_iter()
+        .map(|name| {
+            let entry = &config.accounts[name];
+            let (kind, location) = match entry {
+                ConfigAccountEntry::Local(entry) => ("local", entry.path.clone()),
+                ConfigAccountEntry::Remote(entry) => ("remote", redact_url(&entry.url)),
+                ConfigAccountEntry::Sqlite(entry) => ("sqlite", entry.path.clone()),
+            };
+            (name.as_str(), kind, entry.is_default(), location)
+        })
+        .collect();
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            let width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0).max(4);
+            println!("  {:width$}  {:6}  LOCATION", "NAME", "TYPE", width = width);
+            for (name, kind, default, location) in rows {
+                let marker = if default { "*" } else { " " };
+                println!(
+                    "{} {:width$}  {:6}  {}",
+                    marker,
+                    name,
+                    kind,
+                    location,
+                    width = width
+                );
+            }
+        }
+        OutputFmt::Json => {
+            let rows: Vec<Value> = rows
+                .into_iter()
+                .map(|(name, kind, default, location)| {
+                    json!({"name": name, "type": kind, "default": default, "location": location})
+                })
+                .collect();
+            println!("{}", Value::Array(rows));
+        }
+    }
+
+    Ok(())
+}
+
+/// Describes the resolved settings of the account, secrets redacted.
+pub fn describe(account: &Account) -> Vec<(&'static str, String)> {
+    let mut desc = match account {
+        Account::Local(account) => vec![
+            ("name", account.name.clone()),
+            ("type", "local".into()),
+            ("path", account.path.clone()),
+        ],
+        Account::Sqlite(account) => vec![
+            ("name", account.name.clone()),
+            ("type", "sqlite".into()),
+            ("path", account.path.to_string_lossy().into_owned()),
+        ],
+        Account::Remote(account) => {
+            let mut desc = vec![
+                ("name", account.name.clone()),
+                ("type", "remote".into()),
+                ("url", redact_url(&account.url)),
+            ];
+            match account.oauth2.as_ref() {
+                Some(oauth2) => desc.push((
+                    "auth",
+                    format!(
+                        "oauth2 (client {}, {:?} flow)",
+                        oauth2.client_id, oauth2.flow
+                    ),
+                )),
+                None => {
+                    let scheme = match account.passwd_scheme {
+                        PasswdScheme::Auto => "basic or digest, as asked by the server",
+                        PasswdScheme::Basic => "basic",
+                        PasswdScheme::Digest => "digest",
+                    };
+                    desc.push(("auth", scheme.into()));
+                    desc.push(("login", account.login.clone()));
+                    let passwd = match &account.passwd_source {
+                        PasswdSource::Cmd(cmd) => format!("redacted, from command `{}`", cmd),
+                        PasswdSource::Keyring(entry) => {
+                            format!(r#"redacted, from keyring entry "{}""#, entry)
+                        }
+                        PasswdSource::Netrc => "redacted, from netrc file".into(),
+                    };
+                    desc.push(("password", passwd));
+                }
+            }
+            if let Some(ca_file) = account.tls.ca_file.as_ref() {
+                desc.push(("tls-ca-file", ca_file.clone()));
+            }
+            if let Some(client_cert) = account.tls.client_cert.as_ref() {
+                desc.push(("tls-client-cert", client_cert.clone()));
+            }
+            if !account.tls.pinned_fingerprints.is_empty() {
+                desc.push((
+                    "tls-pinned-fingerprints",
+                    account.tls.pinned_fingerprints.join(", "),
+                ));
+            }
+            if account.tls.insecure.unwrap_or_default() {
+                desc.push(("tls-insecure", "true".into()));
+            }
+            if let Some(proxy) = account.proxy.as_deref() {
+                desc.push(("proxy", redact_url(proxy)));
+            }
+            if let Some(timeout) = account.connect_timeout {
+                desc.push(("connect-timeout", format!("{:?}", timeout)));
+            }
+            if let Some(timeout) = account.timeout {
+                desc.push(("timeout", format!("{:?}", timeout)));
+            }
+            desc.push((
+                "retry",
+                format!(
+                    "{} retries, from {:?} to {:?}",
+                    account.retry.max_retries, account.retry.initial_delay, account.retry.max_delay
+                ),
+            ));
+            if account.cache {
+                desc.push(("cache", "yes".into()));
+            }
+            if account.offline_queue {
+                desc.push(("offline-queue", "yes".into()));
+            }
+            desc
+        }
+    };
+    if let Some(region) = account.default_region() {
+        desc.push(("default-region", region.as_ref().to_owned()));
+    }
+    if account.normalize_tel() {
+        desc.push(("normalize-tel", "yes".into()));
+    }
+    desc
+}
+
+/// Shows the resolved settings of the given account, secrets redacted, plus the discovered
+/// addressbook URL of remote accounts.
+pub fn show(name: &str, config: &Config, output_fmt: OutputFmt) -> Result<()> {
+    let account = Account::try_from((config, Some(name)))?;
+    let is_default = config
+        .accounts
+        .get(name)
+        .map(ConfigAccountEntry::is_default)
+        .unwrap_or_default();
+
+    let mut desc = describe(&account);
+    desc.insert(1, ("default", is_default.to_string()));
+    if let Account::Remote(account) = &account {
+        let addressbook = account.client().and_then(|client| {
+            account
+                .repository(&client)
+                .map(|repo| repo.addressbook_path)
+        });
+        match addressbook {
+            Ok(addressbook) => desc.push(("addressbook", addressbook)),
+            Err(err) => desc.push(("addressbook", format!("error: {:#}", err))),
+        }
+    }
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            let width = desc.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+            for (key, val) in desc {
+                println!("{:width$}  {}", key, val, width = width);
+            }
+        }
+        OutputFmt::Json => {
+            let desc: Map<String, Value> = desc
+                .into_iter()
+                .map(|(key, val)| (key.to_owned(), Value::String(val)))
+                .collect();
+            println!("{}", Value::Object(desc));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a password from the standard input, without echoing it when it is a terminal.
+fn read_passwd() -> Result<String> {
+    let is_tty = atty::is(Stream::Stdin);
+    if is_tty {
+        eprint!("Password: ");
+    }
+
+    let _echo = if is_tty { EchoGuard::disable() } else { None };
+    let mut passwd = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut passwd)
+        .context("cannot read password")?;
+    if is_tty {
+        eprintln!();
+    }
+
+    Ok(passwd.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Disables the terminal echo until dropped.
+struct EchoGuard {
+    #[cfg(unix)]
+    termios: libc::termios,
+}
+
+impl EchoGuard {
+    #[cfg(unix)]
+    fn disable() -> Option<Self> {
+        // SAFETY: the termios struct is fully initialized by tcgetattr before being read.
+        unsafe {
+            let mut termios = std::mem::zeroed::<libc::termios>();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return None;
+            }
+            let mut noecho = termios;
+            noecho.c_lflag &= !libc::ECHO;
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &noecho);
+            Some(Self { termios })
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn disable() -> Option<Self> {
+        None
+    }
+}
+
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restores the attributes previously read by tcgetattr.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.termios);
+        }
+    }
+}
+
+/// Stores the password of the account in the given backend, or in the configured one.
+pub fn set_passwd(backend: Option<&str>, account: &Account) -> Result<()> {
+    let account = match account {
+        Account::Remote(account) => account,
+        account => {
+            return Err(anyhow!(
+                r#"cannot set password of account "{}": only remote accounts have one"#,
+                account.name()
+            ))
+        }
+    };
+
+    let source = match (backend, &account.passwd_source) {
+        (Some("keyring"), PasswdSource::Keyring(entry)) => PasswdSource::Keyring(entry.clone()),
+        (Some("keyring"), _) => PasswdSource::Keyring(account.name.clone()),
+        (Some("netrc"), _) => PasswdSource::Netrc,
+        (_, PasswdSource::Cmd(_)) => {
+            return Err(anyhow!(
+                r#"cannot set password of account "{}": passwd-cmd is read-only, use --backend"#,
+                account.name
+            ))
+        }
+        (_, source) => source.clone(),
+    };
+
+    let passwd = read_passwd()?;
+
+    match &source {
+        PasswdSource::Keyring(entry) => {
+            Keyring::new(entry).set_passwd(&passwd)?;
+            println!(r#"Password stored in keyring entry "{}""#, entry);
+        }
+        _ => {
+            let host = account.host()?;
+            let mut netrc = Netrc::read()?;
+            let login = match account.login.as_str() {
+                "" => netrc
+                    .find(&host)
+                    .and_then(|machine| machine.login.clone())
+                    .ok_or_else(|| anyhow!(r#"cannot find login of account "{}""#, account.name))?,
+                login => login.to_owned(),
+            };
+            netrc.set(&host, &login, &passwd)?;
+            netrc.write()?;
+            println!(r#"Password stored in netrc file for "{}""#, host);
+        }
+    }
+
+    if source != account.passwd_source {
+        match &source {
+            PasswdSource::Keyring(entry) => eprintln!(
+                r#"Add `passwd-keyring = "{}"` to the account config to use it."#,
+                entry
+            ),
+            _ => eprintln!("Add `netrc = true` to the account config to use it."),
+        }
+    }
+
+    Ok(())
+}
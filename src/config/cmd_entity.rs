@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::Deserialize;
+use std::{
+    fmt,
+    io::{Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Maximum duration of a command, long enough to type a passphrase in a pinentry prompt.
+pub const DEFAULT_CMD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Represents a command of the config, either a shell command line or a list of arguments run
+/// without shell.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Cmd {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+impl Default for Cmd {
+    fn default() -> Self {
+        Self::Shell(String::new())
+    }
+}
+
+impl From<&str> for Cmd {
+    fn from(cmd: &str) -> Self {
+        Self::Shell(cmd.to_owned())
+    }
+}
+
+impl From<String> for Cmd {
+    fn from(cmd: String) -> Self {
+        Self::Shell(cmd)
+    }
+}
+
+impl fmt::Display for Cmd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Shell(cmd) => write!(f, "{}", cmd),
+            Self::Argv(argv) => write!(f, "{}", argv.join(" ")),
+        }
+    }
+}
+
+impl fmt::Debug for Cmd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Shell(cmd) => write!(f, "{:?}", cmd),
+            Self::Argv(argv) => write!(f, "{:?}", argv),
+        }
+    }
+}
+
+impl Cmd {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Shell(cmd) => cmd.trim().is_empty(),
+            Self::Argv(argv) => argv.is_empty(),
+        }
+    }
+
+    fn command(&self) -> Result<Command> {
+        match self {
+            Self::Shell(cmd) if cfg!(target_os = "windows") => {
+                let mut command = Command::new("cmd");
+                command.args(["/C", cmd]);
+                Ok(command)
+            }
+            Self::Shell(cmd) => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(cmd);
+                Ok(command)
+            }
+            Self::Argv(argv) => {
+                let (program, args) = argv
+                    .split_first()
+                    .ok_or_else(|| anyhow!("cannot run empty cmd"))?;
+                let mut command = Command::new(program);
+                command.args(args);
+                Ok(command)
+            }
+        }
+    }
+
+    /// Runs the command and returns its standard output.
+    pub fn run(&self) -> Result<String> {
+        self.run_with_timeout(None, DEFAULT_CMD_TIMEOUT)
+    }
+
+    /// Runs the command, writing the given input to its standard input.
+    pub fn run_with_input(&self, input: &str) -> Result<String> {
+        self.run_with_timeout(Some(input), DEFAULT_CMD_TIMEOUT)
+    }
+
+    /// Runs the command and returns its standard output. Fails if the command exits with a
+    /// non-zero status, with its standard error as message, or if it does not exit before the
+    /// timeout. The output is never logged, since it usually holds a secret.
+    pub fn run_with_timeout(&self, input: Option<&str>, timeout: Duration) -> Result<String> {
+        debug!("run cmd {:?}", self);
+
+        let mut child = self
+            .command()?
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!(r#"cannot spawn cmd "{}""#, self))?;
+
+        // Pipes are drained in the background, so that a verbose command cannot block on a
+        // full pipe while we write its input or wait for it.
+        let stdout = child.stdout.take().map(drain);
+        let stderr = child.stderr.take().map(drain);
+
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+            stdin.write_all(input.as_bytes())?;
+        }
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(anyhow!(r#"cmd "{}" timed out after {:?}"#, self, timeout));
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let stdout = stdout.and_then(|t| t.join().ok()).unwrap_or_default();
+        let stderr = stderr.and_then(|t| t.join().ok()).unwrap_or_default();
+
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            return Err(anyhow!(
+                r#"cmd "{}" exited with {}: {}"#,
+                self,
+                status,
+                stderr.trim()
+            ));
+        }
+
+        String::from_utf8(stdout)
+            .with_context(|| format!(r#"cannot decode output of cmd "{}""#, self))
+    }
+}
+
+fn drain<R: Read + Send + 'static>(mut reader: R) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).ok();
+        buf
+    })
+}
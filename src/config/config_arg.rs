@@ -1,8 +1,50 @@
 //! Module related to config CLI.
 //!
-//! This module provides arguments related to config.
+//! This module provides arguments, subcommands and a command matcher related to config.
 
+use anyhow::Result;
 use clap::Arg;
+use log::debug;
+
+/// Represents the config commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd {
+    /// Represents the check config command.
+    Check,
+    /// Represents the init config command.
+    Init,
+}
+
+/// Defines the config command matcher.
+pub fn matches(m: &clap::ArgMatches) -> Result<Option<Cmd>> {
+    if let Some(m) = m.subcommand_matches("config") {
+        if m.subcommand_matches("check").is_some() {
+            debug!("check config subcommand matched");
+            return Ok(Some(Cmd::Check));
+        }
+        if m.subcommand_matches("init").is_some() {
+            debug!("init config subcommand matched");
+            return Ok(Some(Cmd::Init));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Contains config subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("config")
+        .about("Manages the config")
+        .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            clap::SubCommand::with_name("check")
+                .about("Checks the config and reports all its issues"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("init")
+                .about("Adds an account to the config, interactively"),
+        )]
+}
 
 /// Config arguments.
 pub fn args<'a>() -> Vec<Arg<'a, 'a>> {
@@ -1,8 +1,23 @@
-use anyhow::{Context, Error, Result};
-use log::{debug, trace};
-use serde::Deserialize;
-use std::{collections::HashMap, convert::TryFrom, env, fs, path::PathBuf};
-use toml;
+use anyhow::{anyhow, Context, Error, Result};
+use log::{debug, trace, warn};
+use reqwest::Url;
+use serde::{
+    de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use toml::Spanned;
+
+use crate::{
+    config::{Cmd, ConfigIssue, ConfigPosition, OAuth2Config, PasswdSource, TlsConfig},
+    domain::{card_repositories::RetryPolicy, parse_region},
+};
 
 /// Represents the config file of the user.
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -10,6 +25,9 @@ use toml;
 pub struct Config {
     #[serde(flatten)]
     pub accounts: ConfigAccountsMap,
+    /// Positions of the entries of the config file, by dotted key like `work.remote.url`.
+    #[serde(skip)]
+    pub positions: HashMap<String, ConfigPosition>,
 }
 
 /// Represents the accounts section of the config.
@@ -17,9 +35,192 @@ pub type ConfigAccountsMap = HashMap<String, ConfigAccountEntry>;
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(rename_all = "kebab-case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ConfigAccountEntry {
     Local(LocalConfigAccountEntry),
     Remote(RemoteConfigAccountEntry),
+    Sqlite(SqliteConfigAccountEntry),
+}
+
+/// Represents an account as written in the config file, holding the table of its kind. The
+/// TOML deserializer cannot read an enum from a table having sub-tables, like `[work.remote]`
+/// along with `[work.remote.tls]`.
+#[derive(Debug, Default, Deserialize)]
+struct RawConfigAccountEntry {
+    local: Option<LocalConfigAccountEntry>,
+    remote: Option<RemoteConfigAccountEntry>,
+    sqlite: Option<SqliteConfigAccountEntry>,
+}
+
+impl TryFrom<RawConfigAccountEntry> for ConfigAccountEntry {
+    type Error = String;
+
+    fn try_from(entry: RawConfigAccountEntry) -> Result<Self, Self::Error> {
+        match (entry.local, entry.remote, entry.sqlite) {
+            (Some(entry), None, None) => Ok(Self::Local(entry)),
+            (None, Some(entry), None) => Ok(Self::Remote(entry)),
+            (None, None, Some(entry)) => Ok(Self::Sqlite(entry)),
+            (None, None, None) => Err("expected a local, remote or sqlite table".into()),
+            _ => Err("expected only one of the local, remote and sqlite tables".into()),
+        }
+    }
+}
+
+/// Deserializes the entry of one account from the whole config file, skipping the other
+/// accounts, so that errors keep their position in the file. The keys ignored by the entry are
+/// collected, without the account kind.
+struct AccountSeed<'a> {
+    name: &'a str,
+    unknown_keys: &'a mut Vec<String>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for AccountSeed<'a> {
+    type Value = Option<RawConfigAccountEntry>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for AccountSeed<'a> {
+    type Value = Option<RawConfigAccountEntry>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a table of accounts")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entry = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key != self.name {
+                map.next_value::<IgnoredAny>()?;
+                continue;
+            }
+            let unknown_keys = &mut *self.unknown_keys;
+            entry = Some(
+                map.next_value_seed(IgnoredKeysSeed(|path: serde_ignored::Path| {
+                    // Strips the account kind, like in `remote.unknown-key`, and the optional
+                    // values shown as `?`.
+                    let path = path.to_string();
+                    let keys: Vec<_> = path.split('.').filter(|key| *key != "?").collect();
+                    unknown_keys.push(keys[keys.len().min(1)..].join("."));
+                }))?,
+            );
+        }
+        Ok(entry)
+    }
+}
+
+/// Deserializes an account entry, reporting its ignored keys to the callback.
+struct IgnoredKeysSeed<F>(F);
+
+impl<'de, F: FnMut(serde_ignored::Path)> DeserializeSeed<'de> for IgnoredKeysSeed<F> {
+    type Value = RawConfigAccountEntry;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        serde_ignored::deserialize(deserializer, self.0)
+    }
+}
+
+/// Represents the keys of a TOML table along with their span, recursively.
+#[derive(Debug, Default)]
+struct SpannedKeys(Vec<(Spanned<String>, SpannedKeys)>);
+
+impl<'de> Deserialize<'de> for SpannedKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SpannedKeysVisitor)
+    }
+}
+
+struct SpannedKeysVisitor;
+
+impl<'de> Visitor<'de> for SpannedKeysVisitor {
+    type Value = SpannedKeys;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any TOML value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = vec![];
+        while let Some(key) = map.next_key()? {
+            keys.push((key, map.next_value()?));
+        }
+        Ok(SpannedKeys(keys))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(SpannedKeys::default())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(SpannedKeys::default())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(SpannedKeys::default())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(SpannedKeys::default())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(SpannedKeys::default())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(SpannedKeys::default())
+    }
+}
+
+impl SpannedKeys {
+    /// Collects the position of each key, by dotted path.
+    fn positions(
+        &self,
+        content: &str,
+        prefix: &str,
+        positions: &mut HashMap<String, ConfigPosition>,
+    ) {
+        for (key, keys) in self.0.iter() {
+            let path = if prefix.is_empty() {
+                key.get_ref().to_owned()
+            } else {
+                format!("{}.{}", prefix, key.get_ref())
+            };
+            positions
+                .entry(path.clone())
+                .or_insert_with(|| position(content, key.start()));
+            keys.positions(content, &path, positions);
+        }
+    }
+}
+
+/// Converts a byte offset of the content to a position.
+fn position(content: &str, offset: usize) -> ConfigPosition {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+    ConfigPosition {
+        line: before.matches('\n').count() + 1,
+        col: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Splits a TOML error into its message and its position, which toml appends to the message
+/// along with the key, both reported separately.
+fn toml_error(err: toml::de::Error) -> (String, Option<ConfigPosition>) {
+    let pos = err.line_col().map(|(line, col)| ConfigPosition {
+        line: line + 1,
+        col: col + 1,
+    });
+    let msg = err.to_string();
+    let end = [" for key `", " at line "]
+        .iter()
+        .filter_map(|suffix| msg.find(suffix))
+        .min()
+        .unwrap_or(msg.len());
+    (msg[..end].to_owned(), pos)
 }
 
 /// Represents an account in the accounts section.
@@ -28,6 +229,23 @@ pub enum ConfigAccountEntry {
 pub struct LocalConfigAccountEntry {
     pub default: Option<bool>,
     pub path: String,
+    /// Region of the national phone numbers, like `FR`.
+    pub default_region: Option<String>,
+    /// Normalizes the phone numbers to E.164 when cards are created or updated.
+    pub normalize_tel: Option<bool>,
+}
+
+/// Represents an account in the accounts section.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SqliteConfigAccountEntry {
+    pub default: Option<bool>,
+    /// Path of the SQLite database file, created if missing.
+    pub path: String,
+    /// Region of the national phone numbers, like `FR`.
+    pub default_region: Option<String>,
+    /// Normalizes the phone numbers to E.164 when cards are created or updated.
+    pub normalize_tel: Option<bool>,
 }
 
 /// Represents an account in the accounts section.
@@ -37,11 +255,103 @@ pub struct RemoteConfigAccountEntry {
     pub default: Option<bool>,
 
     pub url: String,
+    #[serde(default)]
     pub login: String,
-    pub passwd_cmd: String,
+    /// Command returning the password, either a shell command line or a list of arguments.
+    #[serde(default)]
+    pub passwd_cmd: Cmd,
+    /// Keyring entry holding the password, replacing `passwd-cmd`.
+    pub passwd_keyring: Option<String>,
+    /// Reads the login and the password from the netrc file, replacing `passwd-cmd`.
+    pub netrc: Option<bool>,
+    /// Authentication section, taking precedence over the `login` and password entries. When
+    /// absent, basic or digest authentication is used depending on what the server asks for.
+    pub auth: Option<AuthConfig>,
+
+    pub tls: Option<TlsConfig>,
+
+    /// Proxy URL used for every request, like `http://proxy:3128` or `socks5://proxy:1080`.
+    pub proxy: Option<String>,
+    /// Connection timeout, in seconds.
+    pub connect_timeout: Option<u64>,
+    /// Request timeout, in seconds.
+    pub timeout: Option<u64>,
+    pub retry: Option<RetryConfig>,
+
+    /// Region of the national phone numbers, like `FR`.
+    pub default_region: Option<String>,
+    /// Normalizes the phone numbers to E.164 when cards are created or updated.
+    pub normalize_tel: Option<bool>,
+    /// Keeps a copy of the cards in the cache directory, used when the collection did not
+    /// change and when the server cannot be reached.
+    pub cache: Option<bool>,
+    /// Queues the writes the server cannot receive, to replay them later.
+    pub offline_queue: Option<bool>,
+}
+
+/// Represents the authentication section of a remote account.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AuthConfig {
+    Basic(PasswdAuthConfig),
+    Digest(PasswdAuthConfig),
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuth2Config),
+}
+
+/// Represents the basic or digest authentication section of a remote account.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PasswdAuthConfig {
+    #[serde(default)]
+    pub login: String,
+    #[serde(default)]
+    pub passwd_cmd: Cmd,
+    pub passwd_keyring: Option<String>,
+    pub netrc: Option<bool>,
+}
+
+impl PasswdAuthConfig {
+    pub fn passwd_source(&self) -> Result<PasswdSource> {
+        PasswdSource::new(&self.passwd_cmd, self.passwd_keyring.as_deref(), self.netrc)
+    }
+}
+
+/// Represents the retry policy of idempotent requests of a remote account.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RetryConfig {
+    pub max_retries: Option<u32>,
+    /// Delay before the first retry, in milliseconds.
+    pub initial_delay: Option<u64>,
+    /// Upper bound of the delay between two attempts, in milliseconds.
+    pub max_delay: Option<u64>,
+}
+
+/// Prefix of the env vars overriding account entries, like `CARDAMOM_ACCOUNT_<NAME>_URL`.
+pub const ACCOUNT_ENV_PREFIX: &str = "CARDAMOM_ACCOUNT_";
+
+/// Normalizes an account name the way it appears in env vars: uppercase, with non-alphanumeric
+/// chars replaced by underscores.
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
 }
 
 impl Config {
+    fn path_from_env() -> Result<PathBuf> {
+        let path = env::var("CARDAMOM_CONFIG")
+            .with_context(|| r#"cannot find "CARDAMOM_CONFIG" env var"#)?;
+        Ok(PathBuf::from(path))
+    }
+
     fn path_from_xdg() -> Result<PathBuf> {
         let path = env::var("XDG_CONFIG_HOME")
             .with_context(|| r#"cannot find "XDG_CONFIG_HOME" env var"#)?;
@@ -80,23 +390,382 @@ impl Config {
     }
 
     pub fn path() -> Result<PathBuf> {
-        let path = Self::path_from_xdg()
+        let path = Self::path_from_env()
+            .or_else(|_| Self::path_from_xdg())
             .or_else(|_| Self::path_from_xdg_alt())
             .or_else(|_| Self::path_from_home())
             .with_context(|| "cannot find config path")?;
         Ok(path)
     }
+
+    /// Returns the cache directory: `$XDG_CACHE_HOME/cardamom`, else `~/.cache/cardamom`.
+    pub fn cache_dir() -> Result<PathBuf> {
+        let mut path = match env::var_os("XDG_CACHE_HOME").filter(|path| !path.is_empty()) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let home_var = if cfg!(target_family = "windows") {
+                    "USERPROFILE"
+                } else {
+                    "HOME"
+                };
+                let mut path: PathBuf = env::var(home_var)
+                    .with_context(|| format!(r#"cannot find "{}" env var"#, home_var))?
+                    .into();
+                path.push(".cache");
+                path
+            }
+        };
+        path.push("cardamom");
+        Ok(path)
+    }
+
+    /// Applies the account env vars on top of the config: `CARDAMOM_ACCOUNT_<NAME>_URL`,
+    /// `_LOGIN` and `_PASSWD_CMD` for remote accounts, `_PATH` for local and SQLite ones.
+    /// `<NAME>` is the account name in uppercase, with non-alphanumeric chars replaced by
+    /// underscores. An unknown name defines a new remote account from its URL, which becomes
+    /// the default one if none is set.
+    pub fn merge_env<I: IntoIterator<Item = (String, String)>>(&mut self, vars: I) -> Result<()> {
+        let mut vars: Vec<(String, String, String)> = vars
+            .into_iter()
+            .filter_map(|(key, val)| {
+                let key = key.strip_prefix(ACCOUNT_ENV_PREFIX)?;
+                ["_PASSWD_CMD", "_LOGIN", "_URL", "_PATH"]
+                    .iter()
+                    .find_map(|field| {
+                        let name = key.strip_suffix(field)?;
+                        Some((name.to_owned(), field[1..].to_owned(), val.clone()))
+                    })
+                    .filter(|(name, _, _)| !name.is_empty())
+            })
+            .collect();
+        // Sorts vars so that new accounts are defined by their URL before other fields apply.
+        vars.sort_by_key(|(name, field, _)| (name.clone(), field != "URL"));
+
+        for (name, field, val) in vars {
+            debug!("apply env var {}{}_{}", ACCOUNT_ENV_PREFIX, name, field);
+            let account_name = self
+                .accounts
+                .keys()
+                .find(|account_name| env_name(account_name) == name)
+                .cloned();
+            let account_name = match (account_name, field.as_str()) {
+                (Some(account_name), _) => account_name,
+                (None, "URL") => {
+                    let account_name = name.to_lowercase();
+                    let has_default = self.accounts.values().any(|entry| entry.is_default());
+                    self.accounts.insert(
+                        account_name.clone(),
+                        ConfigAccountEntry::Remote(RemoteConfigAccountEntry {
+                            default: Some(!has_default),
+                            ..RemoteConfigAccountEntry::default()
+                        }),
+                    );
+                    account_name
+                }
+                (None, _) => {
+                    return Err(anyhow!(
+                        "cannot find account matching env var {}{}_{}",
+                        ACCOUNT_ENV_PREFIX,
+                        name,
+                        field
+                    ))
+                }
+            };
+
+            match (self.accounts.get_mut(&account_name), field.as_str()) {
+                (Some(ConfigAccountEntry::Local(entry)), "PATH") => entry.path = val,
+                (Some(ConfigAccountEntry::Sqlite(entry)), "PATH") => entry.path = val,
+                (Some(ConfigAccountEntry::Remote(entry)), "URL") => entry.url = val,
+                (Some(ConfigAccountEntry::Remote(entry)), "LOGIN") => match entry.auth.as_mut() {
+                    Some(AuthConfig::Basic(auth)) | Some(AuthConfig::Digest(auth)) => {
+                        auth.login = val
+                    }
+                    _ => entry.login = val,
+                },
+                (Some(ConfigAccountEntry::Remote(entry)), "PASSWD_CMD") => {
+                    match entry.auth.as_mut() {
+                        Some(AuthConfig::Basic(auth)) | Some(AuthConfig::Digest(auth)) => {
+                            auth.passwd_cmd = val.into();
+                            auth.passwd_keyring = None;
+                            auth.netrc = None;
+                        }
+                        _ => {
+                            entry.passwd_cmd = val.into();
+                            entry.passwd_keyring = None;
+                            entry.netrc = None;
+                        }
+                    }
+                }
+                _ => {
+                    return Err(anyhow!(
+                        r#"cannot override {} of account "{}" from env"#,
+                        field.to_lowercase().replace('_', "-"),
+                        account_name
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigAccountEntry {
+    /// Returns the kind of the account, naming its table in the config file.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Local(_) => "local",
+            Self::Remote(_) => "remote",
+            Self::Sqlite(_) => "sqlite",
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        match self {
+            Self::Local(entry) => entry.default.unwrap_or_default(),
+            Self::Remote(entry) => entry.default.unwrap_or_default(),
+            Self::Sqlite(entry) => entry.default.unwrap_or_default(),
+        }
+    }
+
+    pub fn default_region(&self) -> Option<&str> {
+        match self {
+            Self::Local(entry) => entry.default_region.as_deref(),
+            Self::Remote(entry) => entry.default_region.as_deref(),
+            Self::Sqlite(entry) => entry.default_region.as_deref(),
+        }
+    }
+
+    pub fn normalize_tel(&self) -> bool {
+        match self {
+            Self::Local(entry) => entry.normalize_tel.unwrap_or_default(),
+            Self::Remote(entry) => entry.normalize_tel.unwrap_or_default(),
+            Self::Sqlite(entry) => entry.normalize_tel.unwrap_or_default(),
+        }
+    }
+}
+
+impl Config {
+    /// Resolves the path of the config file: the given one, or the first one found by
+    /// [`Config::path`]. Returns `None` when no config file exists, in which case the config is
+    /// made of the env vars only.
+    pub fn resolve_path(path: Option<&str>) -> Option<PathBuf> {
+        match path {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Config::path()
+                .ok()
+                .filter(|path| path.exists() || env::var_os("CARDAMOM_CONFIG").is_some()),
+        }
+    }
+
+    /// Parses the content of a config file. Accounts that cannot be parsed are skipped and
+    /// reported as issues, like unknown keys. Only syntax errors prevent the parsing. Issues
+    /// carry the position of their entry in the file.
+    pub fn parse(content: &str) -> Result<(Self, Vec<ConfigIssue>), ConfigIssue> {
+        let table: toml::value::Table = toml::from_str(content).map_err(|err| {
+            let (msg, pos) = toml_error(err);
+            let pos = pos.unwrap_or(ConfigPosition { line: 1, col: 1 });
+            ConfigIssue::Syntax {
+                line: pos.line,
+                col: pos.col,
+                msg,
+            }
+        })?;
+
+        let mut config = Config::default();
+        // Dates cannot be read through spanned keys, leaving such files without positions.
+        if let Ok(keys) = toml::from_str::<SpannedKeys>(content) {
+            keys.positions(content, "", &mut config.positions);
+        }
+        let mut issues = vec![];
+
+        for (name, _) in table {
+            let account_pos = config.positions.get(&name).copied();
+            let mut unknown_keys = vec![];
+            let seed = AccountSeed {
+                name: &name,
+                unknown_keys: &mut unknown_keys,
+            };
+            let entry = seed
+                .deserialize(&mut toml::Deserializer::new(content))
+                .map_err(toml_error)
+                .and_then(|entry| {
+                    ConfigAccountEntry::try_from(entry.unwrap_or_default())
+                        .map_err(|msg| (msg, account_pos))
+                });
+            match entry {
+                Ok(entry) => {
+                    let kind = entry.kind();
+                    issues.extend(unknown_keys.into_iter().map(|key| ConfigIssue::UnknownKey {
+                        pos: config.position(&name, kind, &key),
+                        account: name.clone(),
+                        key,
+                    }));
+                    config.accounts.insert(name, entry);
+                }
+                Err((msg, pos)) => issues.push(ConfigIssue::InvalidAccount {
+                    account: name,
+                    msg,
+                    pos: pos.or(account_pos),
+                }),
+            }
+        }
+
+        Ok((config, issues))
+    }
+
+    /// Returns the position of an entry of an account, if written in the config file.
+    fn position(&self, name: &str, kind: &str, key: &str) -> Option<ConfigPosition> {
+        self.positions
+            .get(&format!("{}.{}.{}", name, kind, key))
+            .copied()
+    }
+
+    /// Checks the accounts: default account, URLs, paths and phone regions.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = vec![];
+        let mut names: Vec<&String> = self.accounts.keys().collect();
+        names.sort();
+
+        let defaults: Vec<String> = names
+            .iter()
+            .filter(|name| self.accounts[name.as_str()].is_default())
+            .map(|name| name.to_string())
+            .collect();
+        match (names.len(), defaults.len()) {
+            (0, _) => issues.push(ConfigIssue::NoAccount),
+            (1, _) | (_, 1) => (),
+            (_, 0) => issues.push(ConfigIssue::MissingDefault(
+                names.iter().map(|name| name.to_string()).collect(),
+            )),
+            _ => issues.push(ConfigIssue::DuplicateDefaults(defaults)),
+        }
+
+        for name in names {
+            if let Some(region) = self.accounts[name].default_region() {
+                if parse_region(region).is_err() {
+                    issues.push(ConfigIssue::InvalidRegion {
+                        account: name.to_owned(),
+                        region: region.to_owned(),
+                        pos: self.position(name, self.accounts[name].kind(), "default-region"),
+                    });
+                }
+            }
+
+            match &self.accounts[name] {
+                ConfigAccountEntry::Local(entry) => {
+                    let path = shellexpand::full(&entry.path)
+                        .map(|path| path.to_string())
+                        .unwrap_or_else(|_| entry.path.clone());
+                    if !Path::new(&path).is_dir() {
+                        issues.push(ConfigIssue::MissingPath {
+                            account: name.to_owned(),
+                            path: entry.path.clone(),
+                            pos: self.position(name, "local", "path"),
+                        });
+                    }
+                }
+                ConfigAccountEntry::Sqlite(entry) => {
+                    let path = shellexpand::full(&entry.path)
+                        .map(|path| path.to_string())
+                        .unwrap_or_else(|_| entry.path.clone());
+                    let dir = Path::new(&path)
+                        .parent()
+                        .filter(|dir| !dir.as_os_str().is_empty());
+                    if let Some(dir) = dir.filter(|dir| !dir.is_dir()) {
+                        issues.push(ConfigIssue::MissingPath {
+                            account: name.to_owned(),
+                            path: dir.to_string_lossy().into_owned(),
+                            pos: self.position(name, "sqlite", "path"),
+                        });
+                    }
+                }
+                ConfigAccountEntry::Remote(entry) => {
+                    let reason = match Url::parse(&entry.url) {
+                        Ok(url) if !["http", "https"].contains(&url.scheme()) => {
+                            Some(format!(r#"unsupported scheme "{}""#, url.scheme()))
+                        }
+                        Ok(url) if url.host_str().is_none() => Some("missing host".into()),
+                        Ok(_) => None,
+                        Err(err) => Some(err.to_string()),
+                    };
+                    if let Some(reason) = reason {
+                        issues.push(ConfigIssue::InvalidUrl {
+                            account: name.to_owned(),
+                            url: entry.url.clone(),
+                            reason,
+                            pos: self.position(name, "remote", "url"),
+                        });
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Reads the config file, applies the account env vars and validates the result. Returns
+    /// the config along with all its issues, even the blocking ones.
+    pub fn load(path: Option<&str>) -> Result<(Self, Vec<ConfigIssue>)> {
+        let (mut config, mut issues) = match Self::resolve_path(path) {
+            Some(path) => {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("cannot read config file {:?}", path))?;
+                Self::parse(&content).with_context(|| format!("invalid config file {:?}", path))?
+            }
+            None => {
+                debug!("no config file found, using env vars only");
+                (Config::default(), vec![])
+            }
+        };
+        config
+            .merge_env(env::vars())
+            .context("cannot apply env vars to config")?;
+        issues.extend(config.validate());
+        Ok((config, issues))
+    }
 }
 
 impl TryFrom<Option<&str>> for Config {
     type Error = Error;
 
+    /// Loads the config, failing on the first blocking issue. Warnings are logged.
     fn try_from(path: Option<&str>) -> Result<Self, Self::Error> {
         debug!("init config from `{:?}`", path);
-        let path = path.map(|s| s.into()).unwrap_or(Config::path()?);
-        let content = fs::read_to_string(path).context("cannot read config file")?;
-        let config = toml::from_str(&content).context("cannot parse config file")?;
+        let (config, issues) = Config::load(path)?;
+
+        for issue in issues.iter().filter(|issue| !issue.is_error()) {
+            warn!("{}", issue);
+        }
+        if let Some(issue) = issues.into_iter().find(ConfigIssue::is_error) {
+            let err = match issue {
+                ConfigIssue::NoAccount => anyhow!(
+                    "cannot find config file nor {}<NAME>_URL env var",
+                    ACCOUNT_ENV_PREFIX
+                ),
+                issue => Error::new(issue),
+            };
+            return Err(err.context("invalid config, run `cardamom config check` for details"));
+        }
+
         trace!("{:#?}", config);
         Ok(config)
     }
 }
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        let default = RetryPolicy::default();
+        Self {
+            max_retries: config.max_retries.unwrap_or(default.max_retries),
+            initial_delay: config
+                .initial_delay
+                .map(Duration::from_millis)
+                .unwrap_or(default.initial_delay),
+            max_delay: config
+                .max_delay
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_delay),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Config handling module.
+//!
+//! This module gathers all config actions triggered by the CLI.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+use std::{
+    fs,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Value};
+
+use crate::{
+    config::{Config, ConfigIssueSeverity, PasswdSource, RemoteAccount},
+    domain::card_repositories::RemoteCardRepository,
+    output::OutputFmt,
+};
+
+/// Checks the config and prints all its issues. Fails if at least one of them is an error.
+pub fn check(path: Option<&str>, output_fmt: OutputFmt) -> Result<()> {
+    let (_, issues) = Config::load(path)?;
+    let path = Config::resolve_path(path);
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            match path.as_ref() {
+                Some(path) => println!("Config file: {}", path.display()),
+                None => println!("Config file: none, using env vars only"),
+            }
+            for issue in &issues {
+                match issue.severity() {
+                    ConfigIssueSeverity::Error => println!("error: {}", issue),
+                    ConfigIssueSeverity::Warning => println!("warning: {}", issue),
+                }
+            }
+            if issues.is_empty() {
+                println!("No issue found");
+            }
+        }
+        OutputFmt::Json => {
+            let issues: Vec<_> = issues
+                .iter()
+                .map(|issue| {
+                    let mut json =
+                        json!({"severity": issue.severity(), "message": issue.to_string()});
+                    if let Some(pos) = issue.position() {
+                        json["line"] = pos.line.into();
+                        json["col"] = pos.col.into();
+                    }
+                    json
+                })
+                .collect();
+            println!("{}", json!({"path": path, "issues": issues}));
+        }
+    }
+
+    match errors {
+        0 => Ok(()),
+        1 => Err(anyhow!("config has 1 error")),
+        n => Err(anyhow!("config has {} errors", n)),
+    }
+}
+
+/// Asks a question, returning the trimmed answer or the default one when empty.
+fn ask<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    question: &str,
+    default: Option<&str>,
+) -> Result<String> {
+    loop {
+        match default {
+            Some(default) if !default.is_empty() => write!(output, "{} [{}]: ", question, default)?,
+            _ => write!(output, "{}: ", question)?,
+        }
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Err(anyhow!("cannot read answer: unexpected end of input"));
+        }
+        match (answer.trim(), default) {
+            ("", Some(default)) => return Ok(default.to_owned()),
+            ("", None) => continue,
+            (answer, _) => return Ok(answer.to_owned()),
+        }
+    }
+}
+
+/// Asks a yes/no question.
+fn confirm<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    question: &str,
+    default: bool,
+) -> Result<bool> {
+    let default_answer = if default { "Y/n" } else { "y/N" };
+    let answer = ask(input, output, question, Some(default_answer))?;
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Runs the addressbook discovery with the given settings, and prints the addressbooks found.
+fn discover<W: Write>(output: &mut W, account: &RemoteAccount) -> Result<()> {
+    let client = account.client()?;
+    let auth = account.auth(&client)?;
+    let repository =
+        RemoteCardRepository::undiscovered(&account.url, &client, auth, account.retry.clone())
+            .with_pinned_fingerprints(account.tls.fingerprints());
+    let paths = repository
+        .discover_addressbooks()
+        .context("cannot discover addressbooks")?;
+
+    writeln!(output, "Addressbooks found:")?;
+    for path in paths {
+        writeln!(output, "  - {}", path)?;
+    }
+    Ok(())
+}
+
+/// Asks for the settings of a new account, checks them and appends the account to the config
+/// file, created if needed.
+pub fn init<R: BufRead, W: Write>(path: Option<&str>, input: &mut R, output: &mut W) -> Result<()> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => Config::path()?,
+    };
+    let content = if path.exists() {
+        fs::read_to_string(&path).with_context(|| format!("cannot read config file {:?}", path))?
+    } else {
+        String::new()
+    };
+    let (config, _) =
+        Config::parse(&content).with_context(|| format!("invalid config file {:?}", path))?;
+    writeln!(output, "Adding an account to {}", path.display())?;
+
+    let name = loop {
+        let default = if config.accounts.is_empty() {
+            Some("default")
+        } else {
+            None
+        };
+        let name = ask(input, output, "Account name", default)?;
+        if !config.accounts.contains_key(&name) {
+            break name;
+        }
+        writeln!(output, r#"Account "{}" already exists."#, name)?;
+    };
+
+    let kind = loop {
+        match ask(
+            input,
+            output,
+            "Account type (local, remote)",
+            Some("remote"),
+        )?
+        .as_str()
+        {
+            kind @ ("local" | "remote") => break kind.to_owned(),
+            kind => writeln!(output, r#"Unknown account type "{}"."#, kind)?,
+        }
+    };
+
+    let mut entry = Table::new();
+    if config.accounts.is_empty() {
+        entry.insert("default".into(), Value::Boolean(true));
+    }
+
+    if kind == "local" {
+        let dir = ask(input, output, "Path of the vCard directory", None)?;
+        let expanded = shellexpand::full(&dir)
+            .map(|dir| dir.to_string())
+            .unwrap_or_else(|_| dir.clone());
+        if !Path::new(&expanded).is_dir()
+            && confirm(
+                input,
+                output,
+                "The directory does not exist, create it?",
+                true,
+            )?
+        {
+            fs::create_dir_all(&expanded)
+                .with_context(|| format!("cannot create directory {:?}", expanded))?;
+        }
+        entry.insert("path".into(), Value::String(dir));
+    } else {
+        let url = ask(input, output, "CardDAV server URL", None)?;
+        let login = ask(input, output, "Login", Some(""))?;
+        let passwd_cmd = ask(input, output, "Command returning the password", Some(""))?;
+
+        let account = RemoteAccount {
+            name: name.clone(),
+            url: url.clone(),
+            login: login.clone(),
+            passwd_source: PasswdSource::Cmd(passwd_cmd.as_str().into()),
+            ..RemoteAccount::default()
+        };
+        writeln!(output, "Discovering addressbooks…")?;
+        if let Err(err) = discover(output, &account) {
+            writeln!(output, "Error: {:#}", err)?;
+            if !confirm(input, output, "Save the account anyway?", false)? {
+                return Err(anyhow!("account not saved"));
+            }
+        }
+
+        entry.insert("url".into(), Value::String(url));
+        if !login.is_empty() {
+            entry.insert("login".into(), Value::String(login));
+        }
+        if !passwd_cmd.is_empty() {
+            entry.insert("passwd-cmd".into(), Value::String(passwd_cmd));
+        }
+    }
+
+    let mut account = Table::new();
+    account.insert(kind, Value::Table(entry));
+    let mut accounts = Table::new();
+    accounts.insert(name.clone(), Value::Table(account));
+    let section = toml::to_string(&accounts).context("cannot serialize account")?;
+
+    let content = match content.trim_end() {
+        "" => section,
+        content => format!("{}\n\n{}", content, section),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("cannot create directory {:?}", dir))?;
+    }
+    fs::write(&path, content).with_context(|| format!("cannot write config file {:?}", path))?;
+
+    writeln!(output, r#"Account "{}" saved to {}"#, name, path.display())?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Config issue module.
+//!
+//! This module gathers the issues found while loading the config, reported all at once by the
+//! `config check` command. Only errors prevent the config from loading, warnings are logged.
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Represents the position of an entry in the config file, starting at line 1, column 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConfigPosition {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Formats the position of an issue, if known, to follow its subject.
+fn at(pos: &Option<ConfigPosition>) -> String {
+    pos.map(|pos| format!(" at line {}, column {}", pos.line, pos.col))
+        .unwrap_or_default()
+}
+
+/// Represents an issue of the config.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigIssue {
+    #[error("cannot parse config file at line {line}, column {col}: {msg}")]
+    Syntax {
+        line: usize,
+        col: usize,
+        msg: String,
+    },
+    #[error(r#"cannot parse account "{account}"{}: {msg}"#, at(.pos))]
+    InvalidAccount {
+        account: String,
+        msg: String,
+        pos: Option<ConfigPosition>,
+    },
+    #[error("cannot find any account")]
+    NoAccount,
+    #[error("cannot find default account among {}", .0.join(", "))]
+    MissingDefault(Vec<String>),
+    #[error("cannot have several default accounts: {}", .0.join(", "))]
+    DuplicateDefaults(Vec<String>),
+    #[error(r#"unknown key "{key}" in account "{account}"{}"#, at(.pos))]
+    UnknownKey {
+        account: String,
+        key: String,
+        pos: Option<ConfigPosition>,
+    },
+    #[error(r#"invalid url "{url}" in account "{account}"{}: {reason}"#, at(.pos))]
+    InvalidUrl {
+        account: String,
+        url: String,
+        reason: String,
+        pos: Option<ConfigPosition>,
+    },
+    #[error(r#"invalid phone region "{region}" in account "{account}"{}: expected a country code like FR"#, at(.pos))]
+    InvalidRegion {
+        account: String,
+        region: String,
+        pos: Option<ConfigPosition>,
+    },
+    #[error(r#"cannot find directory "{path}" of account "{account}"{}"#, at(.pos))]
+    MissingPath {
+        account: String,
+        path: String,
+        pos: Option<ConfigPosition>,
+    },
+}
+
+/// Represents the severity of a config issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfigIssueSeverity {
+    Error,
+    Warning,
+}
+
+impl ConfigIssue {
+    pub fn severity(&self) -> ConfigIssueSeverity {
+        match self {
+            Self::UnknownKey { .. } | Self::MissingPath { .. } => ConfigIssueSeverity::Warning,
+            _ => ConfigIssueSeverity::Error,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity() == ConfigIssueSeverity::Error
+    }
+
+    /// Returns the position of the issue in the config file, if it refers to a single entry
+    /// written in the file.
+    pub fn position(&self) -> Option<ConfigPosition> {
+        match self {
+            Self::Syntax { line, col, .. } => Some(ConfigPosition {
+                line: *line,
+                col: *col,
+            }),
+            Self::InvalidAccount { pos, .. }
+            | Self::UnknownKey { pos, .. }
+            | Self::InvalidUrl { pos, .. }
+            | Self::InvalidRegion { pos, .. }
+            | Self::MissingPath { pos, .. } => *pos,
+            _ => None,
+        }
+    }
+}
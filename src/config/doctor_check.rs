@@ -0,0 +1,83 @@
+//! Doctor check module.
+//!
+//! This module gathers the results of the connectivity checks run by the `doctor` command, one
+//! per step of the discovery of a remote addressbook.
+
+use serde::Serialize;
+
+/// Represents the steps checked by the doctor, in order.
+pub const DOCTOR_STEPS: [&str; 9] = [
+    "dns",
+    "connect",
+    "tls",
+    "auth",
+    "current-user-principal",
+    "addressbook-home-set",
+    "addressbooks",
+    "dav-capabilities",
+    "sync-collection",
+];
+
+/// Represents the status of a doctor check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DoctorStatus {
+    Pass,
+    /// The step passed, but the account may not work as expected.
+    Warn,
+    Fail,
+    /// The step was not run because a previous one failed.
+    Skip,
+}
+
+/// Represents the result of a doctor check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoctorCheck {
+    pub step: &'static str,
+    pub status: DoctorStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    pub fn pass<D: ToString>(step: &'static str, detail: D) -> Self {
+        Self {
+            step,
+            status: DoctorStatus::Pass,
+            detail: detail.to_string(),
+            hint: None,
+        }
+    }
+
+    pub fn warn<D: ToString, H: ToString>(step: &'static str, detail: D, hint: H) -> Self {
+        Self {
+            step,
+            status: DoctorStatus::Warn,
+            detail: detail.to_string(),
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    pub fn fail<D: ToString, H: ToString>(step: &'static str, detail: D, hint: H) -> Self {
+        Self {
+            step,
+            status: DoctorStatus::Fail,
+            detail: detail.to_string(),
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    pub fn skip(step: &'static str) -> Self {
+        Self {
+            step,
+            status: DoctorStatus::Skip,
+            detail: "previous step failed".into(),
+            hint: None,
+        }
+    }
+
+    pub fn is_fail(&self) -> bool {
+        self.status == DoctorStatus::Fail
+    }
+}
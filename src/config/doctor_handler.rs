@@ -0,0 +1,420 @@
+//! Doctor handling module.
+//!
+//! This module gathers the connectivity checks of a remote account. Each step of the discovery
+//! is run on its own, so that a failure points to the faulty layer instead of a raw error.
+
+use anyhow::{anyhow, Error, Result};
+use log::debug;
+use reqwest::Url;
+use serde_json::json;
+use std::{
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    config::{Account, DoctorCheck, DoctorStatus, PasswdSource, RemoteAccount, DOCTOR_STEPS},
+    domain::{
+        card_repositories::{fingerprint, RemoteCardRepository},
+        CardError,
+    },
+    output::OutputFmt,
+};
+
+/// Connect timeout used when the account does not define one.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Formats an error with its causes.
+fn describe<E: Into<Error>>(err: E) -> String {
+    format!("{:#}", err.into())
+}
+
+/// Returns the hint matching a failure to get the credentials of the account.
+fn credentials_hint(account: &RemoteAccount) -> String {
+    if account.oauth2.is_some() {
+        return "check the oauth2 section of the account, and that the refresh token command works"
+            .into();
+    }
+    match &account.passwd_source {
+        PasswdSource::Cmd(cmd) if cmd.is_empty() => {
+            "set passwd-cmd, passwd-keyring or netrc in the account".into()
+        }
+        PasswdSource::Cmd(cmd) => format!("check that `{}` prints the password", cmd),
+        PasswdSource::Keyring(_) => {
+            "store the password with `cardamom account set-password`".into()
+        }
+        PasswdSource::Netrc => {
+            "store the password with `cardamom account set-password --backend netrc`".into()
+        }
+    }
+}
+
+/// Runs the checks until one fails, pushing their results.
+fn run(account: &RemoteAccount, checks: &mut Vec<DoctorCheck>) {
+    // DNS
+
+    let url = match Url::parse(&account.url) {
+        Ok(url) => url,
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "dns",
+                format!(r#"cannot parse url "{}": {}"#, account.url, err),
+                "fix the url of the account",
+            ));
+            return;
+        }
+    };
+    let target = match account.proxy.as_deref() {
+        Some(proxy) => match Url::parse(proxy) {
+            Ok(proxy) => proxy,
+            Err(err) => {
+                checks.push(DoctorCheck::fail(
+                    "dns",
+                    format!(r#"cannot parse proxy "{}": {}"#, proxy, err),
+                    "fix the proxy of the account",
+                ));
+                return;
+            }
+        },
+        None => url.clone(),
+    };
+    let host = target.host_str().unwrap_or_default().to_owned();
+    let port = target.port_or_known_default().unwrap_or(80);
+    debug!("resolve {}:{}", host, port);
+
+    let addrs: Vec<SocketAddr> = match (host.as_str(), port).to_socket_addrs() {
+        Ok(addrs) => addrs.collect(),
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "dns",
+                format!(r#"cannot resolve "{}": {}"#, host, err),
+                "check the host of the url, or your DNS settings",
+            ));
+            return;
+        }
+    };
+    let resolved: Vec<String> = addrs.iter().map(|addr| addr.ip().to_string()).collect();
+    checks.push(DoctorCheck::pass(
+        "dns",
+        format!("{} resolved to {}", host, resolved.join(", ")),
+    ));
+
+    // Connect
+
+    let timeout = account.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+    let connected = addrs
+        .iter()
+        .map(|addr| TcpStream::connect_timeout(addr, timeout).map(|_| addr))
+        .find_map(|res| res.ok());
+    match connected {
+        Some(addr) => checks.push(DoctorCheck::pass(
+            "connect",
+            format!("connected to {}", addr),
+        )),
+        None => {
+            checks.push(DoctorCheck::fail(
+                "connect",
+                format!("cannot connect to {}:{}", host, port),
+                format!(
+                    "check that the server is running and that no firewall blocks port {}",
+                    port
+                ),
+            ));
+            return;
+        }
+    }
+
+    // TLS
+
+    if url.scheme() != "https" {
+        checks.push(DoctorCheck::warn(
+            "tls",
+            "plain http, credentials are sent unencrypted",
+            "use an https url if the server supports it",
+        ));
+    } else if account.proxy.is_some() {
+        checks.push(DoctorCheck::pass(
+            "tls",
+            "not checked through the proxy, see the next steps",
+        ));
+    } else {
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or(443);
+        // A pinned certificate replaces the verification of its chain.
+        let connector = if account.tls.is_pinned() {
+            account.tls.probe_connector()
+        } else {
+            account.tls.connector()
+        };
+        let tls = connector.and_then(|connector| {
+            let stream = TcpStream::connect((host, port))?;
+            let stream = connector
+                .connect(host, stream)
+                .map_err(|err| anyhow!("cannot establish TLS connection: {}", err))?;
+            let cert = stream
+                .peer_certificate()?
+                .ok_or_else(|| anyhow!("cannot find server certificate"))?;
+            Ok(cert.to_der()?)
+        });
+        let cert = match tls {
+            Ok(cert) => cert,
+            Err(err) => {
+                checks.push(DoctorCheck::fail(
+                    "tls",
+                    describe(err),
+                    "add the CA of the server to tls.ca-file, or pin its certificate with tls.pinned-fingerprints",
+                ));
+                return;
+            }
+        };
+        if account.tls.is_pinned() {
+            if let Err(err) = account.tls.check_fingerprint(&cert) {
+                checks.push(DoctorCheck::fail(
+                    "tls",
+                    describe(err),
+                    "update tls.pinned-fingerprints if the certificate has been renewed",
+                ));
+                return;
+            }
+        }
+        let fingerprint = fingerprint(&cert);
+        checks.push(DoctorCheck::pass(
+            "tls",
+            format!("certificate sha256:{} accepted", fingerprint),
+        ));
+    }
+
+    // Auth
+
+    let client = match account.client() {
+        Ok(client) => client,
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "auth",
+                describe(err),
+                "check the tls, proxy and timeout options of the account",
+            ));
+            return;
+        }
+    };
+    let auth = match account.auth(&client) {
+        Ok(auth) => auth,
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "auth",
+                describe(err),
+                credentials_hint(account),
+            ));
+            return;
+        }
+    };
+    let repository =
+        RemoteCardRepository::undiscovered(&account.url, &client, auth, account.retry.clone())
+            .with_pinned_fingerprints(account.tls.fingerprints());
+
+    // Current user principal
+
+    let principal = repository.fetch_current_user_principal_path(String::from("/"));
+    match principal {
+        Err(CardError::Unauthorized) => {
+            checks.push(DoctorCheck::fail(
+                "auth",
+                "server rejected the credentials",
+                "check the login and the password, or the auth type expected by the server",
+            ));
+            return;
+        }
+        Err(err @ CardError::Auth(_)) => {
+            checks.push(DoctorCheck::fail(
+                "auth",
+                describe(err),
+                credentials_hint(account),
+            ));
+            return;
+        }
+        Err(CardError::Forbidden) => {
+            checks.push(DoctorCheck::fail(
+                "auth",
+                "server denied access to the url",
+                "check that the account is allowed to access the path of the url",
+            ));
+            return;
+        }
+        Ok(_) => checks.push(DoctorCheck::pass("auth", "credentials accepted")),
+        Err(_) => checks.push(DoctorCheck::pass("auth", "credentials not rejected")),
+    }
+    let principal = match principal {
+        Ok(path) => {
+            checks.push(DoctorCheck::pass(
+                "current-user-principal",
+                format!("found at {}", path),
+            ));
+            path
+        }
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "current-user-principal",
+                describe(err),
+                "check that the url points to the CardDAV server (RFC 5397)",
+            ));
+            return;
+        }
+    };
+
+    // Addressbook home set
+
+    let home = match repository.fetch_addressbook_home_set_path(principal) {
+        Ok(path) => {
+            checks.push(DoctorCheck::pass(
+                "addressbook-home-set",
+                format!("found at {}", path),
+            ));
+            path
+        }
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "addressbook-home-set",
+                describe(err),
+                "check that CardDAV is enabled for this user on the server (RFC 6352)",
+            ));
+            return;
+        }
+    };
+
+    // Addressbooks
+
+    let addressbook = match repository.fetch_addressbook_paths(&home) {
+        Ok(paths) if paths.is_empty() => {
+            checks.push(DoctorCheck::warn(
+                "addressbooks",
+                format!("no addressbook found, {} is used instead", home),
+                "create an addressbook on the server",
+            ));
+            home
+        }
+        Ok(paths) => {
+            checks.push(DoctorCheck::pass(
+                "addressbooks",
+                format!("found {}", paths.join(", ")),
+            ));
+            paths[0].clone()
+        }
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "addressbooks",
+                describe(err),
+                "check that the addressbook home set can be listed",
+            ));
+            return;
+        }
+    };
+
+    // DAV capabilities
+
+    match repository.fetch_dav_capabilities(&addressbook) {
+        Ok(classes) if classes.iter().any(|class| class == "addressbook") => checks.push(
+            DoctorCheck::pass("dav-capabilities", format!("DAV: {}", classes.join(", "))),
+        ),
+        Ok(classes) => {
+            checks.push(DoctorCheck::fail(
+                "dav-capabilities",
+                format!(
+                    "addressbook not advertised by {} (DAV: {})",
+                    addressbook,
+                    classes.join(", ")
+                ),
+                "check that the url points to a CardDAV server",
+            ));
+            return;
+        }
+        Err(err) => {
+            checks.push(DoctorCheck::fail(
+                "dav-capabilities",
+                describe(err),
+                "check that the server answers OPTIONS requests",
+            ));
+            return;
+        }
+    }
+
+    // Sync collection
+
+    match repository.fetch_supported_reports(&addressbook) {
+        Ok(reports) if reports.iter().any(|report| report == "sync-collection") => {
+            checks.push(DoctorCheck::pass("sync-collection", "supported"))
+        }
+        Ok(_) => checks.push(DoctorCheck::warn(
+            "sync-collection",
+            "not supported",
+            "changes are fetched by listing the whole addressbook",
+        )),
+        Err(err) => checks.push(DoctorCheck::warn(
+            "sync-collection",
+            describe(err),
+            "changes are fetched by listing the whole addressbook",
+        )),
+    }
+}
+
+/// Runs the connectivity checks of the account, step by step. Once a step fails, the next ones
+/// are skipped.
+pub fn diagnose(account: &RemoteAccount) -> Vec<DoctorCheck> {
+    let mut checks = Vec::with_capacity(DOCTOR_STEPS.len());
+    run(account, &mut checks);
+    for step in DOCTOR_STEPS.iter().skip(checks.len()) {
+        checks.push(DoctorCheck::skip(step));
+    }
+    checks
+}
+
+/// Diagnoses the connectivity of the account and prints a line per step.
+pub fn doctor(account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let account = match account {
+        Account::Remote(account) => account,
+        account => {
+            return Err(anyhow!(
+                r#"cannot diagnose account "{}": only remote accounts are checked"#,
+                account.name()
+            ))
+        }
+    };
+
+    let checks = diagnose(account);
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            println!("Account: {}", account.name);
+            let width = DOCTOR_STEPS
+                .iter()
+                .map(|step| step.len())
+                .max()
+                .unwrap_or(0);
+            for check in &checks {
+                let status = match check.status {
+                    DoctorStatus::Pass => "pass",
+                    DoctorStatus::Warn => "warn",
+                    DoctorStatus::Fail => "FAIL",
+                    DoctorStatus::Skip => "skip",
+                };
+                println!(
+                    "[{}] {:width$}  {}",
+                    status,
+                    check.step,
+                    check.detail,
+                    width = width
+                );
+                if let Some(hint) = check.hint.as_deref() {
+                    println!("       {:width$}  hint: {}", "", hint, width = width);
+                }
+            }
+        }
+        OutputFmt::Json => {
+            println!("{}", json!({"account": account.name, "checks": checks}));
+        }
+    }
+
+    match checks.iter().filter(|check| check.is_fail()).count() {
+        0 => Ok(()),
+        n => Err(anyhow!("{} check(s) failed", n)),
+    }
+}
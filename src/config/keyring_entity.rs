@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use secret_service::{blocking::SecretService, EncryptionType};
+use std::collections::HashMap;
+
+/// Value of the `service` attribute of the keyring items created by cardamom.
+pub const KEYRING_SERVICE: &str = "cardamom";
+
+/// Represents a password entry of the system keyring, accessed through the Secret Service API
+/// (GNOME Keyring, KWallet, KeePassXC…) over D-Bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyring {
+    pub entry: String,
+}
+
+impl Keyring {
+    pub fn new<S: ToString>(entry: S) -> Self {
+        Self {
+            entry: entry.to_string(),
+        }
+    }
+
+    fn attributes(&self) -> HashMap<&str, &str> {
+        HashMap::from([
+            ("service", KEYRING_SERVICE),
+            ("account", self.entry.as_str()),
+        ])
+    }
+
+    /// Connects to the Secret Service, preferring an encrypted session.
+    fn connect<'a>() -> Result<SecretService<'a>> {
+        SecretService::connect(EncryptionType::Dh)
+            .or_else(|err| {
+                debug!("cannot open encrypted session, trying plain one: {}", err);
+                SecretService::connect(EncryptionType::Plain)
+            })
+            .context("cannot connect to secret service")
+    }
+
+    /// Reads the password of the entry.
+    pub fn get_passwd(&self) -> Result<String> {
+        let ss = Self::connect()?;
+        let items = ss
+            .search_items(self.attributes())
+            .with_context(|| format!(r#"cannot search keyring entry "{}""#, self.entry))?;
+
+        let item = match (items.unlocked.first(), items.locked.first()) {
+            (Some(item), _) => item,
+            (None, Some(item)) => {
+                item.unlock()
+                    .with_context(|| format!(r#"cannot unlock keyring entry "{}""#, self.entry))?;
+                item
+            }
+            (None, None) => return Err(anyhow!(r#"cannot find keyring entry "{}""#, self.entry)),
+        };
+
+        let passwd = item
+            .get_secret()
+            .with_context(|| format!(r#"cannot read keyring entry "{}""#, self.entry))?;
+        String::from_utf8(passwd)
+            .with_context(|| format!(r#"cannot decode keyring entry "{}""#, self.entry))
+    }
+
+    /// Stores the password of the entry in the default collection, replacing the previous one.
+    pub fn set_passwd(&self, passwd: &str) -> Result<()> {
+        let ss = Self::connect()?;
+        let collection = ss
+            .get_default_collection()
+            .context("cannot find default keyring collection")?;
+        if collection.is_locked()? {
+            collection
+                .unlock()
+                .context("cannot unlock default keyring collection")?;
+        }
+
+        collection
+            .create_item(
+                &format!("{}: {}", KEYRING_SERVICE, self.entry),
+                self.attributes(),
+                passwd.as_bytes(),
+                true,
+                "text/plain",
+            )
+            .with_context(|| format!(r#"cannot store keyring entry "{}""#, self.entry))?;
+        Ok(())
+    }
+}
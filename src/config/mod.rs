@@ -1,9 +1,34 @@
 //! Module related to the user's configuration.
 
+pub mod account_arg;
+pub mod account_handler;
 pub mod config_arg;
+pub mod config_handler;
+pub mod doctor_handler;
 
 pub mod account_entity;
 pub use account_entity::*;
 
+pub mod cmd_entity;
+pub use cmd_entity::*;
+
+pub mod config_issue;
+pub use config_issue::*;
+
+pub mod doctor_check;
+pub use doctor_check::*;
+
 pub mod config_entity;
 pub use config_entity::*;
+
+pub mod keyring_entity;
+pub use keyring_entity::*;
+
+pub mod netrc_entity;
+pub use netrc_entity::*;
+
+pub mod tls_entity;
+pub use tls_entity::*;
+
+pub mod oauth2_entity;
+pub use oauth2_entity::*;
@@ -0,0 +1,330 @@
+use anyhow::{anyhow, Context, Error, Result};
+use std::{env, fmt, fs, ops::Range, path::PathBuf, str::FromStr};
+
+use crate::domain::write_private_file;
+
+/// Represents a machine entry of a netrc file. The `default` entry has no host.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NetrcMachine {
+    pub host: Option<String>,
+    pub login: Option<String>,
+    pub passwd: Option<String>,
+    pub account: Option<String>,
+}
+
+/// Represents a macro definition of a netrc file, kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetrcMacro {
+    pub name: String,
+    pub body: String,
+}
+
+/// Represents the location of a machine entry in the content of a netrc file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct MachineSpan {
+    /// Start of the `machine` or `default` token.
+    start: usize,
+    /// End of the last token of the entry.
+    end: usize,
+    login: Option<Range<usize>>,
+    passwd: Option<Range<usize>>,
+}
+
+/// Represents a netrc file, as read by ftp(1) and curl(1). The content is kept as is, so that
+/// setting credentials leaves comments, macros and unknown tokens untouched.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Netrc {
+    pub machines: Vec<NetrcMachine>,
+    pub macros: Vec<NetrcMacro>,
+    content: String,
+    spans: Vec<MachineSpan>,
+}
+
+impl Netrc {
+    /// Returns the path of the netrc file: the `NETRC` env var, or `~/.netrc` (`~/_netrc` on
+    /// Windows).
+    pub fn path() -> Result<PathBuf> {
+        if let Ok(path) = env::var("NETRC") {
+            return Ok(path.into());
+        }
+        let (home_var, file_name) = if cfg!(target_family = "windows") {
+            ("USERPROFILE", "_netrc")
+        } else {
+            ("HOME", ".netrc")
+        };
+        let mut path: PathBuf = env::var(home_var)
+            .with_context(|| format!(r#"cannot find "{}" env var"#, home_var))?
+            .into();
+        path.push(file_name);
+        Ok(path)
+    }
+
+    /// Reads the netrc file. A missing file is considered empty.
+    pub fn read() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        fs::read_to_string(&path)
+            .with_context(|| format!("cannot read netrc file {:?}", path))?
+            .parse()
+            .with_context(|| format!("cannot parse netrc file {:?}", path))
+    }
+
+    /// Writes the netrc file, readable by the user only, so that the credentials are never
+    /// exposed nor partially written.
+    pub fn write(&self) -> Result<()> {
+        let path = Self::path()?;
+        // Follows symlinks, so that a netrc file managed elsewhere is updated in place.
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        write_private_file(&path, self.content.as_bytes())
+            .with_context(|| format!("cannot write netrc file {:?}", path))
+    }
+
+    /// Finds the entry of the given host, falling back to the `default` entry.
+    pub fn find(&self, host: &str) -> Option<&NetrcMachine> {
+        self.machines
+            .iter()
+            .find(|machine| machine.host.as_deref() == Some(host))
+            .or_else(|| self.machines.iter().find(|machine| machine.host.is_none()))
+    }
+
+    /// Sets the credentials of the given host, adding an entry if needed. Only the login and
+    /// password tokens of the entry are changed, the rest of the content is kept as is.
+    pub fn set(&mut self, host: &str, login: &str, passwd: &str) -> Result<()> {
+        let mut edits: Vec<(Range<usize>, String)> = vec![];
+
+        match self
+            .machines
+            .iter()
+            .position(|machine| machine.host.as_deref() == Some(host))
+        {
+            Some(pos) => {
+                let span = &self.spans[pos];
+                for (range, key, value) in [
+                    (&span.login, "login", login),
+                    (&span.passwd, "password", passwd),
+                ] {
+                    match range {
+                        Some(range) => edits.push((range.clone(), quote(value))),
+                        None => {
+                            edits.push((span.end..span.end, format!(" {} {}", key, quote(value))))
+                        }
+                    }
+                }
+            }
+            None => {
+                let entry = format!(
+                    "machine {} login {} password {}\n",
+                    quote(host),
+                    quote(login),
+                    quote(passwd)
+                );
+                // The default entry must stay last, so new entries are inserted before it.
+                match self
+                    .machines
+                    .iter()
+                    .position(|machine| machine.host.is_none())
+                {
+                    Some(pos) => {
+                        let start = self.spans[pos].start;
+                        edits.push((start..start, entry));
+                    }
+                    None if self.content.is_empty() || self.content.ends_with('\n') => {
+                        let end = self.content.len();
+                        edits.push((end..end, entry));
+                    }
+                    None => {
+                        let end = self.content.len();
+                        edits.push((end..end, format!("\n{}", entry)));
+                    }
+                }
+            }
+        }
+
+        // Applies the edits from the end of the content, so that the ranges of the previous
+        // ones stay valid.
+        edits.sort_by_key(|(range, _)| range.start);
+        let mut content = self.content.clone();
+        for (range, text) in edits.into_iter().rev() {
+            content.replace_range(range, &text);
+        }
+        *self = content.parse()?;
+        Ok(())
+    }
+}
+
+/// Represents a token of a netrc file, with its location in the content.
+struct Token {
+    value: String,
+    span: Range<usize>,
+}
+
+/// Splits the content of a netrc file into tokens, handling quoted ones. Macro bodies are
+/// returned as a single token.
+fn tokenize(content: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = content.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            chars
+                .by_ref()
+                .take_while(|(_, c)| *c != '\n')
+                .for_each(drop);
+        } else if c == '"' {
+            chars.next();
+            let mut value = String::new();
+            let end = loop {
+                match chars.next() {
+                    Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                    Some((i, '"')) => break i + 1,
+                    Some((_, c)) => value.push(c),
+                    None => return Err(anyhow!("unterminated quoted token")),
+                }
+            };
+            tokens.push(Token {
+                value,
+                span: start..end,
+            });
+        } else {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let value = content[start..end].to_owned();
+            let is_macdef = value == "macdef";
+            tokens.push(Token {
+                value,
+                span: start..end,
+            });
+            if is_macdef {
+                let mut name = Token {
+                    value: String::new(),
+                    span: end..content.len(),
+                };
+                for (i, c) in chars.by_ref() {
+                    if c == '\n' {
+                        name.span.end = i;
+                        break;
+                    }
+                    name.value.push(c);
+                }
+                name.value = name.value.trim().to_owned();
+                // The macro body ends at the first empty line.
+                let mut body = Token {
+                    value: String::new(),
+                    span: name.span.end..content.len(),
+                };
+                let mut prev = '\n';
+                for (i, c) in chars.by_ref() {
+                    if c == '\n' && prev == '\n' {
+                        body.span.end = i;
+                        break;
+                    }
+                    body.value.push(c);
+                    prev = c;
+                }
+                tokens.push(name);
+                tokens.push(body);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+impl FromStr for Netrc {
+    type Err = Error;
+
+    fn from_str(content: &str) -> Result<Self> {
+        let mut netrc = Netrc {
+            content: content.to_owned(),
+            ..Netrc::default()
+        };
+        let mut tokens = tokenize(content)?.into_iter();
+
+        while let Some(token) = tokens.next() {
+            let mut value = |name: &str| {
+                tokens
+                    .next()
+                    .ok_or_else(|| anyhow!(r#"missing value after "{}""#, name))
+            };
+            match token.value.as_str() {
+                "machine" => {
+                    let host = value("machine")?;
+                    netrc.machines.push(NetrcMachine {
+                        host: Some(host.value),
+                        ..NetrcMachine::default()
+                    });
+                    netrc.spans.push(MachineSpan {
+                        start: token.span.start,
+                        end: host.span.end,
+                        ..MachineSpan::default()
+                    });
+                }
+                "default" => {
+                    netrc.machines.push(NetrcMachine::default());
+                    netrc.spans.push(MachineSpan {
+                        start: token.span.start,
+                        end: token.span.end,
+                        ..MachineSpan::default()
+                    });
+                }
+                "macdef" => {
+                    let name = value("macdef")?.value;
+                    let body = value("macdef")?.value;
+                    netrc.macros.push(NetrcMacro { name, body });
+                }
+                key @ ("login" | "password" | "account") => {
+                    let val = value(key)?;
+                    let (machine, span) = netrc
+                        .machines
+                        .last_mut()
+                        .zip(netrc.spans.last_mut())
+                        .ok_or_else(|| anyhow!(r#"unexpected "{}" outside of machine"#, key))?;
+                    span.end = val.span.end;
+                    match key {
+                        "login" => {
+                            machine.login = Some(val.value);
+                            span.login = Some(val.span);
+                        }
+                        "password" => {
+                            machine.passwd = Some(val.value);
+                            span.passwd = Some(val.span);
+                        }
+                        _ => machine.account = Some(val.value),
+                    }
+                }
+                // Tokens unknown to this parser, like the `port` of some clients, are skipped.
+                _ => (),
+            }
+        }
+
+        Ok(netrc)
+    }
+}
+
+/// Quotes a token if it is empty, contains whitespace or quotes, or starts like a comment.
+fn quote(token: &str) -> String {
+    if token.is_empty()
+        || token.starts_with('#')
+        || token.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\')
+    {
+        format!(r#""{}""#, token.replace('\\', r"\\").replace('"', r#"\""#))
+    } else {
+        token.to_owned()
+    }
+}
+
+impl fmt::Display for Netrc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.content)
+    }
+}
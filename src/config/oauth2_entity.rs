@@ -0,0 +1,449 @@
+use log::{debug, info, warn};
+use reqwest::{blocking::Client, Url};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::{
+    config::Cmd,
+    domain::{card_repositories::TokenProvider, CardError},
+};
+
+/// Represents the OAuth2 flow used to obtain the first refresh token.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OAuth2Flow {
+    /// Authorization code flow with PKCE, redirecting to a local listener (RFC 6749, RFC 7636).
+    #[default]
+    AuthorizationCode,
+    /// Device authorization flow, for machines without a browser (RFC 8628).
+    DeviceCode,
+}
+
+/// Represents the OAuth2 section of a remote account.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OAuth2Config {
+    pub client_id: String,
+    /// Command returning the client secret, for providers that require one.
+    pub client_secret_cmd: Option<Cmd>,
+    /// Authorization endpoint, used by the authorization code flow.
+    pub auth_url: Option<String>,
+    /// Device authorization endpoint, used by the device code flow.
+    pub device_auth_url: Option<String>,
+    pub token_url: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub flow: OAuth2Flow,
+    /// Port of the local redirect listener. A random port is used by default.
+    pub redirect_port: Option<u16>,
+    /// Time given to the user to authorize cardamom in the browser, in seconds.
+    pub redirect_timeout: Option<u64>,
+    /// Command returning the stored refresh token. An empty output triggers the flow.
+    pub refresh_token_cmd: Cmd,
+    /// Command storing a new refresh token, given on its standard input.
+    pub refresh_token_store_cmd: Option<Cmd>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceAuthResponse {
+    device_code: String,
+    user_code: String,
+    #[serde(alias = "verification_url")]
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    interval: Option<u64>,
+}
+
+#[derive(Debug)]
+struct AccessToken {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl AccessToken {
+    fn is_valid(&self) -> bool {
+        // Considers the token expired a bit earlier, to cover the request round trip.
+        self.expires_at
+            .map(|expires_at| Instant::now() + Duration::from_secs(30) < expires_at)
+            .unwrap_or(true)
+    }
+}
+
+/// Represents the result of a token request.
+enum TokenResult {
+    Token(TokenResponse),
+    Error(ErrorResponse),
+}
+
+fn auth_err(msg: impl ToString) -> CardError {
+    CardError::Auth(msg.to_string())
+}
+
+/// Time given to the user to authorize cardamom in the browser when none is configured.
+const DEFAULT_REDIRECT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Time given to a connection to send its request, so that a connection opened ahead by the
+/// browser and left idle does not hold the redirect listener.
+const REDIRECT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Waits for the browser to be redirected to the listener with the authorization response,
+/// until the timeout. Returns the connection along with the redirect URL. Other requests, like
+/// `/favicon.ico` or connections closed without any request, are answered with a 404 and
+/// ignored.
+fn accept_redirect(
+    listener: &TcpListener,
+    timeout: Duration,
+) -> Result<(TcpStream, Url), CardError> {
+    let deadline = Instant::now() + timeout;
+    listener.set_nonblocking(true)?;
+    loop {
+        if Instant::now() >= deadline {
+            return Err(auth_err(format!(
+                "authorization not received within {} seconds",
+                timeout.as_secs()
+            )));
+        }
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        stream.set_nonblocking(false)?;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        stream.set_read_timeout(Some(
+            remaining.clamp(Duration::from_secs(1), REDIRECT_REQUEST_TIMEOUT),
+        ))?;
+        let mut request_line = String::new();
+        if let Err(err) = BufReader::new(&stream).read_line(&mut request_line) {
+            debug!("ignore redirect listener connection: {}", err);
+            continue;
+        }
+
+        let redirect = request_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|path| Url::parse(&format!("http://127.0.0.1{}", path)).ok())
+            .filter(|url| {
+                url.path() == "/"
+                    && url
+                        .query_pairs()
+                        .any(|(key, _)| key == "code" || key == "error")
+            });
+        match redirect {
+            Some(redirect) => return Ok((stream, redirect)),
+            None => {
+                debug!("ignore redirect listener request {:?}", request_line.trim());
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+            }
+        }
+    }
+}
+
+/// Provides OAuth2 access tokens, refreshing them with the stored refresh token, or running the
+/// configured flow when no refresh token is available.
+pub struct OAuth2TokenProvider {
+    config: OAuth2Config,
+    client: Client,
+    access_token: Mutex<Option<AccessToken>>,
+}
+
+impl OAuth2TokenProvider {
+    pub fn new(config: OAuth2Config, client: Client) -> Self {
+        Self {
+            config,
+            client,
+            access_token: Mutex::new(None),
+        }
+    }
+
+    fn client_secret(&self) -> Result<Option<String>, CardError> {
+        self.config
+            .client_secret_cmd
+            .as_ref()
+            .map(|cmd| {
+                cmd.run()
+                    .map(|secret| secret.trim_end_matches(['\r', '\n']).to_owned())
+                    .map_err(|err| auth_err(format!("cannot run client secret cmd: {:#}", err)))
+            })
+            .transpose()
+    }
+
+    fn stored_refresh_token(&self) -> Option<String> {
+        match self.config.refresh_token_cmd.run() {
+            Ok(token) => Some(token.trim().to_owned()).filter(|token| !token.is_empty()),
+            Err(err) => {
+                warn!("cannot run refresh token cmd: {:#}", err);
+                None
+            }
+        }
+    }
+
+    fn store_refresh_token(&self, token: &str) -> Result<(), CardError> {
+        match self.config.refresh_token_store_cmd.as_ref() {
+            Some(cmd) => cmd
+                .run_with_input(token)
+                .map(|_| ())
+                .map_err(|err| auth_err(format!("cannot run refresh token store cmd: {:#}", err))),
+            None => {
+                warn!("no refresh token store cmd configured, the refresh token will be lost");
+                Ok(())
+            }
+        }
+    }
+
+    fn request_token(&self, params: &[(&str, &str)]) -> Result<TokenResult, CardError> {
+        let secret = self.client_secret()?;
+        let mut form = params.to_vec();
+        form.push(("client_id", &self.config.client_id));
+        if let Some(secret) = secret.as_deref() {
+            form.push(("client_secret", secret));
+        }
+
+        let res = self
+            .client
+            .post(&self.config.token_url)
+            .form(&form)
+            .send()?;
+        let status = res.status();
+        let body = res.text()?;
+
+        if status.is_success() {
+            let token = serde_json::from_str(&body)
+                .map_err(|err| CardError::parse("oauth2 token response", err))?;
+            Ok(TokenResult::Token(token))
+        } else {
+            let err = serde_json::from_str(&body).map_err(|_| CardError::ServerError {
+                status: status.as_u16(),
+                body,
+            })?;
+            Ok(TokenResult::Error(err))
+        }
+    }
+
+    /// Caches the access token and stores the new refresh token, if any.
+    fn save(&self, token: TokenResponse) -> Result<String, CardError> {
+        if let Some(refresh_token) = token.refresh_token.as_deref() {
+            self.store_refresh_token(refresh_token)?;
+        }
+
+        let access_token = AccessToken {
+            value: token.access_token.clone(),
+            expires_at: token
+                .expires_in
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+        };
+        if let Ok(mut cache) = self.access_token.lock() {
+            *cache = Some(access_token);
+        }
+
+        Ok(token.access_token)
+    }
+
+    fn authorization_code_flow(&self) -> Result<TokenResponse, CardError> {
+        let auth_url = self
+            .config
+            .auth_url
+            .as_deref()
+            .ok_or_else(|| auth_err("cannot find auth url for authorization code flow"))?;
+
+        let listener = TcpListener::bind(("127.0.0.1", self.config.redirect_port.unwrap_or(0)))?;
+        let redirect_uri = format!("http://127.0.0.1:{}/", listener.local_addr()?.port());
+        let state = Uuid::new_v4().to_simple().to_string();
+        let verifier = format!(
+            "{}{}",
+            Uuid::new_v4().to_simple(),
+            Uuid::new_v4().to_simple()
+        );
+        let challenge =
+            base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD);
+        let scope = self.config.scopes.join(" ");
+
+        let url = Url::parse_with_params(
+            auth_url,
+            &[
+                ("response_type", "code"),
+                ("client_id", &self.config.client_id),
+                ("redirect_uri", &redirect_uri),
+                ("scope", &scope),
+                ("state", &state),
+                ("code_challenge", &challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|err| CardError::parse("oauth2 auth url", err))?;
+
+        eprintln!("Open the following URL in your browser to authorize cardamom:");
+        eprintln!("{}", url);
+
+        let timeout = self
+            .config
+            .redirect_timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REDIRECT_TIMEOUT);
+        let (mut stream, redirect) = accept_redirect(&listener, timeout)?;
+        let param = |name: &str| {
+            redirect
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, val)| val.into_owned())
+        };
+
+        let body = "Cardamom has been authorized, you can close this page.";
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+
+        if let Some(err) = param("error") {
+            return Err(auth_err(format!("authorization denied: {}", err)));
+        }
+        if param("state").as_deref() != Some(state.as_str()) {
+            return Err(auth_err("authorization state mismatch"));
+        }
+        let code = param("code").ok_or_else(|| auth_err("cannot find authorization code"))?;
+
+        match self.request_token(&[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("code_verifier", &verifier),
+        ])? {
+            TokenResult::Token(token) => Ok(token),
+            TokenResult::Error(err) => Err(auth_err(format_oauth2_error(&err))),
+        }
+    }
+
+    fn device_code_flow(&self) -> Result<TokenResponse, CardError> {
+        let device_auth_url = self
+            .config
+            .device_auth_url
+            .as_deref()
+            .ok_or_else(|| auth_err("cannot find device auth url for device code flow"))?;
+
+        let scope = self.config.scopes.join(" ");
+        let res = self
+            .client
+            .post(device_auth_url)
+            .form(&[
+                ("client_id", self.config.client_id.as_str()),
+                ("scope", &scope),
+            ])
+            .send()?;
+        let status = res.status();
+        let body = res.text()?;
+        if !status.is_success() {
+            return Err(CardError::ServerError {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let device: DeviceAuthResponse = serde_json::from_str(&body)
+            .map_err(|err| CardError::parse("oauth2 device auth response", err))?;
+
+        match device.verification_uri_complete.as_deref() {
+            Some(uri) => eprintln!("Open {} to authorize cardamom.", uri),
+            None => eprintln!(
+                "Open {} and enter the code {} to authorize cardamom.",
+                device.verification_uri, device.user_code
+            ),
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+        let mut interval = Duration::from_secs(device.interval.unwrap_or(5));
+
+        while Instant::now() < deadline {
+            thread::sleep(interval);
+            match self.request_token(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ("device_code", &device.device_code),
+            ])? {
+                TokenResult::Token(token) => return Ok(token),
+                TokenResult::Error(err) if err.error == "authorization_pending" => {
+                    debug!("device authorization pending");
+                }
+                TokenResult::Error(err) if err.error == "slow_down" => {
+                    interval += Duration::from_secs(5);
+                }
+                TokenResult::Error(err) => return Err(auth_err(format_oauth2_error(&err))),
+            }
+        }
+
+        Err(auth_err("device code expired"))
+    }
+}
+
+fn format_oauth2_error(err: &ErrorResponse) -> String {
+    match err.error_description.as_deref() {
+        Some(desc) => format!("{}: {}", err.error, desc),
+        None => err.error.to_owned(),
+    }
+}
+
+impl TokenProvider for OAuth2TokenProvider {
+    fn token(&self) -> Result<String, CardError> {
+        if let Ok(cache) = self.access_token.lock() {
+            if let Some(token) = cache.as_ref().filter(|token| token.is_valid()) {
+                return Ok(token.value.to_owned());
+            }
+        }
+        self.refresh()
+    }
+
+    fn refresh(&self) -> Result<String, CardError> {
+        if let Some(refresh_token) = self.stored_refresh_token() {
+            debug!("refresh oauth2 access token");
+            match self.request_token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &refresh_token),
+            ])? {
+                TokenResult::Token(token) => return self.save(token),
+                TokenResult::Error(err) if err.error == "invalid_grant" => {
+                    warn!("refresh token rejected, authorization required");
+                }
+                TokenResult::Error(err) => return Err(auth_err(format_oauth2_error(&err))),
+            }
+        }
+
+        info!(
+            "no valid refresh token, starting oauth2 {:?} flow",
+            self.config.flow
+        );
+        let token = match self.config.flow {
+            OAuth2Flow::AuthorizationCode => self.authorization_code_flow()?,
+            OAuth2Flow::DeviceCode => self.device_code_flow()?,
+        };
+        self.save(token)
+    }
+}
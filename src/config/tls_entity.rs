@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Context, Result};
+use log::trace;
+use native_tls::{Certificate, Identity, TlsConnector, TlsConnectorBuilder};
+use serde::Deserialize;
+use std::fs;
+
+use crate::{config::Cmd, domain::card_repositories::fingerprint};
+
+/// Represents the TLS options of a remote account.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TlsConfig {
+    /// Path to a PEM bundle of additional trusted root certificates.
+    pub ca_file: Option<String>,
+    /// Path to the client certificate, either PKCS#12 (when no key is given) or PEM.
+    pub client_cert: Option<String>,
+    /// Path to the PEM PKCS#8 private key of the client certificate.
+    pub client_key: Option<String>,
+    /// Command returning the password of the PKCS#12 client certificate.
+    pub client_cert_passwd_cmd: Option<Cmd>,
+    /// SHA-256 fingerprints of the accepted server certificates.
+    #[serde(default)]
+    pub pinned_fingerprints: Vec<String>,
+    /// Disables the verification of the server certificate and hostname.
+    pub insecure: Option<bool>,
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>> {
+    let path = shellexpand::full(path).context("cannot expand path")?;
+    fs::read(path.as_ref()).with_context(|| format!(r#"cannot read file "{}""#, path))
+}
+
+/// Normalizes a fingerprint to lowercase hexadecimal, accepting an optional `sha256:` prefix and
+/// colon separators.
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    let fingerprint = fingerprint.trim();
+    let fingerprint = fingerprint
+        .strip_prefix("sha256:")
+        .or_else(|| fingerprint.strip_prefix("SHA256:"))
+        .unwrap_or(fingerprint);
+    fingerprint
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+impl TlsConfig {
+    pub fn is_insecure(&self) -> bool {
+        self.insecure.unwrap_or_default()
+    }
+
+    /// Checks if the server certificate is pinned.
+    pub fn is_pinned(&self) -> bool {
+        !self.pinned_fingerprints.is_empty()
+    }
+
+    /// Returns the pinned fingerprints, in lowercase hexadecimal.
+    pub fn fingerprints(&self) -> Vec<String> {
+        self.pinned_fingerprints
+            .iter()
+            .map(|fingerprint| normalize_fingerprint(fingerprint))
+            .collect()
+    }
+
+    fn identity(&self) -> Result<Option<Identity>> {
+        let cert_path = match self.client_cert.as_deref() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let cert = read_file(cert_path)?;
+
+        let identity = match self.client_key.as_deref() {
+            Some(key_path) => {
+                let key = read_file(key_path)?;
+                Identity::from_pkcs8(&cert, &key)
+                    .context("cannot parse PEM client certificate and key")?
+            }
+            None => {
+                let passwd = match self.client_cert_passwd_cmd.as_ref() {
+                    Some(cmd) => cmd
+                        .run()
+                        .context("cannot run client cert passwd cmd")?
+                        .trim_end_matches(['\r', '\n'])
+                        .to_owned(),
+                    None => String::new(),
+                };
+                Identity::from_pkcs12(&cert, &passwd)
+                    .context("cannot parse PKCS#12 client certificate")?
+            }
+        };
+
+        Ok(Some(identity))
+    }
+
+    /// Adds the certificates of the CA file, if any, to the roots of the builder.
+    fn add_ca_file(&self, builder: &mut TlsConnectorBuilder) -> Result<()> {
+        if let Some(path) = self.ca_file.as_deref() {
+            let pem = read_file(path)?;
+            let pem = String::from_utf8_lossy(&pem);
+            for cert in pem
+                .split_inclusive("-----END CERTIFICATE-----")
+                .filter(|cert| cert.contains("-----BEGIN CERTIFICATE-----"))
+            {
+                let cert = Certificate::from_pem(cert.trim().as_bytes())
+                    .with_context(|| format!(r#"cannot parse CA certificate from "{}""#, path))?;
+                builder.add_root_certificate(cert);
+            }
+        }
+        Ok(())
+    }
+
+    fn builder(&self) -> Result<TlsConnectorBuilder> {
+        let mut builder = TlsConnector::builder();
+        self.add_ca_file(&mut builder)?;
+
+        if let Some(identity) = self.identity()? {
+            builder.identity(identity);
+        }
+
+        if self.is_insecure() {
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+
+        Ok(builder)
+    }
+
+    /// Builds the TLS connector matching the options.
+    pub fn connector(&self) -> Result<TlsConnector> {
+        self.builder()?
+            .build()
+            .context("cannot build TLS connector")
+    }
+
+    /// Builds the TLS connector trusting only the given DER server certificate, once checked
+    /// against the pinned fingerprints, and the CA file. The system roots are left out so that
+    /// the handshake itself rejects any other certificate before a request is sent: a pinned
+    /// certificate issued by a CA then needs this CA in the CA file. The fingerprint of each
+    /// connection is still checked again by the card repository.
+    pub fn pinned_connector(&self, cert: &[u8]) -> Result<TlsConnector> {
+        self.check_fingerprint(cert)?;
+        let cert = Certificate::from_der(cert).context("cannot parse server certificate")?;
+        let mut builder = TlsConnector::builder();
+        builder
+            .disable_built_in_roots(true)
+            .add_root_certificate(cert);
+        self.add_ca_file(&mut builder)?;
+        if let Some(identity) = self.identity()? {
+            builder.identity(identity);
+        }
+        builder.build().context("cannot build TLS connector")
+    }
+
+    /// Builds the TLS connector accepting any server certificate, only used to fetch the
+    /// certificate of the server before checking it against the pinned fingerprints.
+    pub fn probe_connector(&self) -> Result<TlsConnector> {
+        self.builder()?
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()
+            .context("cannot build TLS connector")
+    }
+
+    /// Checks that the DER server certificate matches one of the pinned fingerprints.
+    pub fn check_fingerprint(&self, cert: &[u8]) -> Result<()> {
+        let fingerprint = fingerprint(cert);
+        trace!("server certificate fingerprint: {}", fingerprint);
+
+        if self.fingerprints().contains(&fingerprint) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                r#"server certificate fingerprint "sha256:{}" does not match any pinned fingerprint"#,
+                fingerprint
+            ))
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Birthday CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to birthdays and
+//! anniversaries.
+
+use anyhow::{Context, Result};
+use log::{debug, trace};
+
+type Days = i64;
+type AlarmDays = Option<u32>;
+
+/// Represents the birthday commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd {
+    /// Represents the list upcoming birthdays command.
+    List(Days),
+    /// Represents the export birthdays as iCalendar command.
+    Ics(AlarmDays),
+}
+
+/// Defines the birthday command matcher.
+pub fn matches(m: &clap::ArgMatches) -> Result<Option<Cmd>> {
+    if let Some(m) = m.subcommand_matches("birthdays") {
+        debug!("birthdays subcommand matched");
+        let days = m.value_of("days").unwrap_or("30");
+        let days = days
+            .parse()
+            .with_context(|| format!(r#"cannot parse days "{}""#, days))?;
+        trace!("days: {}", days);
+        return Ok(Some(Cmd::List(days)));
+    }
+
+    if let Some(m) = m.subcommand_matches("ics") {
+        debug!("ics subcommand matched");
+        let alarm = m
+            .value_of("alarm")
+            .map(|days| {
+                days.parse()
+                    .with_context(|| format!(r#"cannot parse alarm days "{}""#, days))
+            })
+            .transpose()?;
+        trace!("alarm: {:?}", alarm);
+        return Ok(Some(Cmd::Ics(alarm)));
+    }
+
+    Ok(None)
+}
+
+/// Contains birthday subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![
+        clap::SubCommand::with_name("birthdays")
+            .aliases(&["bdays"])
+            .about("Lists the upcoming birthdays and anniversaries")
+            .arg(days_arg()),
+        clap::SubCommand::with_name("ics")
+            .about("Exports the birthdays and anniversaries as an iCalendar feed")
+            .arg(alarm_arg()),
+    ]
+}
+
+/// Defines the days argument.
+pub fn days_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("days")
+        .long("days")
+        .short("d")
+        .help("Lists the events happening within this number of days")
+        .value_name("DAYS")
+        .default_value("30")
+}
+
+/// Defines the alarm argument.
+pub fn alarm_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("alarm")
+        .long("alarm")
+        .short("A")
+        .help("Adds a reminder this number of days before each event, 0 for the same day")
+        .value_name("DAYS")
+}
@@ -0,0 +1,170 @@
+//! Birthday module.
+//!
+//! This module extracts the birthdays and anniversaries of cards, from the `BDAY`,
+//! `ANNIVERSARY` and `X-ANNIVERSARY` properties, and computes their next occurrences.
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use crate::domain::{Card, VcardProp};
+
+/// Represents a date whose year may be unknown, like the vCard 4 `--0415`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PartialDate {
+    pub year: Option<i32>,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl PartialDate {
+    /// Parses a date or a date-time value, in basic (`19960415`, `--0415`) or extended
+    /// (`1996-04-15`, `--04-15`) format. Values with an unknown month or day, like `1996` or
+    /// `---15`, are rejected since they cannot be celebrated.
+    pub fn parse(value: &str) -> Option<Self> {
+        let date = value.trim().split('T').next()?;
+        let (year, month_day) = match date.strip_prefix("--") {
+            Some(month_day) => (None, month_day.replace('-', "")),
+            None => {
+                let date = date.replace('-', "");
+                if date.len() != 8 || !date.is_ascii() {
+                    return None;
+                }
+                let (year, month_day) = date.split_at(4);
+                (Some(year.parse().ok()?), month_day.to_owned())
+            }
+        };
+        if month_day.len() != 4 || !month_day.is_ascii() {
+            return None;
+        }
+        let month = month_day[..2].parse().ok()?;
+        let day = month_day[2..].parse().ok()?;
+
+        // Checks the date against a leap year when the year is unknown, to accept Feb 29.
+        NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day)?;
+        Some(Self { year, month, day })
+    }
+
+    /// Returns the occurrence of the date in the given year. Feb 29 falls on Feb 28 in common
+    /// years.
+    pub fn in_year(&self, year: i32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, self.month, self.day)
+            .unwrap_or_else(|| NaiveDate::from_ymd(year, self.month, self.day - 1))
+    }
+
+    /// Returns the first occurrence of the date on or after the given day.
+    pub fn next_occurrence(&self, today: NaiveDate) -> NaiveDate {
+        let date = self.in_year(today.year());
+        if date >= today {
+            date
+        } else {
+            self.in_year(today.year() + 1)
+        }
+    }
+}
+
+/// Represents the kind of a yearly event of a card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    Birthday,
+    Anniversary,
+}
+
+/// Represents a birthday or an anniversary of a card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CardEvent {
+    pub card_id: String,
+    pub name: String,
+    pub kind: EventKind,
+    pub date: PartialDate,
+}
+
+/// Represents an event occurring soon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpcomingEvent {
+    #[serde(flatten)]
+    pub event: CardEvent,
+    pub next: NaiveDate,
+    pub days: i64,
+    /// Years completed on the next occurrence, when the year is known.
+    pub years: Option<i32>,
+}
+
+/// Parses the date of a property, ignoring text values like `circa 1800` and the year
+/// macOS uses for dates without year.
+fn parse_prop_date(prop: &VcardProp) -> Option<PartialDate> {
+    if prop.has_param("VALUE", "text") {
+        return None;
+    }
+    let mut date = PartialDate::parse(&prop.value)?;
+    if let (Some(year), Some(omit)) = (date.year, prop.param("X-APPLE-OMIT-YEAR")) {
+        if omit.trim().parse() == Ok(year) {
+            date.year = None;
+        }
+    }
+    Some(date)
+}
+
+impl CardEvent {
+    /// Extracts the events of the card. `ANNIVERSARY` (vCard 4) takes precedence over
+    /// `X-ANNIVERSARY` (vCard 3).
+    pub fn from_card(card: &Card) -> Vec<Self> {
+        let props = card.props();
+        let find = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                props
+                    .iter()
+                    .filter(|prop| prop.name == *name)
+                    .find_map(parse_prop_date)
+            })
+        };
+        let name = card.full_name();
+
+        [
+            (EventKind::Birthday, find(&["BDAY"])),
+            (
+                EventKind::Anniversary,
+                find(&["ANNIVERSARY", "X-ANNIVERSARY"]),
+            ),
+        ]
+        .iter()
+        .filter_map(|(kind, date)| {
+            date.map(|date| Self {
+                card_id: card.id.clone(),
+                name: name.clone(),
+                kind: *kind,
+                date,
+            })
+        })
+        .collect()
+    }
+
+    /// Computes the next occurrence of the event, if it happens within the given number of
+    /// days.
+    pub fn upcoming(&self, today: NaiveDate, days: i64) -> Option<UpcomingEvent> {
+        let next = self.date.next_occurrence(today);
+        let delta = (next - today).num_days();
+        if delta > days {
+            return None;
+        }
+        Some(UpcomingEvent {
+            event: self.clone(),
+            next,
+            days: delta,
+            years: self.date.year.map(|year| next.year() - year),
+        })
+    }
+}
+
+/// Lists the events of the cards happening within the given number of days, soonest first.
+pub fn upcoming_events(cards: &[Card], today: NaiveDate, days: i64) -> Vec<UpcomingEvent> {
+    let mut events: Vec<UpcomingEvent> = cards
+        .iter()
+        .flat_map(CardEvent::from_card)
+        .filter_map(|event| event.upcoming(today, days))
+        .collect();
+    events.sort_by(|a, b| {
+        (a.next, &a.event.name, a.event.kind).cmp(&(b.next, &b.event.name, b.event.kind))
+    });
+    events
+}
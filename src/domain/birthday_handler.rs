@@ -0,0 +1,67 @@
+//! Birthday handling module.
+//!
+//! This module gathers birthday actions triggered by the CLI.
+
+use anyhow::Result;
+use chrono::{Local, Utc};
+
+use crate::{
+    config::Account,
+    domain::{to_ics, upcoming_events, CardEvent, EventKind},
+    output::OutputFmt,
+};
+
+/// Lists the birthdays and anniversaries happening within the given number of days.
+pub fn list(days: i64, account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let cards = account.with_repository(|repository| Ok(repository.read_all()?))?;
+    let events = upcoming_events(&cards, Local::today().naive_local(), days);
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            if events.is_empty() {
+                println!("No birthday nor anniversary in the next {} days", days);
+            }
+            let width = events
+                .iter()
+                .map(|event| event.event.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for event in events {
+                let when = match event.days {
+                    0 => "today".to_owned(),
+                    1 => "tomorrow".to_owned(),
+                    days => format!("in {} days", days),
+                };
+                let kind = match event.event.kind {
+                    EventKind::Birthday => "birthday",
+                    EventKind::Anniversary => "anniversary",
+                };
+                let years = match event.years {
+                    Some(years) => format!("  {} years", years),
+                    None => String::new(),
+                };
+                println!(
+                    "{}  {:11}  {:width$}  {}{}",
+                    event.next,
+                    when,
+                    event.event.name,
+                    kind,
+                    years,
+                    width = width
+                );
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&events)?),
+    }
+
+    Ok(())
+}
+
+/// Prints the birthdays and anniversaries as an iCalendar feed.
+pub fn ics(alarm: Option<u32>, account: &Account) -> Result<()> {
+    let cards = account.with_repository(|repository| Ok(repository.read_all()?))?;
+    let mut events: Vec<CardEvent> = cards.iter().flat_map(CardEvent::from_card).collect();
+    events.sort_by(|a, b| (&a.card_id, a.kind).cmp(&(&b.card_id, b.kind)));
+    print!("{}", to_ics(&events, alarm, Utc::now()));
+    Ok(())
+}
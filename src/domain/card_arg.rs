@@ -4,15 +4,29 @@
 //! domain.
 
 use anyhow::Result;
-use clap;
 use log::{debug, trace};
 
 type Id<'a> = &'a str;
 type RawCard<'a> = &'a str;
+type Query<'a> = Option<&'a str>;
+type Tag<'a> = Option<&'a str>;
+
+/// Represents the cards a bulk command operates on.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// Every card of the account.
+    All,
+    /// The card of the given id.
+    Id(&'a str),
+    /// Every card matching the given search query.
+    Search(&'a str),
+}
 
 /// Represents the card commands.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Cmd<'a> {
+    /// Represents the list cards command.
+    List(Query<'a>, Tag<'a>),
     /// Represents the create card command.
     Create(RawCard<'a>),
     /// Represents the read card command.
@@ -25,6 +39,15 @@ pub enum Cmd<'a> {
 
 /// Defines the card command matcher.
 pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    if let Some(m) = m.subcommand_matches("list") {
+        debug!("list subcommand matched");
+        let query = m.value_of("query");
+        trace!("query: {:?}", query);
+        let tag = m.value_of("tag");
+        trace!("tag: {:?}", tag);
+        return Ok(Some(Cmd::List(query, tag)));
+    }
+
     if let Some(m) = m.subcommand_matches("create") {
         debug!("create subcommand matched");
         let card = m.value_of("card").unwrap_or_default();
@@ -61,6 +84,21 @@ pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
 /// Contains card subcommands.
 pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
     vec![
+        clap::SubCommand::with_name("list")
+            .aliases(&["ls", "l"])
+            .about("Lists the cards, optionally matching a search query")
+            .arg(
+                clap::Arg::with_name("query")
+                    .help("Keeps the cards whose id, names, organization, emails or phone numbers contain this text")
+                    .value_name("QUERY"),
+            )
+            .arg(
+                clap::Arg::with_name("tag")
+                    .long("tag")
+                    .short("t")
+                    .help("Keeps the cards having this tag")
+                    .value_name("TAG"),
+            ),
         clap::SubCommand::with_name("create")
             .aliases(&["c"])
             .about("Creates a new card")
@@ -83,7 +121,10 @@ pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
 
 /// Defines the raw card argument.
 pub fn raw_card_arg<'a>() -> clap::Arg<'a, 'a> {
-    clap::Arg::with_name("card").raw(true).last(true)
+    clap::Arg::with_name("card")
+        .help("Specifies the card, in vCard format")
+        .raw(true)
+        .last(true)
 }
 
 /// Defines the card id argument.
@@ -93,3 +134,46 @@ pub fn id_arg<'a>() -> clap::Arg<'a, 'a> {
         .value_name("ID")
         .required(true)
 }
+
+/// Defines the arguments selecting the cards a bulk command operates on.
+pub fn target_args<'a>(required: bool) -> Vec<clap::Arg<'a, 'a>> {
+    vec![
+        clap::Arg::with_name("id")
+            .long("id")
+            .short("i")
+            .help(if required {
+                "Operates on the card of this id"
+            } else {
+                "Operates on the card of this id only"
+            })
+            .value_name("ID"),
+        clap::Arg::with_name("search")
+            .long("search")
+            .short("s")
+            .help(if required {
+                "Operates on every card matching this query"
+            } else {
+                "Operates on the cards matching this query only"
+            })
+            .value_name("QUERY"),
+    ]
+}
+
+/// Defines the group of arguments selecting the cards a bulk command operates on, which are
+/// mutually exclusive.
+pub fn target_group<'a>(required: bool) -> clap::ArgGroup<'a> {
+    clap::ArgGroup::with_name("target")
+        .args(&["id", "search"])
+        .required(required)
+}
+
+/// Matches the cards a bulk command operates on, every card by default.
+pub fn target<'a>(m: &'a clap::ArgMatches) -> Target<'a> {
+    let target = match (m.value_of("id"), m.value_of("search")) {
+        (Some(id), _) => Target::Id(id),
+        (None, Some(query)) => Target::Search(query),
+        (None, None) => Target::All,
+    };
+    trace!("target: {:?}", target);
+    target
+}
@@ -0,0 +1,59 @@
+//! Card error module.
+//!
+//! This module gathers the errors returned by the card repositories, so that callers can branch
+//! on the kind of failure.
+
+use std::{error, io};
+use thiserror::Error;
+
+/// Represents a boxed error, used as the source of parse errors.
+pub type BoxError = Box<dyn error::Error + Send + Sync>;
+
+/// Represents the card repository errors.
+#[derive(Debug, Error)]
+pub enum CardError {
+    #[error(r#"cannot find card "{0}""#)]
+    NotFound(String),
+    #[error(r#"cannot write card "{id}": it has been modified in the meantime"#)]
+    PreconditionFailed {
+        id: String,
+        current_etag: Option<String>,
+    },
+    #[error("cannot authenticate: invalid credentials")]
+    Unauthorized,
+    #[error("cannot authenticate: {0}")]
+    Auth(String),
+    #[error("cannot access resource: permission denied")]
+    Forbidden,
+    #[error("server replied with status {status}: {body}")]
+    ServerError { status: u16, body: String },
+    #[error(r#"cannot trust server certificate "sha256:{0}": no pinned fingerprint matches"#)]
+    UntrustedCertificate(String),
+    #[error("cannot send request")]
+    Network(#[from] reqwest::Error),
+    #[error("cannot reach server: working offline")]
+    Offline,
+    #[error("cannot parse {0}")]
+    Parse(String, #[source] BoxError),
+    #[error("cannot access file system")]
+    Io(#[from] io::Error),
+    #[error("cannot access database")]
+    Database(#[from] rusqlite::Error),
+}
+
+impl CardError {
+    /// Builds a parse error from a description of what could not be parsed and its cause.
+    pub fn parse<E: Into<BoxError>>(what: impl ToString, err: E) -> Self {
+        Self::Parse(what.to_string(), err.into())
+    }
+
+    /// Checks if the error means that the server cannot be reached, as opposed to the server
+    /// rejecting the request.
+    pub fn is_offline(&self) -> bool {
+        match self {
+            Self::Network(err) => err.is_connect() || err.is_timeout(),
+            Self::Offline => true,
+            _ => false,
+        }
+    }
+}
@@ -2,9 +2,101 @@
 //!
 //! This module gathers all card actions triggered by the CLI.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Serialize;
 
-use crate::config::Account;
+use crate::{
+    config::Account,
+    domain::{card_arg::Target, Card, CardFilter, CardRepository},
+    output::OutputFmt,
+};
+
+/// Represents a card in listings.
+#[derive(Debug, Serialize)]
+struct CardSummary {
+    id: String,
+    name: String,
+    tags: Vec<String>,
+}
+
+/// Reads the cards targeted by a command.
+pub fn select(repository: &dyn CardRepository, target: &Target) -> Result<Vec<Card>> {
+    Ok(match target {
+        Target::All => repository.read_all()?,
+        Target::Id(id) => vec![repository.read(id)?],
+        Target::Search(query) => {
+            let filter = CardFilter {
+                query: Some(query.to_string()),
+                tag: None,
+            };
+            repository
+                .search(&filter)?
+                .into_iter()
+                .filter(|card| filter.matches(card))
+                .collect()
+        }
+    })
+}
+
+/// Applies the edit to the targeted cards, then writes the changed ones back using their etag,
+/// so that concurrent modifications are not overwritten. Returns the number of cards changed.
+pub fn edit<F: FnMut(&mut Card) -> bool>(
+    target: &Target,
+    account: &Account,
+    mut f: F,
+) -> Result<usize> {
+    account.with_writable_repository(|repository| {
+        let mut updated = 0;
+        for mut card in select(repository, target)? {
+            if f(&mut card) {
+                repository.update(&mut card).with_context(|| {
+                    format!(
+                        r#"cannot update card "{}" ({} card(s) already updated)"#,
+                        card.id, updated
+                    )
+                })?;
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    })
+}
+
+/// Lists the cards matching the filter, sorted by name.
+pub fn list(filter: &CardFilter, account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let cards = account.with_repository(|repository| Ok(repository.search(filter)?))?;
+    let mut cards: Vec<CardSummary> = cards
+        .iter()
+        .filter(|card| filter.matches(card))
+        .map(|card| CardSummary {
+            id: card.id.clone(),
+            name: card.full_name(),
+            tags: card.tags(),
+        })
+        .collect();
+    cards.sort_by_key(|card| card.name.to_lowercase());
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            let width = cards
+                .iter()
+                .map(|card| card.id.chars().count())
+                .max()
+                .unwrap_or(0);
+            for card in cards {
+                let tags = if card.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!("  [{}]", card.tags.join(", "))
+                };
+                println!("{:width$}  {}{}", card.id, card.name, tags, width = width);
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&cards)?),
+    }
+
+    Ok(())
+}
 
 /// Creates a card.
 pub fn create(_raw_card: &str, _account: &Account) -> Result<()> {
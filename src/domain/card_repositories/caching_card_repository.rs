@@ -0,0 +1,252 @@
+//! Caching card repository module.
+//!
+//! This module provides a card repository decorator keeping a copy of the cards and of their
+//! etags on disk. Reads are served from the copy as long as the ctag of the collection is
+//! unchanged, and the copy is used as a fallback when the server cannot be reached.
+
+use chrono::{DateTime, Local, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crate::domain::{write_private_file, Card, CardError, CardRepository};
+
+/// File name of the card cache, in the cache directory of the account.
+pub const CARD_CACHE_FILE: &str = "cards.json";
+
+/// Represents a cached card, indexed by its id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedCard {
+    pub etag: Option<String>,
+    pub date: DateTime<Local>,
+    pub raw: String,
+}
+
+impl CachedCard {
+    fn to_card(&self, id: &str) -> Card {
+        Card {
+            id: id.to_owned(),
+            etag: self.etag.clone(),
+            date: self.date,
+            raw: self.raw.clone(),
+        }
+    }
+}
+
+impl From<&Card> for CachedCard {
+    fn from(card: &Card) -> Self {
+        Self {
+            etag: card.etag.clone(),
+            date: card.date,
+            raw: card.raw.clone(),
+        }
+    }
+}
+
+/// Represents the card cache of an account.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardCache {
+    /// Ctag of the collection when all its cards were last fetched.
+    pub ctag: Option<String>,
+    /// Date all the cards of the collection were last fetched.
+    pub synced_at: Option<DateTime<Utc>>,
+    pub cards: BTreeMap<String, CachedCard>,
+}
+
+impl CardCache {
+    /// Returns the cached cards, ordered by id.
+    pub fn cards(&self) -> Vec<Card> {
+        self.cards
+            .iter()
+            .map(|(id, card)| card.to_card(id))
+            .collect()
+    }
+}
+
+/// Card repository decorator caching the cards of the given repository in the given file. A
+/// missing repository means the server could not be reached at all: reads are then served from
+/// the cache and writes fail.
+pub struct CachingCardRepository<'a> {
+    pub repository: Option<&'a dyn CardRepository>,
+    pub path: PathBuf,
+}
+
+impl<'a> CachingCardRepository<'a> {
+    pub fn new(repository: Option<&'a dyn CardRepository>, path: PathBuf) -> Self {
+        Self { repository, path }
+    }
+
+    /// Reads the cache. A missing cache is empty, and so is a corrupted one.
+    pub fn load(&self) -> CardCache {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return CardCache::default(),
+            Err(err) => {
+                warn!("cannot read card cache {:?}: {}", self.path, err);
+                return CardCache::default();
+            }
+        };
+        serde_json::from_str(&content).unwrap_or_else(|err| {
+            warn!("cannot parse card cache {:?}: {}", self.path, err);
+            CardCache::default()
+        })
+    }
+
+    /// Writes the cache. Failures are only logged, since the cache can be rebuilt.
+    fn store(&self, cache: &CardCache) {
+        if let Err(err) = self.try_store(cache) {
+            warn!("cannot write card cache {:?}: {:?}", self.path, err);
+        }
+    }
+
+    fn try_store(&self, cache: &CardCache) -> Result<(), CardError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json =
+            serde_json::to_string(cache).map_err(|err| CardError::parse("card cache", err))?;
+        // The cards hold personal data, hidden from other users.
+        write_private_file(&self.path, json.as_bytes())?;
+        Ok(())
+    }
+
+    fn remote(&self) -> Result<&'a dyn CardRepository, CardError> {
+        self.repository.ok_or(CardError::Offline)
+    }
+
+    /// Falls back to the cached cards when the server cannot be reached, provided they were
+    /// fetched at least once.
+    fn stale_cards(&self, cache: CardCache, err: CardError) -> Result<Vec<Card>, CardError> {
+        match cache.synced_at {
+            Some(synced_at) => {
+                eprintln!(
+                    "Warning: cannot reach server, showing cards cached on {}",
+                    synced_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                );
+                Ok(cache.cards())
+            }
+            None => Err(err),
+        }
+    }
+
+    /// Falls back to the cached card when the server cannot be reached.
+    fn stale_card(&self, cache: &CardCache, id: &str, err: CardError) -> Result<Card, CardError> {
+        match cache.cards.get(id) {
+            Some(card) => {
+                eprintln!(
+                    r#"Warning: cannot reach server, showing cached card "{}""#,
+                    id
+                );
+                Ok(card.to_card(id))
+            }
+            None => Err(err),
+        }
+    }
+}
+
+impl<'a> CardRepository for CachingCardRepository<'a> {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        self.remote()?.create(card)?;
+        let mut cache = self.load();
+        cache
+            .cards
+            .insert(card.id.clone(), CachedCard::from(&*card));
+        self.store(&cache);
+        Ok(())
+    }
+
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        let mut cache = self.load();
+        let ctag = match self.remote().and_then(|repository| repository.ctag()) {
+            Ok(ctag) => ctag,
+            Err(err) if err.is_offline() => return self.stale_card(&cache, id, err),
+            Err(err) => return Err(err),
+        };
+        let cached = cache.cards.get(id);
+
+        if let Some(card) = cached.filter(|_| ctag.is_some() && ctag == cache.ctag) {
+            debug!(r#"ctag unchanged, read card "{}" from cache"#, id);
+            return Ok(card.to_card(id));
+        }
+
+        let res = match cached.and_then(|card| card.etag.as_deref()) {
+            Some(etag) => self.remote()?.read_if_none_match(id, etag),
+            None => self.remote()?.read(id).map(Some),
+        };
+        match res {
+            Ok(Some(card)) => {
+                cache.cards.insert(id.to_owned(), CachedCard::from(&card));
+                self.store(&cache);
+                Ok(card)
+            }
+            Ok(None) => {
+                debug!(r#"etag unchanged, read card "{}" from cache"#, id);
+                Ok(cache.cards[id].to_card(id))
+            }
+            Err(err @ CardError::NotFound(_)) => {
+                if cache.cards.remove(id).is_some() {
+                    self.store(&cache);
+                }
+                Err(err)
+            }
+            Err(err) if err.is_offline() => self.stale_card(&cache, id, err),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        let mut cache = self.load();
+        let ctag = match self.remote().and_then(|repository| repository.ctag()) {
+            Ok(ctag) => ctag,
+            Err(err) if err.is_offline() => return self.stale_cards(cache, err),
+            Err(err) => return Err(err),
+        };
+
+        if ctag.is_some() && ctag == cache.ctag {
+            debug!("ctag unchanged, read cards from cache");
+            return Ok(cache.cards());
+        }
+
+        let cards = match self.remote()?.read_all() {
+            Ok(cards) => cards,
+            Err(err) if err.is_offline() => return self.stale_cards(cache, err),
+            Err(err) => return Err(err),
+        };
+        cache.ctag = ctag;
+        cache.synced_at = Some(Utc::now());
+        cache.cards = cards
+            .iter()
+            .map(|card| (card.id.clone(), CachedCard::from(card)))
+            .collect();
+        self.store(&cache);
+        Ok(cards)
+    }
+
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        self.remote()?.update(card)?;
+        let mut cache = self.load();
+        cache
+            .cards
+            .insert(card.id.clone(), CachedCard::from(&*card));
+        self.store(&cache);
+        Ok(())
+    }
+
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        self.remote()?.delete(card)?;
+        let mut cache = self.load();
+        if cache.cards.remove(&card.id).is_some() {
+            self.store(&cache);
+        }
+        Ok(())
+    }
+
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        self.remote()?.ctag()
+    }
+
+    fn read_if_none_match(&self, id: &str, etag: &str) -> Result<Option<Card>, CardError> {
+        let card = self.read(id)?;
+        Ok(Some(card).filter(|card| card.etag.as_deref() != Some(etag)))
+    }
+}
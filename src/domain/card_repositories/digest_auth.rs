@@ -0,0 +1,327 @@
+//! Digest auth module.
+//!
+//! This module provides the HTTP Digest authentication scheme (RFC 7616), with the MD5 and
+//! SHA-256 algorithms (and their session variants) and the `auth` quality of protection.
+
+use md5::Md5;
+use reqwest::{
+    blocking::{Request, Response},
+    header::WWW_AUTHENTICATE,
+};
+use sha2::{Digest, Sha256};
+use std::{fmt, sync::Mutex};
+use uuid::Uuid;
+
+use crate::domain::CardError;
+
+/// Represents the hash algorithm of a digest challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    fn parse(algorithm: &str) -> Option<Self> {
+        match algorithm.to_ascii_uppercase().as_str() {
+            "MD5" => Some(Self::Md5),
+            "MD5-SESS" => Some(Self::Md5Sess),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-256-SESS" => Some(Self::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_sess(&self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    fn hash(&self, data: &str) -> String {
+        let digest = match self {
+            Self::Md5 | Self::Md5Sess => Md5::digest(data.as_bytes()).to_vec(),
+            Self::Sha256 | Self::Sha256Sess => Sha256::digest(data.as_bytes()).to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Represents a digest challenge sent by the server in a `WWW-Authenticate` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: DigestAlgorithm,
+    /// Whether the server supports the `auth` quality of protection.
+    pub qop_auth: bool,
+    pub stale: bool,
+}
+
+/// Splits the parameters of a challenge, like `realm="a, b", nonce="c"`, into key-value pairs.
+fn parse_params(params: &str) -> Vec<(String, String)> {
+    let mut pairs = vec![];
+    let mut chars = params.chars().peekable();
+
+    loop {
+        while matches!(chars.peek(), Some(c) if *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+        let key: String = chars
+            .by_ref()
+            .take_while(|c| *c != '=')
+            .collect::<String>()
+            .trim()
+            .to_ascii_lowercase();
+        if key.is_empty() {
+            break;
+        }
+
+        let mut val = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => val.extend(chars.next()),
+                    '"' => break,
+                    c => val.push(c),
+                }
+            }
+        } else {
+            val = chars.by_ref().take_while(|c| *c != ',').collect();
+        }
+        pairs.push((key, val.trim().to_owned()));
+    }
+
+    pairs
+}
+
+impl DigestChallenge {
+    /// Parses a `WWW-Authenticate` header value. Returns `None` if the header is not a digest
+    /// challenge or uses an unsupported algorithm.
+    pub fn parse(header: &str) -> Option<Self> {
+        let header = header.trim();
+        let (scheme, params) = header.split_at(header.find(' ').unwrap_or(header.len()));
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+
+        let params = parse_params(params);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, val)| val.to_owned())
+        };
+
+        Some(Self {
+            realm: param("realm").unwrap_or_default(),
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            algorithm: match param("algorithm") {
+                Some(algorithm) => DigestAlgorithm::parse(&algorithm)?,
+                None => DigestAlgorithm::Md5,
+            },
+            qop_auth: param("qop")
+                .map(|qop| qop.split(',').any(|qop| qop.trim() == "auth"))
+                .unwrap_or(false),
+            stale: param("stale")
+                .map(|stale| stale.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        })
+    }
+
+    /// Computes the digest response for the given credentials and request.
+    pub fn response(
+        &self,
+        login: &str,
+        passwd: &str,
+        method: &str,
+        uri: &str,
+        nc: u32,
+        cnonce: &str,
+    ) -> String {
+        let hash = |data: String| self.algorithm.hash(&data);
+
+        let mut ha1 = hash(format!("{}:{}:{}", login, self.realm, passwd));
+        if self.algorithm.is_sess() {
+            ha1 = hash(format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = hash(format!("{}:{}", method, uri));
+
+        if self.qop_auth {
+            hash(format!(
+                "{}:{}:{:08x}:{}:auth:{}",
+                ha1, self.nonce, nc, cnonce, ha2
+            ))
+        } else {
+            hash(format!("{}:{}:{}", ha1, self.nonce, ha2))
+        }
+    }
+
+    /// Builds the `Authorization` header value for the given credentials and request.
+    pub fn authorization(
+        &self,
+        login: &str,
+        passwd: &str,
+        method: &str,
+        uri: &str,
+        nc: u32,
+        cnonce: &str,
+    ) -> String {
+        let response = self.response(login, passwd, method, uri, nc, cnonce);
+        let mut header = format!(
+            r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, response="{}""#,
+            login,
+            self.realm,
+            self.nonce,
+            uri,
+            self.algorithm.name(),
+            response
+        );
+        if self.qop_auth {
+            header.push_str(&format!(
+                r#", qop=auth, nc={:08x}, cnonce="{}""#,
+                nc, cnonce
+            ));
+        }
+        if let Some(opaque) = self.opaque.as_deref() {
+            header.push_str(&format!(r#", opaque="{}""#, opaque));
+        }
+        header
+    }
+}
+
+#[derive(Debug)]
+enum DigestState {
+    /// The server asked for basic credentials, only when negotiating.
+    Basic,
+    Digest {
+        challenge: DigestChallenge,
+        nc: u32,
+    },
+}
+
+/// Represents the digest authentication of remote requests. The nonce of the last challenge is
+/// reused across requests, until the server rejects it.
+pub struct DigestAuth {
+    pub login: String,
+    pub passwd: String,
+    /// Accepts a basic challenge as well as a digest one.
+    pub negotiate: bool,
+    state: Mutex<Option<DigestState>>,
+}
+
+impl fmt::Debug for DigestAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DigestAuth")
+            .field("login", &self.login)
+            .field("passwd", &"<redacted>")
+            .field("negotiate", &self.negotiate)
+            .finish()
+    }
+}
+
+impl DigestAuth {
+    /// Creates a digest authentication. The first request is sent without credentials, in order
+    /// to receive a challenge.
+    pub fn new(login: String, passwd: String) -> Self {
+        Self {
+            login,
+            passwd,
+            negotiate: false,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Creates an authentication using the scheme asked by the server. The first request is sent
+    /// without credentials, then basic or digest ones are sent depending on the challenge.
+    pub fn negotiate(login: String, passwd: String) -> Self {
+        Self {
+            negotiate: true,
+            ..Self::new(login, passwd)
+        }
+    }
+
+    /// Builds the authorization header of the given request, if any.
+    pub fn authorization(&self, req: &Request) -> Option<String> {
+        let mut state = self.state.lock().ok()?;
+        match state.as_mut()? {
+            DigestState::Basic => Some(format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", self.login, self.passwd))
+            )),
+            DigestState::Digest { challenge, nc } => {
+                *nc += 1;
+                let url = req.url();
+                let uri = match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_owned(),
+                };
+                let cnonce = Uuid::new_v4().to_simple().to_string();
+                Some(challenge.authorization(
+                    &self.login,
+                    &self.passwd,
+                    req.method().as_str(),
+                    &uri,
+                    *nc,
+                    &cnonce,
+                ))
+            }
+        }
+    }
+
+    /// Stores the challenge of a `401 Unauthorized` response, a basic one being only accepted
+    /// when negotiating. Returns `true` if the request should be sent again, which is not the
+    /// case when the server rejected the basic credentials or a fresh nonce (meaning that the
+    /// credentials are invalid).
+    pub fn challenge(&self, res: &Response) -> Result<bool, CardError> {
+        let headers: Vec<_> = res
+            .headers()
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .collect();
+
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| CardError::Auth("cannot lock digest state".into()))?;
+
+        let challenge = match headers.iter().copied().find_map(DigestChallenge::parse) {
+            Some(challenge) => challenge,
+            None => {
+                let basic = headers.iter().any(|header| {
+                    header
+                        .split_whitespace()
+                        .next()
+                        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("basic"))
+                });
+                if !self.negotiate || !basic || state.is_some() {
+                    return Ok(false);
+                }
+                *state = Some(DigestState::Basic);
+                return Ok(true);
+            }
+        };
+
+        let retry = match state.as_ref() {
+            Some(DigestState::Digest {
+                challenge: prev, ..
+            }) => challenge.stale || prev.nonce != challenge.nonce,
+            _ => true,
+        };
+        *state = Some(DigestState::Digest { challenge, nc: 0 });
+        Ok(retry)
+    }
+}
@@ -1,27 +1,25 @@
-use anyhow::Result;
-
-use crate::domain::{Card, CardRepository};
+use crate::domain::{Card, CardError, CardRepository};
 
 pub struct LocalCardRepository;
 
 impl CardRepository for LocalCardRepository {
-    fn create(&self, _card: &mut Card) -> Result<()> {
+    fn create(&self, _card: &mut Card) -> Result<(), CardError> {
         todo!();
     }
 
-    fn read(&self, _id: &str) -> Result<Card> {
+    fn read(&self, _id: &str) -> Result<Card, CardError> {
         todo!()
     }
 
-    fn read_all(&self) -> Result<Vec<Card>> {
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
         todo!()
     }
 
-    fn update(&self, _card: &mut Card) -> Result<()> {
+    fn update(&self, _card: &mut Card) -> Result<(), CardError> {
         todo!()
     }
 
-    fn delete(&self, _card: &Card) -> Result<()> {
+    fn delete(&self, _card: &Card) -> Result<(), CardError> {
         todo!()
     }
 }
@@ -0,0 +1,340 @@
+//! Multistatus module.
+//!
+//! This module provides a namespace-aware parser for WebDAV `207 Multi-Status` bodies (RFC 4918).
+//! Element names are resolved against their namespace, so `<D:getetag>`, `<d:getetag>` and
+//! `<getetag xmlns="DAV:">` are all treated the same way.
+
+use quick_xml::{events::Event, Reader};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// The WebDAV namespace.
+pub const DAV: &str = "DAV:";
+/// The CardDAV namespace.
+pub const CARDDAV: &str = "urn:ietf:params:xml:ns:carddav";
+/// The CalendarServer namespace, used by the `getctag` extension.
+pub const CALSERVER: &str = "http://calendarserver.org/ns/";
+
+/// Represents the multistatus errors.
+#[derive(Debug, Error)]
+pub enum MultistatusError {
+    #[error("cannot parse xml")]
+    Xml(#[from] quick_xml::Error),
+    #[error("cannot find element <{0}> in multistatus")]
+    MissingElement(&'static str),
+    #[error(r#"cannot parse status line "{0}""#)]
+    InvalidStatus(String),
+    #[error(r#"cannot find property "{name}" in response "{href}"{}"#, .status.as_ref().map(|s| format!(" (reported as {})", s)).unwrap_or_default())]
+    MissingProp {
+        href: String,
+        name: &'static str,
+        status: Option<Status>,
+    },
+    #[error(r#"cannot parse property "{name}" in response "{href}": {reason}"#)]
+    InvalidProp {
+        href: String,
+        name: &'static str,
+        reason: String,
+    },
+}
+
+type Result<T> = std::result::Result<T, MultistatusError>;
+
+/// Represents a namespaced XML element.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub ns: Option<String>,
+    pub name: String,
+    pub text: String,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    /// Checks if the element matches the given namespace and local name.
+    pub fn is(&self, ns: &str, name: &str) -> bool {
+        self.ns.as_deref() == Some(ns) && self.name == name
+    }
+
+    /// Finds the first child matching the given namespace and local name.
+    pub fn child(&self, ns: &str, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.is(ns, name))
+    }
+
+    /// Iterates over the children matching the given namespace and local name.
+    pub fn children<'a>(
+        &'a self,
+        ns: &'a str,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.is(ns, name))
+    }
+
+    /// Returns the trimmed text content of the element.
+    pub fn text(&self) -> &str {
+        self.text.trim()
+    }
+
+    /// Parses an XML document into its root element.
+    pub fn parse(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        let mut ns_buf = Vec::new();
+        let mut stack: Vec<Element> = vec![Element::default()];
+
+        loop {
+            match reader.read_namespaced_event(&mut buf, &mut ns_buf)? {
+                (ns, Event::Start(ref e)) => stack.push(Element {
+                    ns: ns.map(|ns| String::from_utf8_lossy(ns).into_owned()),
+                    name: String::from_utf8_lossy(e.local_name()).into_owned(),
+                    ..Element::default()
+                }),
+                (ns, Event::Empty(ref e)) => {
+                    let elem = Element {
+                        ns: ns.map(|ns| String::from_utf8_lossy(ns).into_owned()),
+                        name: String::from_utf8_lossy(e.local_name()).into_owned(),
+                        ..Element::default()
+                    };
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(elem);
+                    }
+                }
+                (_, Event::Text(ref e)) => {
+                    let text = e.unescape_and_decode(&reader)?;
+                    if let Some(elem) = stack.last_mut() {
+                        elem.text.push_str(&text);
+                    }
+                }
+                (_, Event::CData(ref e)) => {
+                    let text = reader.decode(e)?;
+                    if let Some(elem) = stack.last_mut() {
+                        elem.text.push_str(text);
+                    }
+                }
+                (_, Event::End(_)) if stack.len() > 1 => {
+                    let elem = stack.pop().unwrap_or_default();
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(elem);
+                    }
+                }
+                (_, Event::Eof) => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+
+        Ok(stack
+            .into_iter()
+            .next()
+            .and_then(|doc| doc.children.into_iter().next())
+            .unwrap_or_default())
+    }
+}
+
+/// Represents an HTTP status line, like `HTTP/1.1 200 OK`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub code: u16,
+    pub reason: String,
+}
+
+impl Status {
+    /// Checks if the status is in the 2xx range.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code)
+    }
+}
+
+impl FromStr for Status {
+    type Err = MultistatusError;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let mut parts = line.trim().splitn(3, ' ');
+        let _version = parts.next();
+        let code = parts
+            .next()
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| MultistatusError::InvalidStatus(line.trim().to_owned()))?;
+        let reason = parts.next().unwrap_or_default().to_owned();
+        Ok(Self { code, reason })
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.reason)
+    }
+}
+
+/// Represents a `207 Multi-Status` body.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Multistatus {
+    pub responses: Vec<Response>,
+    /// The sync token, only set by `sync-collection` reports (RFC 6578).
+    pub sync_token: Option<String>,
+}
+
+/// Represents a response of a multistatus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub href: String,
+    /// The response status, only set when the response has no propstat (for example a deleted
+    /// member in a `sync-collection` report).
+    pub status: Option<Status>,
+    pub propstats: Vec<Propstat>,
+}
+
+/// Represents a propstat of a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Propstat {
+    pub props: Vec<Element>,
+    pub status: Status,
+}
+
+/// Represents the properties of a response, merged across its propstats.
+#[derive(Debug)]
+pub struct Props<'a> {
+    pub href: &'a str,
+    found: Vec<&'a Element>,
+    failed: Vec<(&'a Element, &'a Status)>,
+}
+
+/// Defines how to build a typed value from the properties of a response.
+pub trait FromProps: Sized {
+    fn from_props(props: &Props) -> Result<Self>;
+}
+
+impl FromStr for Multistatus {
+    type Err = MultistatusError;
+
+    fn from_str(xml: &str) -> Result<Self> {
+        let root = Element::parse(xml)?;
+        if !root.is(DAV, "multistatus") {
+            return Err(MultistatusError::MissingElement("multistatus"));
+        }
+
+        let responses = root
+            .children(DAV, "response")
+            .map(Response::try_from_element)
+            .collect::<Result<_>>()?;
+        let sync_token = root
+            .child(DAV, "sync-token")
+            .map(|token| token.text().to_owned());
+
+        Ok(Self {
+            responses,
+            sync_token,
+        })
+    }
+}
+
+impl Response {
+    fn try_from_element(elem: &Element) -> Result<Self> {
+        let href = elem
+            .child(DAV, "href")
+            .map(|href| href.text().to_owned())
+            .ok_or(MultistatusError::MissingElement("href"))?;
+        let status = elem
+            .child(DAV, "status")
+            .map(|status| status.text().parse())
+            .transpose()?;
+        let propstats = elem
+            .children(DAV, "propstat")
+            .map(Propstat::try_from_element)
+            .collect::<Result<Vec<_>>>()?;
+
+        if status.is_none() && propstats.is_empty() {
+            return Err(MultistatusError::MissingElement("propstat"));
+        }
+
+        Ok(Self {
+            href,
+            status,
+            propstats,
+        })
+    }
+
+    /// Checks if the response reports a missing resource, like a deleted member in a
+    /// `sync-collection` report.
+    pub fn is_not_found(&self) -> bool {
+        self.status.as_ref().map(|s| s.code == 404).unwrap_or(false)
+    }
+
+    /// Gathers the properties of the response. Only properties of successful propstats are
+    /// considered found.
+    pub fn props(&self) -> Props<'_> {
+        let mut props = Props {
+            href: &self.href,
+            found: vec![],
+            failed: vec![],
+        };
+
+        for propstat in &self.propstats {
+            for prop in &propstat.props {
+                if propstat.status.is_success() {
+                    props.found.push(prop);
+                } else {
+                    props.failed.push((prop, &propstat.status));
+                }
+            }
+        }
+
+        props
+    }
+
+    /// Builds a typed value from the properties of the response.
+    pub fn prop<T: FromProps>(&self) -> Result<T> {
+        T::from_props(&self.props())
+    }
+}
+
+impl Propstat {
+    fn try_from_element(elem: &Element) -> Result<Self> {
+        let status = elem
+            .child(DAV, "status")
+            .ok_or(MultistatusError::MissingElement("status"))?
+            .text()
+            .parse()?;
+        let props = elem
+            .child(DAV, "prop")
+            .ok_or(MultistatusError::MissingElement("prop"))?
+            .children
+            .clone();
+        Ok(Self { props, status })
+    }
+}
+
+impl<'a> Props<'a> {
+    /// Finds a successfully returned property.
+    pub fn find(&self, ns: &str, name: &str) -> Option<&'a Element> {
+        self.found.iter().copied().find(|prop| prop.is(ns, name))
+    }
+
+    /// Gets a successfully returned property, or fails with an error naming the missing
+    /// property (and the status the server reported for it, if any).
+    pub fn get(&self, ns: &str, name: &'static str) -> Result<&'a Element> {
+        self.find(ns, name)
+            .ok_or_else(|| MultistatusError::MissingProp {
+                href: self.href.to_owned(),
+                name,
+                status: self
+                    .failed
+                    .iter()
+                    .find(|(prop, _)| prop.is(ns, name))
+                    .map(|(_, status)| (*status).clone()),
+            })
+    }
+
+    /// Gets the text content of a successfully returned property.
+    pub fn text(&self, ns: &str, name: &'static str) -> Result<String> {
+        self.get(ns, name).map(|prop| prop.text().to_owned())
+    }
+
+    /// Builds an invalid property error for this response.
+    pub fn invalid(&self, name: &'static str, reason: impl ToString) -> MultistatusError {
+        MultistatusError::InvalidProp {
+            href: self.href.to_owned(),
+            name,
+            reason: reason.to_string(),
+        }
+    }
+}
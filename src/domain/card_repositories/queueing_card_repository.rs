@@ -0,0 +1,205 @@
+//! Queueing card repository module.
+//!
+//! This module provides a card repository decorator journaling the writes the server could not
+//! receive, then replaying them in order once it can be reached again.
+
+use log::debug;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::domain::{
+    is_card_file_name, Card, CardError, CardRepository, FlushReport, QueuedWrite, WriteConflict,
+    WriteOp, WriteQueue,
+};
+
+/// Name of the directory holding the local version of the cards whose queued write conflicted,
+/// next to the write queue.
+const CONFLICTS_DIR: &str = "conflicts";
+
+/// Card repository decorator queueing the writes of the given repository in the given file
+/// when the server cannot be reached. A missing repository means the server could not be
+/// reached at all.
+pub struct QueueingCardRepository<'a> {
+    pub repository: Option<&'a dyn CardRepository>,
+    pub path: PathBuf,
+}
+
+impl<'a> QueueingCardRepository<'a> {
+    pub fn new(repository: Option<&'a dyn CardRepository>, path: PathBuf) -> Self {
+        Self { repository, path }
+    }
+
+    fn remote(&self) -> Result<&'a dyn CardRepository, CardError> {
+        self.repository.ok_or(CardError::Offline)
+    }
+
+    /// Returns the queued writes, oldest first.
+    pub fn pending(&self) -> Result<Vec<QueuedWrite>, CardError> {
+        Ok(WriteQueue::read(&self.path)?.writes)
+    }
+
+    /// Sends a write to the server, unless earlier writes are still queued, in which case it is
+    /// reported as offline so that it gets queued after them.
+    fn send<F>(&self, f: F) -> Result<(), CardError>
+    where
+        F: FnOnce(&dyn CardRepository) -> Result<(), CardError>,
+    {
+        if !self.pending()?.is_empty() {
+            return Err(CardError::Offline);
+        }
+        f(self.remote()?)
+    }
+
+    fn enqueue(&self, op: WriteOp, card: &Card) -> Result<(), CardError> {
+        let mut queue = WriteQueue::read(&self.path)?;
+        queue.writes.push(QueuedWrite::new(op, card));
+        queue.write(&self.path)?;
+        eprintln!(
+            r#"Warning: cannot reach server, {} of card "{}" queued until the next flush"#,
+            op, card.id
+        );
+        Ok(())
+    }
+
+    /// Replays a queued write using the given etag as precondition, then returns the new etag
+    /// of the card.
+    fn replay(
+        &self,
+        write: &QueuedWrite,
+        etag: Option<String>,
+    ) -> Result<Option<String>, CardError> {
+        let repository = self.remote()?;
+        let mut card = write.to_card(etag);
+        match write.op {
+            WriteOp::Create => repository.create(&mut card)?,
+            WriteOp::Update => repository.update(&mut card)?,
+            WriteOp::Delete => {
+                repository.delete(&card)?;
+                return Ok(None);
+            }
+        }
+        Ok(card.etag)
+    }
+
+    /// Saves the local version of a card whose write conflicted, so that it can be merged by
+    /// hand.
+    fn save_conflict(&self, write: &QueuedWrite) -> Result<Option<String>, CardError> {
+        if write.op == WriteOp::Delete {
+            return Ok(None);
+        }
+        let dir = self
+            .path
+            .parent()
+            .map(|dir| dir.join(CONFLICTS_DIR))
+            .unwrap_or_else(|| PathBuf::from(CONFLICTS_DIR));
+        fs::create_dir_all(&dir)?;
+        // Ids coming from the server could escape the directory, they are hashed instead.
+        let name = if is_card_file_name(&write.card_id) {
+            write.card_id.clone()
+        } else {
+            Sha256::digest(write.card_id.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect()
+        };
+        let path = dir.join(format!(
+            "{}-{}.vcf",
+            name,
+            write.queued_at.format("%Y%m%dT%H%M%S")
+        ));
+        fs::write(&path, &write.raw)?;
+        Ok(Some(path.to_string_lossy().into_owned()))
+    }
+
+    /// Replays the queued writes in order. Writes rejected because the card changed in the
+    /// meantime are dropped from the queue and reported as conflicts. The replay stops at the
+    /// first write the server cannot receive, leaving it and the following ones queued.
+    pub fn flush(&self) -> Result<FlushReport, CardError> {
+        let mut queue = WriteQueue::read(&self.path)?;
+        let mut report = FlushReport::default();
+        // Etags of the cards written during the replay, replacing the base etags of their
+        // following writes.
+        let mut etags: HashMap<String, Option<String>> = HashMap::new();
+
+        let mut writes = std::mem::take(&mut queue.writes).into_iter();
+        while let Some(write) = writes.next() {
+            let etag = match etags.get(&write.card_id) {
+                Some(etag) => etag.clone(),
+                None => write.base_etag.clone(),
+            };
+            match self.replay(&write, etag) {
+                Ok(etag) => {
+                    debug!(r#"replayed {} of card "{}""#, write.op, write.card_id);
+                    etags.insert(write.card_id.clone(), etag);
+                    report.flushed.push(write);
+                }
+                Err(CardError::NotFound(_)) if write.op == WriteOp::Delete => {
+                    etags.insert(write.card_id.clone(), None);
+                    report.flushed.push(write);
+                }
+                Err(err @ CardError::PreconditionFailed { .. })
+                | Err(err @ CardError::NotFound(_)) => {
+                    let saved_to = self.save_conflict(&write)?;
+                    report.conflicts.push(WriteConflict {
+                        write,
+                        reason: err.to_string(),
+                        saved_to,
+                    });
+                }
+                Err(err) if err.is_offline() => {
+                    report.pending.push(write);
+                    report.pending.extend(writes);
+                    break;
+                }
+                Err(err) => {
+                    queue.writes = Some(write).into_iter().chain(writes).collect();
+                    queue.write(&self.path)?;
+                    return Err(err);
+                }
+            }
+        }
+
+        queue.writes = report.pending.clone();
+        queue.write(&self.path)?;
+        Ok(report)
+    }
+}
+
+impl<'a> CardRepository for QueueingCardRepository<'a> {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        match self.send(|repository| repository.create(card)) {
+            Err(err) if err.is_offline() => self.enqueue(WriteOp::Create, card),
+            res => res,
+        }
+    }
+
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        self.remote()?.read(id)
+    }
+
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        self.remote()?.read_all()
+    }
+
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        match self.send(|repository| repository.update(card)) {
+            Err(err) if err.is_offline() => self.enqueue(WriteOp::Update, card),
+            res => res,
+        }
+    }
+
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        match self.send(|repository| repository.delete(card)) {
+            Err(err) if err.is_offline() => self.enqueue(WriteOp::Delete, card),
+            res => res,
+        }
+    }
+
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        self.remote()?.ctag()
+    }
+
+    fn read_if_none_match(&self, id: &str, etag: &str) -> Result<Option<Card>, CardError> {
+        self.remote()?.read_if_none_match(id, etag)
+    }
+}
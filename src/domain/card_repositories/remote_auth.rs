@@ -0,0 +1,76 @@
+//! Remote auth module.
+//!
+//! This module provides the authentication schemes applied by the remote card repository to
+//! every request.
+
+use reqwest::{
+    blocking::{Request, Response},
+    header::{HeaderValue, AUTHORIZATION},
+};
+use std::fmt;
+
+use crate::domain::{card_repositories::DigestAuth, CardError};
+
+/// Represents a source of OAuth2 bearer tokens.
+pub trait TokenProvider {
+    /// Returns a valid access token, refreshing it if needed.
+    fn token(&self) -> Result<String, CardError>;
+    /// Forces the refresh of the access token, after the server rejected it.
+    fn refresh(&self) -> Result<String, CardError>;
+}
+
+/// Represents the authentication of remote requests.
+#[derive(Default)]
+pub enum Auth {
+    #[default]
+    None,
+    Basic {
+        login: String,
+        passwd: String,
+    },
+    Digest(DigestAuth),
+    Bearer(Box<dyn TokenProvider>),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Basic { login, .. } => write!(f, "Basic({:?})", login),
+            Self::Digest(auth) => write!(f, "Digest({:?})", auth.login),
+            Self::Bearer(_) => write!(f, "Bearer"),
+        }
+    }
+}
+
+impl Auth {
+    /// Sets the authorization header of the given request.
+    pub fn authorize(&self, req: &mut Request) -> Result<(), CardError> {
+        let header = match self {
+            Self::None => return Ok(()),
+            Self::Basic { login, passwd } => {
+                format!("Basic {}", base64::encode(format!("{}:{}", login, passwd)))
+            }
+            Self::Digest(auth) => match auth.authorization(req) {
+                Some(header) => header,
+                None => return Ok(()),
+            },
+            Self::Bearer(provider) => format!("Bearer {}", provider.token()?),
+        };
+        let mut header =
+            HeaderValue::from_str(&header).map_err(|err| CardError::parse("credentials", err))?;
+        header.set_sensitive(true);
+        req.headers_mut().insert(AUTHORIZATION, header);
+        Ok(())
+    }
+
+    /// Reacts to a `401 Unauthorized` response. Returns `true` if the request should be sent
+    /// again with fresh credentials.
+    pub fn challenge(&self, res: &Response) -> Result<bool, CardError> {
+        match self {
+            Self::Digest(auth) => auth.challenge(res),
+            Self::Bearer(provider) => provider.refresh().map(|_| true),
+            _ => Ok(false),
+        }
+    }
+}
@@ -1,393 +1,613 @@
-use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Local};
-use quick_xml::de as xml;
-use reqwest::{blocking::Client, Method};
-use serde::Deserialize;
-
-use crate::domain::{Card, CardRepository};
+use reqwest::{
+    blocking::{Client, Request, RequestBuilder, Response},
+    tls::TlsInfo,
+    Method, StatusCode,
+};
+use sha2::{Digest, Sha256};
+
+use crate::domain::{
+    card_repositories::{
+        multistatus::{FromProps, Multistatus, MultistatusError, Props, CALSERVER, CARDDAV, DAV},
+        Auth, RetryPolicy,
+    },
+    Card, CardError, CardRepository,
+};
 
 pub struct RemoteCardRepository<'a> {
+    pub host: String,
     pub addressbook_path: String,
     pub client: &'a Client,
+    pub auth: Auth,
+    pub retry: RetryPolicy,
+    /// SHA-256 fingerprints of the accepted server certificates, in lowercase hexadecimal. Any
+    /// certificate is accepted when empty.
+    pub pinned_fingerprints: Vec<String>,
+}
+
+/// Computes the SHA-256 fingerprint of a DER certificate, in lowercase hexadecimal.
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
 }
 
 impl<'a> RemoteCardRepository<'a> {
-    pub fn new(host: &str, client: &'a Client) -> Result<Self> {
-        Ok(Self {
-            addressbook_path: format!("{}{}", host, addressbook_path(host, client)?),
-            client,
-        })
+    pub fn new(host: &str, client: &'a Client) -> Result<Self, CardError> {
+        Self::with_options(host, client, Auth::None, RetryPolicy::default())
     }
-}
 
-impl<'a> CardRepository for RemoteCardRepository<'a> {
-    fn create(&self, card: &mut Card) -> Result<()> {
-        let res = self
-            .client
-            .put(format!("{}{}.vcf", self.addressbook_path, card.id))
-            .basic_auth("user", Some(""))
-            .header("Content-Type", "text/vcard; charset=utf-8")
-            .body(card.raw.clone())
-            .send()
-            .with_context(|| "cannot create card")?;
-        let res_status = res.status();
-
-        if !res_status.is_success() {
-            let reason = res.text().unwrap_or(res_status.to_string());
-            return Err(anyhow!(reason).context("cannot create card"));
+    /// Creates a repository using the given authentication and retry policy, then discovers the
+    /// addressbook of the current user.
+    pub fn with_options(
+        host: &str,
+        client: &'a Client,
+        auth: Auth,
+        retry: RetryPolicy,
+    ) -> Result<Self, CardError> {
+        Self::undiscovered(host, client, auth, retry).discover()
+    }
+
+    /// Discovers the addressbook of the current user.
+    pub fn discover(mut self) -> Result<Self, CardError> {
+        self.addressbook_path = format!("{}{}", self.host, self.discover_addressbook()?);
+        Ok(self)
+    }
+
+    /// Creates a repository without discovering the addressbook, in order to run the discovery
+    /// steps one by one.
+    pub fn undiscovered(host: &str, client: &'a Client, auth: Auth, retry: RetryPolicy) -> Self {
+        Self {
+            host: host.to_owned(),
+            addressbook_path: String::new(),
+            client,
+            auth,
+            retry,
+            pinned_fingerprints: vec![],
         }
+    }
 
-        card.etag = res
-            .headers()
-            .get("etag")
-            .and_then(|h| h.to_str().ok())
-            .or_else(|| card.etag.as_deref())
-            .map(String::from);
+    /// Only accepts the server certificates matching the given fingerprints. The client must
+    /// be built with TLS info enabled, so that the certificate of each response is known.
+    pub fn with_pinned_fingerprints(mut self, fingerprints: Vec<String>) -> Self {
+        self.pinned_fingerprints = fingerprints;
+        self
+    }
 
-        Ok(())
+    fn card_url(&self, id: &str) -> String {
+        format!("{}{}.vcf", self.addressbook_path, id)
     }
 
-    fn read(&self, id: &str) -> Result<Card> {
-        let res = self
-            .client
-            .get(format!("{}{}.vcf", self.addressbook_path, id))
-            .basic_auth("user", Some(""))
-            .header("Depth", "1")
-            .send()
-            .with_context(|| anyhow!(r#"cannot read card "{}""#, id))?;
-        let res_status = res.status();
+    /// Sends a request with the authentication of the repository. Idempotent requests are sent
+    /// using the retry policy. If the server rejects the credentials and the authentication
+    /// scheme can recover (like a refreshed bearer token), the request is sent once more.
+    fn send(&self, req: RequestBuilder, idempotent: bool) -> Result<Response, CardError> {
+        let req = req.build()?;
+        let res = self.execute(&req, idempotent)?;
 
-        if !res_status.is_success() {
-            let reason = res.text().unwrap_or(res_status.to_string());
-            return Err(anyhow!(reason).context(format!(r#"cannot read card "{}""#, id)));
+        if res.status() == StatusCode::UNAUTHORIZED && self.auth.challenge(&res)? {
+            return self.execute(&req, idempotent);
         }
 
-        let date = res
-            .headers()
-            .get("last-modified")
-            .ok_or_else(|| anyhow!(r#"cannot get last modified date of card "{}""#, id))?;
-        let date = date
-            .to_str()
-            .with_context(|| anyhow!(r#"cannot parse last modified date of card "{}""#, id))?;
-        let date = DateTime::parse_from_rfc2822(date)
-            .with_context(|| anyhow!(r#"cannot parse last modified date of card "{}""#, id))?
-            .with_timezone(&Local);
-        let etag = res
-            .headers()
-            .get("etag")
-            .and_then(|h| h.to_str().ok())
-            .map(String::from);
-        let raw = res
-            .text()
-            .context(anyhow!(r#"cannot read content of card "{}""#, id))?;
-
-        Ok(Card {
-            id: id.to_owned(),
-            etag,
-            date,
-            raw,
-        })
+        Ok(res)
     }
 
-    fn read_all(&self) -> Result<Vec<Card>> {
-        todo!()
+    fn execute(&self, req: &Request, idempotent: bool) -> Result<Response, CardError> {
+        let mut req = req
+            .try_clone()
+            .ok_or_else(|| CardError::parse("request", "cannot clone request body"))?;
+        self.auth.authorize(&mut req)?;
+
+        let res = if idempotent {
+            self.retry.execute(self.client, req)?
+        } else {
+            self.client.execute(req)?
+        };
+        self.check_certificate(&res)?;
+        Ok(res)
     }
 
-    fn update(&self, card: &mut Card) -> Result<()> {
-        let mut req = self
-            .client
-            .put(format!("{}{}.vcf", self.addressbook_path, card.id))
-            .basic_auth("user", Some(""))
-            .header("Content-Type", "text/vcard; charset=utf-8")
-            .body(card.raw.clone());
+    /// Checks that the certificate of the connection the response came from matches one of the
+    /// pinned fingerprints.
+    fn check_certificate(&self, res: &Response) -> Result<(), CardError> {
+        if self.pinned_fingerprints.is_empty() {
+            return Ok(());
+        }
+        let fingerprint = res
+            .extensions()
+            .get::<TlsInfo>()
+            .and_then(TlsInfo::peer_certificate)
+            .map(fingerprint)
+            .unwrap_or_default();
+        if self.pinned_fingerprints.contains(&fingerprint) {
+            Ok(())
+        } else {
+            Err(CardError::UntrustedCertificate(fingerprint))
+        }
+    }
+
+    /// Fetches the current etag of a card, used to report precondition failures.
+    fn fetch_etag(&self, id: &str) -> Option<String> {
+        self.send(self.client.head(self.card_url(id)), true)
+            .ok()
+            .filter(|res| res.status().is_success())
+            .and_then(|res| etag(&res))
+    }
 
-        if let Some(etag) = card.etag.as_deref() {
-            req = req.header("If-Match", etag);
+    /// Maps an unsuccessful response of a card request to the matching error.
+    fn check(&self, id: &str, res: Response) -> Result<Response, CardError> {
+        match res.status() {
+            status if status.is_success() => Ok(res),
+            StatusCode::PRECONDITION_FAILED => Err(CardError::PreconditionFailed {
+                id: id.to_owned(),
+                current_etag: self.fetch_etag(id),
+            }),
+            StatusCode::NOT_FOUND => Err(CardError::NotFound(id.to_owned())),
+            _ => check_status(res),
         }
+    }
 
-        let res = req
-            .send()
-            .with_context(|| format!(r#"cannot update card "{}""#, card.id))?;
-        let res_status = res.status();
+    /// Sends a propfind request and parses its multistatus response.
+    fn propfind(
+        &self,
+        path: &str,
+        depth: &str,
+        body: &'static str,
+        what: &str,
+    ) -> Result<Multistatus, CardError> {
+        let req = self
+            .client
+            .request(propfind()?, format!("{}{}", self.host, path))
+            .header("Depth", depth)
+            .body(body);
+        let res = check_status(self.send(req, true)?)?.text()?;
+        res.parse()
+            .map_err(|err: MultistatusError| CardError::parse(format!("{} response", what), err))
+    }
 
-        if !res_status.is_success() {
-            let reason = res.text().unwrap_or(res_status.to_string());
-            return Err(anyhow!(reason).context(format!(r#"cannot update card "{}""#, card.id)));
+    pub fn fetch_current_user_principal_path(&self, path: String) -> Result<String, CardError> {
+        let res = self.propfind(
+            &path,
+            "0",
+            r#"
+            <D:propfind xmlns:D="DAV:">
+                <D:prop>
+                    <D:current-user-principal />
+                </D:prop>
+            </D:propfind>
+            "#,
+            "current user principal",
+        )?;
+
+        match res.responses.first() {
+            Some(res) => Ok(res
+                .prop::<CurrentUserPrincipalProp>()
+                .map_err(|err| CardError::parse("current user principal response", err))?
+                .href),
+            None => Ok(path),
         }
+    }
 
-        card.etag = res
-            .headers()
-            .get("etag")
-            .and_then(|h| h.to_str().ok())
-            .or_else(|| card.etag.as_deref())
-            .map(String::from);
+    pub fn fetch_addressbook_home_set_path(&self, path: String) -> Result<String, CardError> {
+        let res = self.propfind(
+            &path,
+            "0",
+            r#"
+            <D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+                <D:prop>
+                    <C:addressbook-home-set />
+                </D:prop>
+            </D:propfind>
+            "#,
+            "addressbook home set",
+        )?;
+
+        match res.responses.first() {
+            Some(res) => Ok(res
+                .prop::<AddressbookHomeSetProp>()
+                .map_err(|err| CardError::parse("addressbook home set response", err))?
+                .href),
+            None => Ok(path),
+        }
+    }
 
-        Ok(())
+    pub fn fetch_addressbook_paths(&self, path: &str) -> Result<Vec<String>, CardError> {
+        let res = self.propfind(
+            path,
+            "1",
+            r#"
+            <D:propfind xmlns:D="DAV:">
+                <D:prop>
+                    <D:resourcetype />
+                </D:prop>
+            </D:propfind>
+            "#,
+            "addressbook",
+        )?;
+
+        Ok(res
+            .responses
+            .iter()
+            .filter(|res| {
+                res.prop::<AddressbookProp>()
+                    .map(|prop| prop.is_addressbook)
+                    .unwrap_or(false)
+            })
+            .map(|res| res.href.to_owned())
+            .collect())
     }
 
-    fn delete(&self, card: &Card) -> Result<()> {
-        let mut req = self
+    /// Fetches the compliance classes advertised by the `DAV` header of an OPTIONS response,
+    /// like `addressbook` for CardDAV servers.
+    pub fn fetch_dav_capabilities(&self, path: &str) -> Result<Vec<String>, CardError> {
+        let req = self
             .client
-            .delete(format!("{}{}.vcf", self.addressbook_path, card.id))
-            .basic_auth("user", Some(""));
+            .request(Method::OPTIONS, format!("{}{}", self.host, path));
+        let res = check_status(self.send(req, true)?)?;
+        Ok(res
+            .headers()
+            .get_all("DAV")
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|header| header.split(','))
+            .map(|class| class.trim().to_owned())
+            .filter(|class| !class.is_empty())
+            .collect())
+    }
 
-        if let Some(etag) = card.etag.as_deref() {
-            req = req.header("If-Match", etag);
+    /// Fetches the names of the reports supported by the given collection, like
+    /// `sync-collection` (RFC 6578).
+    pub fn fetch_supported_reports(&self, path: &str) -> Result<Vec<String>, CardError> {
+        let res = self.propfind(
+            path,
+            "0",
+            r#"
+            <D:propfind xmlns:D="DAV:">
+                <D:prop>
+                    <D:supported-report-set />
+                </D:prop>
+            </D:propfind>
+            "#,
+            "supported report set",
+        )?;
+
+        match res.responses.first() {
+            Some(res) => Ok(res
+                .prop::<SupportedReportSetProp>()
+                .map_err(|err| CardError::parse("supported report set response", err))?
+                .reports),
+            None => Ok(vec![]),
         }
+    }
 
-        let res = req
-            .send()
-            .with_context(|| format!(r#"cannot delete card "{}""#, card.id))?;
-        let res_status = res.status();
+    /// Discovers the path of the first addressbook of the current user, following RFC 6764:
+    /// current user principal, then addressbook home set, then addressbook collections.
+    pub fn discover_addressbook(&self) -> Result<String, CardError> {
+        let path = self.discover_addressbooks()?.into_iter().next();
+        Ok(path.unwrap_or_default())
+    }
 
-        if !res_status.is_success() {
-            let reason = res.text().unwrap_or(res_status.to_string());
-            return Err(anyhow!(reason).context(format!(r#"cannot delete card "{}""#, card.id)));
-        }
+    /// Discovers the paths of all the addressbooks of the current user. The addressbook home
+    /// set is returned when it does not contain any addressbook collection.
+    pub fn discover_addressbooks(&self) -> Result<Vec<String>, CardError> {
+        let path = String::from("/");
+        let path = self.fetch_current_user_principal_path(path)?;
+        let path = self.fetch_addressbook_home_set_path(path)?;
+        let paths = self.fetch_addressbook_paths(&path)?;
+        Ok(if paths.is_empty() { vec![path] } else { paths })
+    }
+}
 
+impl<'a> CardRepository for RemoteCardRepository<'a> {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        let req = self
+            .client
+            .put(self.card_url(&card.id))
+            .header("Content-Type", "text/vcard; charset=utf-8")
+            .header("If-None-Match", "*")
+            .body(card.raw.clone());
+        let res = self.check(&card.id, self.send(req, false)?)?;
+
+        card.etag = etag(&res).or_else(|| card.etag.take());
         Ok(())
     }
-}
 
-// Common structs
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        let req = self.client.get(self.card_url(id));
+        let res = self.check(id, self.send(req, true)?)?;
+        card_from_response(id, res)
+    }
 
-#[derive(Debug, Deserialize)]
-pub struct Multistatus<T> {
-    #[serde(rename = "response")]
-    pub responses: Vec<Response<T>>,
-}
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        let req = self
+            .client
+            .request(report()?, &self.addressbook_path)
+            .header("Depth", "1")
+            .body(
+                r#"
+                <C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+                    <D:prop>
+                        <D:getetag />
+                        <D:getlastmodified />
+                        <C:address-data />
+                    </D:prop>
+                </C:addressbook-query>
+                "#,
+            );
+        let res = check_status(self.send(req, true)?)?.text()?;
+        let res: Multistatus = res
+            .parse()
+            .map_err(|err| CardError::parse("addressbook query response", err))?;
+
+        res.responses
+            .iter()
+            .filter(|res| !res.is_not_found())
+            .map(|res| {
+                let prop = res
+                    .prop::<AddressDataProp>()
+                    .map_err(|err| CardError::parse("addressbook query response", err))?;
+                Ok(Card {
+                    id: card_id(&res.href),
+                    etag: Some(prop.getetag),
+                    date: prop.getlastmodified,
+                    raw: prop.address_data,
+                })
+            })
+            .collect()
+    }
 
-#[derive(Debug, Deserialize)]
-pub struct Response<T> {
-    pub href: Href,
-    pub propstat: Propstat<T>,
-}
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        let req = self
+            .client
+            .put(self.card_url(&card.id))
+            .header("Content-Type", "text/vcard; charset=utf-8")
+            .body(card.raw.clone());
+        let res = match card.etag.as_deref() {
+            Some(etag) => self.send(req.header("If-Match", etag), true)?,
+            None => self.send(req, false)?,
+        };
+        let res = self.check(&card.id, res)?;
 
-#[derive(Debug, Deserialize)]
-pub struct Propstat<T> {
-    pub prop: T,
-    pub status: Option<Status>,
-}
+        card.etag = etag(&res).or_else(|| card.etag.take());
+        Ok(())
+    }
 
-#[derive(Debug, Deserialize)]
-pub struct Href {
-    #[serde(rename = "$value")]
-    pub value: String,
-}
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        let req = self.client.delete(self.card_url(&card.id));
+        let res = match card.etag.as_deref() {
+            Some(etag) => self.send(req.header("If-Match", etag), true)?,
+            None => self.send(req, false)?,
+        };
+        self.check(&card.id, res)?;
+        Ok(())
+    }
 
-#[derive(Debug, Deserialize)]
-pub struct Status {
-    #[serde(rename = "$value")]
-    pub value: String,
-}
+    /// Fetches the `getctag` of the addressbook, falling back to its sync token (RFC 6578).
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        let path = self
+            .addressbook_path
+            .strip_prefix(&self.host)
+            .unwrap_or(&self.addressbook_path);
+        let res = self.propfind(
+            path,
+            "0",
+            r#"
+            <D:propfind xmlns:D="DAV:" xmlns:CS="http://calendarserver.org/ns/">
+                <D:prop>
+                    <CS:getctag />
+                    <D:sync-token />
+                </D:prop>
+            </D:propfind>
+            "#,
+            "ctag",
+        )?;
+
+        Ok(res
+            .responses
+            .first()
+            .and_then(|res| res.prop::<CtagProp>().ok())
+            .map(|prop| prop.getctag))
+    }
 
-#[derive(Debug, Deserialize)]
-pub struct GetCtag {
-    #[serde(rename = "$value")]
-    pub value: String,
+    fn read_if_none_match(&self, id: &str, etag: &str) -> Result<Option<Card>, CardError> {
+        let req = self
+            .client
+            .get(self.card_url(id))
+            .header("If-None-Match", etag);
+        let res = self.send(req, true)?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let res = self.check(id, res)?;
+        card_from_response(id, res).map(Some)
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct GetEtag {
-    #[serde(rename = "$value")]
-    pub value: String,
+/// Extracts the etag header of a response.
+fn etag(res: &Response) -> Option<String> {
+    res.headers()
+        .get("etag")
+        .and_then(|h| h.to_str().ok())
+        .map(String::from)
 }
 
-#[derive(Debug, Deserialize)]
-pub struct GetLastModified {
-    #[serde(with = "date_parser", rename = "$value")]
-    pub value: DateTime<Local>,
+/// Builds a card from the response of a GET request.
+fn card_from_response(id: &str, res: Response) -> Result<Card, CardError> {
+    let date = res
+        .headers()
+        .get("last-modified")
+        .ok_or_else(|| {
+            CardError::parse(
+                format!(r#"last modified date of card "{}""#, id),
+                "missing header",
+            )
+        })?
+        .to_str()
+        .map_err(|err| CardError::parse(format!(r#"last modified date of card "{}""#, id), err))?;
+    let date = DateTime::parse_from_rfc2822(date)
+        .map_err(|err| CardError::parse(format!(r#"last modified date of card "{}""#, id), err))?
+        .with_timezone(&Local);
+    let etag = etag(&res);
+    let raw = res.text()?;
+
+    Ok(Card {
+        id: id.to_owned(),
+        etag,
+        date,
+        raw,
+    })
 }
 
-mod date_parser {
-    use chrono::{DateTime, Local};
-    use serde::{self, Deserialize, Deserializer};
+/// Extracts the card id from its href, which is the file name without the `.vcf` extension.
+fn card_id(href: &str) -> String {
+    let name = href
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    name.strip_suffix(".vcf").unwrap_or(name).to_owned()
+}
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Local>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        DateTime::parse_from_rfc2822(&s)
-            .map(|d| d.into())
-            .map_err(serde::de::Error::custom)
+/// Maps an unsuccessful response to the matching error, regardless of the requested resource.
+fn check_status(res: Response) -> Result<Response, CardError> {
+    match res.status() {
+        status if status.is_success() => Ok(res),
+        StatusCode::UNAUTHORIZED => Err(CardError::Unauthorized),
+        StatusCode::FORBIDDEN => Err(CardError::Forbidden),
+        status => Err(CardError::ServerError {
+            status: status.as_u16(),
+            body: res.text().unwrap_or_default(),
+        }),
     }
 }
 
-// Current user principal structs
+// Current user principal props
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug)]
 struct CurrentUserPrincipalProp {
-    pub current_user_principal: CurrentUserPrincipal,
+    pub href: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct CurrentUserPrincipal {
-    pub href: Href,
+impl FromProps for CurrentUserPrincipalProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        let prop = props.get(DAV, "current-user-principal")?;
+        let href = prop
+            .child(DAV, "href")
+            .ok_or_else(|| props.invalid("current-user-principal", "missing href"))?;
+        Ok(Self {
+            href: href.text().to_owned(),
+        })
+    }
 }
 
-// Addressbook home set structs
+// Addressbook home set props
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug)]
 struct AddressbookHomeSetProp {
-    pub addressbook_home_set: AddressbookHomeSet,
+    pub href: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct AddressbookHomeSet {
-    pub href: Href,
+impl FromProps for AddressbookHomeSetProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        let prop = props.get(CARDDAV, "addressbook-home-set")?;
+        let href = prop
+            .child(DAV, "href")
+            .ok_or_else(|| props.invalid("addressbook-home-set", "missing href"))?;
+        Ok(Self {
+            href: href.text().to_owned(),
+        })
+    }
 }
 
-// Addressbook structs
+// Addressbook props
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug)]
 struct AddressbookProp {
-    pub resourcetype: AddressbookResourceType,
+    pub is_addressbook: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct AddressbookResourceType {
-    pub addressbook: Option<Addressbook>,
+impl FromProps for AddressbookProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        let prop = props.get(DAV, "resourcetype")?;
+        Ok(Self {
+            is_addressbook: prop.child(CARDDAV, "addressbook").is_some(),
+        })
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct Addressbook {}
-
-// Address data structs
+// Supported report set props
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub struct AddressDataProp {
-    pub address_data: AddressData,
-    pub getetag: GetEtag,
-    pub getlastmodified: GetLastModified,
+#[derive(Debug)]
+struct SupportedReportSetProp {
+    pub reports: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct AddressData {
-    #[serde(rename = "$value")]
-    pub value: String,
+impl FromProps for SupportedReportSetProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        let prop = props.get(DAV, "supported-report-set")?;
+        let reports = prop
+            .children(DAV, "supported-report")
+            .filter_map(|report| report.child(DAV, "report"))
+            .flat_map(|report| report.children.iter())
+            .map(|report| report.name.to_owned())
+            .collect();
+        Ok(Self { reports })
+    }
 }
 
-// Ctag structs
+// Address data props
 
-#[derive(Debug, Deserialize)]
-pub struct CtagProp {
-    pub getctag: GetCtag,
+#[derive(Debug)]
+pub struct AddressDataProp {
+    pub address_data: String,
+    pub getetag: String,
+    pub getlastmodified: DateTime<Local>,
 }
 
-// Methods
+impl FromProps for AddressDataProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        let getlastmodified = props.text(DAV, "getlastmodified")?;
+        let getlastmodified = DateTime::parse_from_rfc2822(&getlastmodified)
+            .map_err(|err| props.invalid("getlastmodified", err))?
+            .with_timezone(&Local);
+        Ok(Self {
+            address_data: props.get(CARDDAV, "address-data")?.text.to_owned(),
+            getetag: props.text(DAV, "getetag")?,
+            getlastmodified,
+        })
+    }
+}
 
-fn propfind() -> Result<Method> {
-    Method::from_bytes(b"PROPFIND").context(r#"cannot create custom method "PROPFIND""#)
+// Ctag props
+
+#[derive(Debug)]
+pub struct CtagProp {
+    pub getctag: String,
 }
 
-fn fetch_current_user_principal_url(host: &str, path: String, client: &Client) -> Result<String> {
-    let res = client
-        .request(propfind()?, format!("{}{}", host, path))
-        .basic_auth("user", Some(""))
-        .body(
-            r#"
-            <D:propfind xmlns:D="DAV:">
-                <D:prop>
-                    <D:current-user-principal />
-                </D:prop>
-            </D:propfind>
-            "#,
-        )
-        .send()
-        .context("cannot send current user principal request")?;
-    let res = res
-        .text()
-        .context("cannot extract text body from current user principal response")?;
-    let res: Multistatus<CurrentUserPrincipalProp> =
-        xml::from_str(&res).context("cannot parse current user principal response")?;
-
-    Ok(res
-        .responses
-        .first()
-        .map(|res| {
-            res.propstat
-                .prop
-                .current_user_principal
-                .href
-                .value
-                .to_owned()
+impl FromProps for CtagProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        let getctag = props
+            .find(CALSERVER, "getctag")
+            .or_else(|| props.find(DAV, "sync-token"))
+            .map(|prop| prop.text().to_owned());
+        Ok(Self {
+            getctag: match getctag {
+                Some(getctag) => getctag,
+                None => props.text(CALSERVER, "getctag")?,
+            },
         })
-        .unwrap_or(path))
+    }
 }
 
-fn fetch_addressbook_home_set_url(host: &str, path: String, client: &Client) -> Result<String> {
-    let res = client
-        .request(propfind()?, format!("{}{}", host, path))
-        .basic_auth("user", Some(""))
-        .body(
-            r#"
-            <D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
-                <D:prop>
-                    <C:addressbook-home-set />
-                </D:prop>
-            </D:propfind>
-            "#,
-        )
-        .send()
-        .context("cannot send addressbook home set request")?;
-    let res = res
-        .text()
-        .context("cannot extract text body from addressbook home set response")?;
-    let res: Multistatus<AddressbookHomeSetProp> =
-        xml::from_str(&res).context("cannot parse addressbook home set response")?;
-
-    Ok(res
-        .responses
-        .first()
-        .map(|res| res.propstat.prop.addressbook_home_set.href.value.to_owned())
-        .unwrap_or(path))
-}
+// Methods
 
-fn fetch_addressbook_url(host: &str, path: String, client: &Client) -> Result<String> {
-    let res = client
-        .request(propfind()?, host)
-        .basic_auth("user", Some(""))
-        .send()
-        .context("cannot send addressbook request")?;
-    let res = res
-        .text()
-        .context("cannot extract text body from addressbook response")?;
-    let res: Multistatus<AddressbookProp> =
-        xml::from_str(&res).context("cannot parse addressbook response")?;
-
-    Ok(res
-        .responses
-        .iter()
-        .find(|res| {
-            let valid_status = res
-                .propstat
-                .status
-                .as_ref()
-                .map(|s| s.value.ends_with("200 OK"))
-                .unwrap_or(false);
-            let has_addressbook = res
-                .propstat
-                .prop
-                .resourcetype
-                .addressbook
-                .as_ref()
-                .is_some();
-
-            valid_status && has_addressbook
-        })
-        .map(|res| res.href.value.to_owned())
-        .unwrap_or(path))
+fn propfind() -> Result<Method, CardError> {
+    Method::from_bytes(b"PROPFIND")
+        .map_err(|err| CardError::parse(r#"custom method "PROPFIND""#, err))
 }
 
-pub fn addressbook_path(host: &str, client: &Client) -> Result<String> {
-    let path = String::from("/");
-    let path = fetch_current_user_principal_url(host, path, client)?;
-    let path = fetch_addressbook_home_set_url(host, path, client)?;
-    let path = fetch_addressbook_url(host, path, client)?;
-    Ok(path)
+fn report() -> Result<Method, CardError> {
+    Method::from_bytes(b"REPORT").map_err(|err| CardError::parse(r#"custom method "REPORT""#, err))
 }
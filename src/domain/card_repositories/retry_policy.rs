@@ -0,0 +1,95 @@
+//! Retry policy module.
+//!
+//! This module provides the retry policy applied by the remote card repository to idempotent
+//! requests, with an exponential backoff between attempts.
+
+use log::warn;
+use reqwest::{
+    blocking::{Client, Request, Response},
+    StatusCode,
+};
+use std::{thread, time::Duration};
+
+use crate::domain::CardError;
+
+/// Represents the retry policy of idempotent requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after each attempt.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Builds a policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Computes the delay to wait before the given retry (starting at 0).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Executes an idempotent request, retrying on network errors and on transient server
+    /// statuses (429, 502, 503 and 504). The caller is responsible for only passing idempotent
+    /// requests: GET, HEAD, PROPFIND, REPORT, or PUT and DELETE guarded by `If-Match`.
+    pub fn execute(&self, client: &Client, req: Request) -> Result<Response, CardError> {
+        let mut retry = 0;
+
+        loop {
+            let attempt = match req.try_clone() {
+                Some(attempt) if retry < self.max_retries => attempt,
+                // Either the last attempt or a streamed body that cannot be replayed.
+                _ => return Ok(client.execute(req)?),
+            };
+
+            let reason = match client.execute(attempt) {
+                Ok(res) if is_transient(res.status()) => format!("status {}", res.status()),
+                Ok(res) => return Ok(res),
+                Err(err) if err.is_connect() || err.is_timeout() || err.is_request() => {
+                    err.to_string()
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            let delay = self.delay(retry);
+            retry += 1;
+            warn!(
+                "request failed ({}), retrying in {:?} (retry {}/{})",
+                reason, delay, retry, self.max_retries
+            );
+            thread::sleep(delay);
+        }
+    }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
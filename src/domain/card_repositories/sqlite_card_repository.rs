@@ -0,0 +1,280 @@
+//! SQLite card repository module.
+//!
+//! This module provides a card repository storing the raw vCards in a single SQLite file, next
+//! to columns extracted from them (full name, emails, phone numbers, organization and revision).
+//! Their search text is indexed by a trigram full-text table, so that large addressbooks can be
+//! searched without scanning every card. The full name, organization and revision columns are
+//! not indexed, since no query filters or sorts on them.
+
+use chrono::{DateTime, Local};
+use log::debug;
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row, ToSql};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use crate::domain::{search_text, Card, CardError, CardFilter, CardRepository};
+
+/// Version of the schema, stored in the `user_version` pragma of the database.
+const SCHEMA_VERSION: i64 = 1;
+
+/// The search text is indexed by a full-text table matching any substring of at least 3
+/// characters. It refers to the cards by an explicit rowid, which `VACUUM` keeps.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS cards (
+        rowid INTEGER PRIMARY KEY,
+        id TEXT NOT NULL UNIQUE,
+        etag TEXT NOT NULL,
+        date TEXT NOT NULL,
+        raw TEXT NOT NULL,
+        fn TEXT NOT NULL,
+        emails TEXT NOT NULL,
+        tels TEXT NOT NULL,
+        org TEXT,
+        rev TEXT,
+        search TEXT NOT NULL
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS cards_fts USING fts5 (
+        search,
+        content = 'cards',
+        content_rowid = 'rowid',
+        tokenize = 'trigram'
+    );
+    CREATE TRIGGER IF NOT EXISTS cards_fts_insert AFTER INSERT ON cards BEGIN
+        INSERT INTO cards_fts (rowid, search) VALUES (new.rowid, new.search);
+    END;
+    CREATE TRIGGER IF NOT EXISTS cards_fts_delete AFTER DELETE ON cards BEGIN
+        INSERT INTO cards_fts (cards_fts, rowid, search) VALUES ('delete', old.rowid, old.search);
+    END;
+    CREATE TRIGGER IF NOT EXISTS cards_fts_update AFTER UPDATE ON cards BEGIN
+        INSERT INTO cards_fts (cards_fts, rowid, search) VALUES ('delete', old.rowid, old.search);
+        INSERT INTO cards_fts (rowid, search) VALUES (new.rowid, new.search);
+    END;
+";
+
+/// Minimum length of the queries the full-text index can answer.
+const MIN_INDEXED_QUERY_LEN: usize = 3;
+
+const SELECT: &str = "SELECT id, etag, date, raw FROM cards";
+
+/// Computes the etag of a card from its content.
+fn etag(raw: &str) -> String {
+    let digest: String = Sha256::digest(raw.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("\"{}\"", digest)
+}
+
+fn card_from_row(row: &Row) -> rusqlite::Result<Card> {
+    let date: String = row.get(2)?;
+    let date = DateTime::parse_from_rfc3339(&date)
+        .map(|date| date.with_timezone(&Local))
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(err)))?;
+    Ok(Card {
+        id: row.get(0)?,
+        etag: row.get(1)?,
+        date,
+        raw: row.get(3)?,
+    })
+}
+
+/// Represents the indexed columns of a card.
+struct CardColumns {
+    full_name: String,
+    emails: String,
+    tels: String,
+    org: Option<String>,
+    rev: Option<String>,
+    search: String,
+}
+
+impl From<&Card> for CardColumns {
+    fn from(card: &Card) -> Self {
+        let props = card.props();
+        let values = |name: &str| {
+            props
+                .iter()
+                .filter(|prop| prop.name == name)
+                .map(|prop| prop.text())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Self {
+            full_name: card.full_name(),
+            emails: values("EMAIL"),
+            tels: values("TEL"),
+            org: card.org(),
+            rev: props
+                .iter()
+                .find(|prop| prop.name == "REV")
+                .map(|prop| prop.text()),
+            search: search_text(card),
+        }
+    }
+}
+
+/// Card repository storing the cards in a SQLite database.
+pub struct SqliteCardRepository {
+    pub conn: Connection,
+}
+
+impl SqliteCardRepository {
+    /// Opens the database at the given path, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self, CardError> {
+        debug!("open card database {:?}", path);
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Opens a database living in memory, lost once closed.
+    pub fn open_in_memory() -> Result<Self, CardError> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(conn: Connection) -> Result<Self, CardError> {
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(CardError::parse(
+                "card database",
+                format!("unsupported schema version {}", version),
+            ));
+        }
+        if version < SCHEMA_VERSION {
+            debug!("migrate card database from version {}", version);
+            conn.execute_batch(SCHEMA)?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        Ok(Self { conn })
+    }
+
+    fn current_etag(&self, id: &str) -> Result<Option<String>, CardError> {
+        Ok(self
+            .conn
+            .query_row("SELECT etag FROM cards WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Checks that the card exists and that its etag, if any, is the current one.
+    fn check_etag(&self, card: &Card) -> Result<(), CardError> {
+        match (self.current_etag(&card.id)?, card.etag.as_deref()) {
+            (None, _) => Err(CardError::NotFound(card.id.clone())),
+            (Some(current), Some(etag)) if current != etag => Err(CardError::PreconditionFailed {
+                id: card.id.clone(),
+                current_etag: Some(current),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the card with the given date, then updates its etag and date.
+    fn write(&self, card: &mut Card, date: DateTime<Local>) -> Result<(), CardError> {
+        let columns = CardColumns::from(&*card);
+        let etag = etag(&card.raw);
+        self.conn.execute(
+            // An upsert rather than a replace, so that the update trigger keeps the full-text
+            // index in sync.
+            "INSERT INTO cards (id, etag, date, raw, fn, emails, tels, org, rev, search)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT (id) DO UPDATE SET
+                etag = excluded.etag, date = excluded.date, raw = excluded.raw, fn = excluded.fn,
+                emails = excluded.emails, tels = excluded.tels, org = excluded.org,
+                rev = excluded.rev, search = excluded.search",
+            params![
+                card.id,
+                etag,
+                date.to_rfc3339(),
+                card.raw,
+                columns.full_name,
+                columns.emails,
+                columns.tels,
+                columns.org,
+                columns.rev,
+                columns.search,
+            ],
+        )?;
+        card.etag = Some(etag);
+        card.date = date;
+        Ok(())
+    }
+
+    fn query(&self, sql: &str, params: &[&dyn ToSql]) -> Result<Vec<Card>, CardError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let cards = stmt
+            .query_map(params, card_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(cards)
+    }
+
+    /// Imports the cards in a single transaction, replacing the existing ones having the same
+    /// id. The cards keep their date. Returns the number of cards imported.
+    pub fn import(&self, cards: &mut [Card]) -> Result<usize, CardError> {
+        let tx = self.conn.unchecked_transaction()?;
+        for card in cards.iter_mut() {
+            let date = card.date;
+            self.write(card, date)?;
+        }
+        tx.commit()?;
+        Ok(cards.len())
+    }
+}
+
+impl CardRepository for SqliteCardRepository {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        if let Some(current_etag) = self.current_etag(&card.id)? {
+            return Err(CardError::PreconditionFailed {
+                id: card.id.clone(),
+                current_etag: Some(current_etag),
+            });
+        }
+        self.write(card, Local::now())
+    }
+
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        self.query(&format!("{} WHERE id = ?1", SELECT), &[&id])?
+            .pop()
+            .ok_or_else(|| CardError::NotFound(id.to_owned()))
+    }
+
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        self.query(&format!("{} ORDER BY id", SELECT), &[])
+    }
+
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        self.check_etag(card)?;
+        self.write(card, Local::now())
+    }
+
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        self.check_etag(card)?;
+        self.conn
+            .execute("DELETE FROM cards WHERE id = ?1", [&card.id])?;
+        Ok(())
+    }
+
+    /// Searches the cards through the full-text index. Queries too short for it are matched
+    /// by scanning the search text of every card.
+    fn search(&self, filter: &CardFilter) -> Result<Vec<Card>, CardError> {
+        let query = match filter.query.as_deref() {
+            Some(query) => query.to_lowercase(),
+            None => return self.read_all(),
+        };
+        if query.chars().count() < MIN_INDEXED_QUERY_LEN {
+            return self.query(
+                &format!("{} WHERE instr(search, ?1) > 0 ORDER BY id", SELECT),
+                &[&query],
+            );
+        }
+        // The query is quoted as a phrase, matched as a substring by the trigram tokenizer.
+        let phrase = format!(r#""{}""#, query.replace('"', r#""""#));
+        self.query(
+            &format!(
+                "{} WHERE rowid IN (SELECT rowid FROM cards_fts WHERE cards_fts MATCH ?1)
+                 ORDER BY id",
+                SELECT
+            ),
+            &[&phrase],
+        )
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use crate::domain::{Card, CardError, CardFilter, CardRepository, TelIndex};
+
+/// Card repository decorator removing the phone number index at the given path once a card is
+/// created, updated or deleted, so that the next lookup rebuilds it.
+pub struct TelIndexInvalidatingCardRepository<'a> {
+    pub repository: &'a dyn CardRepository,
+    pub path: PathBuf,
+}
+
+impl<'a> TelIndexInvalidatingCardRepository<'a> {
+    pub fn new(repository: &'a dyn CardRepository, path: PathBuf) -> Self {
+        Self { repository, path }
+    }
+}
+
+impl<'a> CardRepository for TelIndexInvalidatingCardRepository<'a> {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        self.repository.create(card)?;
+        TelIndex::invalidate(&self.path)
+    }
+
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        self.repository.read(id)
+    }
+
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        self.repository.read_all()
+    }
+
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        self.repository.update(card)?;
+        TelIndex::invalidate(&self.path)
+    }
+
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        self.repository.delete(card)?;
+        TelIndex::invalidate(&self.path)
+    }
+
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        self.repository.ctag()
+    }
+
+    fn read_if_none_match(&self, id: &str, etag: &str) -> Result<Option<Card>, CardError> {
+        self.repository.read_if_none_match(id, etag)
+    }
+
+    fn search(&self, filter: &CardFilter) -> Result<Vec<Card>, CardError> {
+        self.repository.search(filter)
+    }
+}
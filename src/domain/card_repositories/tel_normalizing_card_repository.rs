@@ -0,0 +1,68 @@
+use log::{debug, warn};
+
+use crate::domain::{Card, CardError, CardFilter, CardRepository, Region};
+
+/// Card repository decorator normalizing the phone numbers of the cards it creates or updates
+/// to E.164. Numbers that cannot be normalized are written as is.
+pub struct TelNormalizingCardRepository<'a> {
+    pub repository: &'a dyn CardRepository,
+    pub region: Option<Region>,
+}
+
+impl<'a> TelNormalizingCardRepository<'a> {
+    pub fn new(repository: &'a dyn CardRepository, region: Option<Region>) -> Self {
+        Self { repository, region }
+    }
+
+    fn normalize(&self, card: &mut Card) {
+        let report = card.normalize_tels(self.region);
+        for change in report.changes {
+            debug!(
+                r#"normalize phone number "{}" to "{}" in card "{}""#,
+                change.old, change.new, card.id
+            );
+        }
+        for skip in report.skipped {
+            warn!(
+                r#"cannot normalize phone number "{}" of card "{}": {}"#,
+                skip.value, card.id, skip.reason
+            );
+        }
+    }
+}
+
+impl<'a> CardRepository for TelNormalizingCardRepository<'a> {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        self.normalize(card);
+        self.repository.create(card)
+    }
+
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        self.repository.read(id)
+    }
+
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        self.repository.read_all()
+    }
+
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        self.normalize(card);
+        self.repository.update(card)
+    }
+
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        self.repository.delete(card)
+    }
+
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        self.repository.ctag()
+    }
+
+    fn read_if_none_match(&self, id: &str, etag: &str) -> Result<Option<Card>, CardError> {
+        self.repository.read_if_none_match(id, etag)
+    }
+
+    fn search(&self, filter: &CardFilter) -> Result<Vec<Card>, CardError> {
+        self.repository.search(filter)
+    }
+}
@@ -1,11 +1,27 @@
-use anyhow::Result;
-
-use crate::domain::Card;
+use crate::domain::{Card, CardError, CardFilter};
 
 pub trait CardRepository {
-    fn create(&self, card: &mut Card) -> Result<()>;
-    fn read(&self, id: &str) -> Result<Card>;
-    fn read_all(&self) -> Result<Vec<Card>>;
-    fn update(&self, card: &mut Card) -> Result<()>;
-    fn delete(&self, card: &Card) -> Result<()>;
+    fn create(&self, card: &mut Card) -> Result<(), CardError>;
+    fn read(&self, id: &str) -> Result<Card, CardError>;
+    fn read_all(&self) -> Result<Vec<Card>, CardError>;
+    fn update(&self, card: &mut Card) -> Result<(), CardError>;
+    fn delete(&self, card: &Card) -> Result<(), CardError>;
+
+    /// Returns the tag of the collection, which changes whenever one of its cards changes.
+    /// Returns `None` when the repository cannot tell.
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        Ok(None)
+    }
+
+    /// Reads a card unless its etag still matches the given one, in which case `None` is
+    /// returned.
+    fn read_if_none_match(&self, id: &str, _etag: &str) -> Result<Option<Card>, CardError> {
+        self.read(id).map(Some)
+    }
+
+    /// Reads the cards that may match the filter. Repositories able to narrow them down using
+    /// an index override it, callers still applying the filter to the result.
+    fn search(&self, _filter: &CardFilter) -> Result<Vec<Card>, CardError> {
+        self.read_all()
+    }
 }
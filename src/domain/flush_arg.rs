@@ -0,0 +1,40 @@
+//! Flush CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to the offline
+//! write queue.
+
+use anyhow::Result;
+use log::{debug, trace};
+
+type List = bool;
+
+/// Represents the flush commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd {
+    /// Represents the flush command, or the listing of the queued writes.
+    Flush(List),
+}
+
+/// Defines the flush command matcher.
+pub fn matches(m: &clap::ArgMatches) -> Result<Option<Cmd>> {
+    if let Some(m) = m.subcommand_matches("flush") {
+        debug!("flush subcommand matched");
+        let list = m.is_present("list");
+        trace!("list: {}", list);
+        return Ok(Some(Cmd::Flush(list)));
+    }
+
+    Ok(None)
+}
+
+/// Contains flush subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("flush")
+        .about("Replays the writes queued while the server could not be reached")
+        .arg(
+            clap::Arg::with_name("list")
+                .long("list")
+                .short("l")
+                .help("Lists the queued writes without replaying them"),
+        )]
+}
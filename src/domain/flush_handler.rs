@@ -0,0 +1,64 @@
+//! Flush handling module.
+//!
+//! This module gathers offline write queue actions triggered by the CLI.
+
+use anyhow::{Context, Error, Result};
+
+use crate::{config::Account, domain::CardError, output::OutputFmt};
+
+/// Lists the queued writes, oldest first.
+pub fn list(account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let writes = account.with_write_queue(|queue| Ok(queue.pending()?))?;
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            for write in writes {
+                println!(
+                    "{} {} {}",
+                    write.queued_at.format("%Y-%m-%d %H:%M"),
+                    write.op,
+                    write.card_id
+                );
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&writes)?),
+    }
+
+    Ok(())
+}
+
+/// Replays the queued writes. Fails when some of them are still pending.
+pub fn flush(account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let report =
+        account.with_write_queue(|queue| queue.flush().context("cannot replay queued writes"))?;
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            for write in &report.flushed {
+                println!("{} {}", write.op, write.card_id);
+            }
+            for conflict in &report.conflicts {
+                match conflict.saved_to.as_deref() {
+                    Some(path) => println!(
+                        "! {} {}: {} (local version saved to {})",
+                        conflict.write.op, conflict.write.card_id, conflict.reason, path
+                    ),
+                    None => println!(
+                        "! {} {}: {}",
+                        conflict.write.op, conflict.write.card_id, conflict.reason
+                    ),
+                }
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&report)?),
+    }
+
+    if !report.pending.is_empty() {
+        return Err(Error::new(CardError::Offline).context(format!(
+            "cannot replay queued writes ({} write(s) still pending)",
+            report.pending.len()
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Group CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to contact
+//! groups.
+
+use anyhow::Result;
+use log::{debug, trace};
+use std::convert::TryFrom;
+
+use crate::domain::GroupKind;
+
+type Group<'a> = &'a str;
+type Name<'a> = &'a str;
+type Ids<'a> = Vec<&'a str>;
+
+/// Represents the group commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the list groups command.
+    List,
+    /// Represents the show group members command.
+    Show(Group<'a>),
+    /// Represents the create group command.
+    Create(Name<'a>, Option<GroupKind>),
+    /// Represents the add group members command.
+    Add(Group<'a>, Ids<'a>),
+    /// Represents the remove group members command.
+    Remove(Group<'a>, Ids<'a>),
+}
+
+/// Defines the group command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    let m = match m.subcommand_matches("groups") {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+
+    if let Some(m) = m.subcommand_matches("show") {
+        debug!("show group subcommand matched");
+        let group = m.value_of("group").unwrap();
+        trace!("group: {}", group);
+        return Ok(Some(Cmd::Show(group)));
+    }
+
+    if let Some(m) = m.subcommand_matches("create") {
+        debug!("create group subcommand matched");
+        let name = m.value_of("name").unwrap();
+        trace!("name: {}", name);
+        let kind = m.value_of("vcard").map(GroupKind::try_from).transpose()?;
+        trace!("kind: {:?}", kind);
+        return Ok(Some(Cmd::Create(name, kind)));
+    }
+
+    if let Some(m) = m.subcommand_matches("add") {
+        debug!("add group members subcommand matched");
+        let group = m.value_of("group").unwrap();
+        trace!("group: {}", group);
+        let ids: Vec<&str> = m.values_of("ids").unwrap_or_default().collect();
+        trace!("ids: {:?}", ids);
+        return Ok(Some(Cmd::Add(group, ids)));
+    }
+
+    if let Some(m) = m.subcommand_matches("remove") {
+        debug!("remove group members subcommand matched");
+        let group = m.value_of("group").unwrap();
+        trace!("group: {}", group);
+        let ids: Vec<&str> = m.values_of("ids").unwrap_or_default().collect();
+        trace!("ids: {:?}", ids);
+        return Ok(Some(Cmd::Remove(group, ids)));
+    }
+
+    debug!("list groups subcommand matched");
+    Ok(Some(Cmd::List))
+}
+
+/// Contains group subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("groups")
+        .about("Lists the contact groups")
+        .subcommand(
+            clap::SubCommand::with_name("show")
+                .about("Shows the members of a group")
+                .arg(group_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("create")
+                .about("Creates an empty group")
+                .arg(
+                    clap::Arg::with_name("name")
+                        .help("Specifies the group name")
+                        .value_name("NAME")
+                        .required(true),
+                )
+                .arg(vcard_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("add")
+                .about("Adds cards to a group")
+                .arg(group_arg())
+                .arg(ids_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("remove")
+                .aliases(&["rm"])
+                .about("Removes cards from a group")
+                .arg(group_arg())
+                .arg(ids_arg()),
+        )]
+}
+
+/// Defines the group argument.
+pub fn group_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("group")
+        .help("Specifies the group, by card id or by name")
+        .value_name("GROUP")
+        .required(true)
+}
+
+/// Defines the card ids argument.
+pub fn ids_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("ids")
+        .help("Specifies the ids of the member cards")
+        .value_name("ID")
+        .multiple(true)
+        .required(true)
+}
+
+/// Defines the group vCard version argument.
+pub fn vcard_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("vcard")
+        .long("vcard")
+        .help("Creates a vCard 4.0 group, or a vCard 3.0 group as macOS and Nextcloud do [default: the version of the existing groups, else 4.0]")
+        .value_name("VERSION")
+        .possible_values(&["3.0", "4.0"])
+}
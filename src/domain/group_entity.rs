@@ -0,0 +1,190 @@
+//! Group module.
+//!
+//! This module reads and edits contact groups, which are cards themselves: vCard 4 groups have
+//! a `KIND:group` property and `MEMBER` properties, while the groups created by macOS and
+//! Nextcloud are vCard 3 cards with `X-ADDRESSBOOKSERVER-KIND:group` and
+//! `X-ADDRESSBOOKSERVER-MEMBER` properties. Members refer to the UID of their card.
+
+use anyhow::{anyhow, Error, Result};
+use chrono::Local;
+use serde::Serialize;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+use crate::domain::{escape, Card, VcardProp};
+
+const URN_UUID: &str = "urn:uuid:";
+
+/// Represents the flavor of a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupKind {
+    /// vCard 4 group, with `KIND` and `MEMBER` properties.
+    Vcard4,
+    /// vCard 3 group, with `X-ADDRESSBOOKSERVER-KIND` and `X-ADDRESSBOOKSERVER-MEMBER`
+    /// properties.
+    Apple,
+}
+
+impl GroupKind {
+    fn kind_prop(&self) -> &'static str {
+        match self {
+            Self::Vcard4 => "KIND",
+            Self::Apple => "X-ADDRESSBOOKSERVER-KIND",
+        }
+    }
+
+    fn member_prop(&self) -> &'static str {
+        match self {
+            Self::Vcard4 => "MEMBER",
+            Self::Apple => "X-ADDRESSBOOKSERVER-MEMBER",
+        }
+    }
+}
+
+impl TryFrom<&str> for GroupKind {
+    type Error = Error;
+
+    /// Parses the vCard version of the group.
+    fn try_from(version: &str) -> Result<Self, Self::Error> {
+        match version {
+            "4.0" | "4" => Ok(Self::Vcard4),
+            "3.0" | "3" => Ok(Self::Apple),
+            version => Err(anyhow!(r#"cannot parse group vCard version "{}""#, version)),
+        }
+    }
+}
+
+/// Represents a group card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Group {
+    pub card_id: String,
+    pub name: String,
+    pub kind: GroupKind,
+    /// UIDs of the members, without the `urn:uuid:` prefix.
+    pub members: Vec<String>,
+}
+
+/// Builds the value of a member property from the UID of the member.
+pub fn member_uri(uid: &str) -> String {
+    format!("{}{}", URN_UUID, strip_urn_uuid(uid))
+}
+
+/// Strips the `urn:uuid:` prefix of a UID or a member value, if any.
+pub fn strip_urn_uuid(uid: &str) -> &str {
+    let uid = uid.trim();
+    match uid.get(..URN_UUID.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(URN_UUID) => &uid[URN_UUID.len()..],
+        _ => uid,
+    }
+}
+
+fn is_member_prop(prop: &VcardProp) -> bool {
+    prop.name == GroupKind::Vcard4.member_prop() || prop.name == GroupKind::Apple.member_prop()
+}
+
+impl Group {
+    /// Reads the group of a card, if it is one.
+    pub fn from_card(card: &Card) -> Option<Self> {
+        let kind = card.group_kind()?;
+        let members = card
+            .props()
+            .iter()
+            .filter(|prop| is_member_prop(prop))
+            .map(|prop| strip_urn_uuid(&prop.value).to_owned())
+            .collect();
+        Some(Self {
+            card_id: card.id.clone(),
+            name: card.full_name(),
+            kind,
+            members,
+        })
+    }
+
+    /// Checks if the card is a member of the group.
+    pub fn has_member(&self, card: &Card) -> bool {
+        let uid = card.member_uid();
+        self.members
+            .iter()
+            .any(|member| member.eq_ignore_ascii_case(&uid))
+    }
+
+    /// Builds a new empty group card.
+    pub fn new_card(name: &str, kind: GroupKind) -> Card {
+        let uid = Uuid::new_v4().to_hyphenated().to_string();
+        let lines = match kind {
+            GroupKind::Vcard4 => vec![
+                "BEGIN:VCARD".to_owned(),
+                "VERSION:4.0".to_owned(),
+                format!("UID:{}", member_uri(&uid)),
+                "KIND:group".to_owned(),
+                format!("FN:{}", escape(name)),
+                "END:VCARD".to_owned(),
+            ],
+            GroupKind::Apple => vec![
+                "BEGIN:VCARD".to_owned(),
+                "VERSION:3.0".to_owned(),
+                format!("UID:{}", uid),
+                "X-ADDRESSBOOKSERVER-KIND:group".to_owned(),
+                format!("N:{}", escape(name)),
+                format!("FN:{}", escape(name)),
+                "END:VCARD".to_owned(),
+            ],
+        };
+        Card {
+            id: uid,
+            etag: None,
+            date: Local::now(),
+            raw: lines.join("\r\n") + "\r\n",
+        }
+    }
+}
+
+impl Card {
+    /// Returns the flavor of the group, if the card is one.
+    pub fn group_kind(&self) -> Option<GroupKind> {
+        [GroupKind::Vcard4, GroupKind::Apple]
+            .iter()
+            .find(|kind| {
+                self.props().iter().any(|prop| {
+                    prop.name == kind.kind_prop()
+                        && prop.text().trim().eq_ignore_ascii_case("group")
+                })
+            })
+            .copied()
+    }
+
+    /// Returns the UID other cards use to refer to this one, the card id when it has no UID.
+    pub fn member_uid(&self) -> String {
+        match self.uid() {
+            Some(uid) => strip_urn_uuid(&uid).to_owned(),
+            None => self.id.clone(),
+        }
+    }
+
+    /// Adds a member to the group card, unless it already is one. Returns whether the card
+    /// changed.
+    pub fn add_member(&mut self, uid: &str) -> bool {
+        let group = match Group::from_card(self) {
+            Some(group) => group,
+            None => return false,
+        };
+        let uid = strip_urn_uuid(uid);
+        if group.members.iter().any(|m| m.eq_ignore_ascii_case(uid)) {
+            return false;
+        }
+        self.push_prop(&format!("{}:{}", group.kind.member_prop(), member_uri(uid)));
+        true
+    }
+
+    /// Removes a member from the group card. Returns whether the card changed.
+    pub fn remove_member(&mut self, uid: &str) -> bool {
+        if self.group_kind().is_none() {
+            return false;
+        }
+        let uid = strip_urn_uuid(uid);
+        self.remove_props(|prop| {
+            is_member_prop(prop) && strip_urn_uuid(&prop.value).eq_ignore_ascii_case(uid)
+        }) > 0
+    }
+}
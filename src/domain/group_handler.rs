@@ -0,0 +1,192 @@
+//! Group handling module.
+//!
+//! This module gathers group actions triggered by the CLI.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::{
+    config::Account,
+    domain::{Card, CardError, CardRepository, Group, GroupKind},
+    output::OutputFmt,
+};
+
+/// Represents a member of a group, along with its card when it could be found.
+#[derive(Debug, Serialize)]
+struct Member<'a> {
+    uid: &'a str,
+    card_id: Option<&'a str>,
+    name: Option<String>,
+}
+
+/// Finds the position of the group card matching the given card id, else the given name.
+fn find_group(cards: &[Card], group: &str) -> Result<usize> {
+    let groups = || {
+        cards
+            .iter()
+            .enumerate()
+            .filter(|(_, card)| card.group_kind().is_some())
+    };
+    if let Some((i, _)) = groups().find(|(_, card)| card.id == group) {
+        return Ok(i);
+    }
+    let named: Vec<usize> = groups()
+        .filter(|(_, card)| card.full_name().eq_ignore_ascii_case(group))
+        .map(|(i, _)| i)
+        .collect();
+    match named.as_slice() {
+        [] => Err(anyhow!(r#"cannot find group "{}""#, group)),
+        [i] => Ok(*i),
+        _ => Err(anyhow!(
+            r#"cannot select group "{}": several groups share this name, use its card id instead"#,
+            group
+        )),
+    }
+}
+
+/// Finds the UIDs of the given cards. Ids of cards that cannot be found are taken as UIDs,
+/// so that dangling members can still be removed.
+fn member_uids(
+    repository: &dyn CardRepository,
+    ids: &[&str],
+    lenient: bool,
+) -> Result<Vec<String>> {
+    ids.iter()
+        .map(|id| match repository.read(id) {
+            Ok(card) => Ok(card.member_uid()),
+            Err(CardError::NotFound(_)) if lenient => Ok(id.to_string()),
+            Err(err) => Err(err.into()),
+        })
+        .collect()
+}
+
+/// Lists the groups of the account.
+pub fn list(account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let cards = account.with_repository(|repository| Ok(repository.read_all()?))?;
+    let mut groups: Vec<Group> = cards.iter().filter_map(Group::from_card).collect();
+    groups.sort_by_key(|group| group.name.to_lowercase());
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            if groups.is_empty() {
+                println!("No group found");
+            }
+            let width = groups
+                .iter()
+                .map(|group| group.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for group in groups {
+                println!(
+                    "{:width$}  {:>3} member(s)  {}",
+                    group.name,
+                    group.members.len(),
+                    group.card_id,
+                    width = width
+                );
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&groups)?),
+    }
+
+    Ok(())
+}
+
+/// Shows the members of a group.
+pub fn show(group: &str, account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let cards = account.with_repository(|repository| Ok(repository.read_all()?))?;
+    let group = Group::from_card(&cards[find_group(&cards, group)?]).unwrap();
+    let members: Vec<Member> = group
+        .members
+        .iter()
+        .map(|uid| {
+            let card = cards
+                .iter()
+                .find(|card| card.member_uid().eq_ignore_ascii_case(uid));
+            Member {
+                uid,
+                card_id: card.map(|card| card.id.as_str()),
+                name: card.map(Card::full_name),
+            }
+        })
+        .collect();
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            println!("{} ({} member(s))", group.name, members.len());
+            for member in members {
+                match (member.card_id, member.name) {
+                    (Some(id), Some(name)) => println!("{}  {}", id, name),
+                    _ => println!("{}  (card not found)", member.uid),
+                }
+            }
+        }
+        OutputFmt::Json => {
+            #[derive(Serialize)]
+            struct GroupMembers<'a> {
+                #[serde(flatten)]
+                group: &'a Group,
+                members: Vec<Member<'a>>,
+            }
+            let group = GroupMembers {
+                group: &group,
+                members,
+            };
+            println!("{}", serde_json::to_string(&group)?)
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates an empty group. Without explicit kind, the group follows the kind of the existing
+/// groups, so that it shows up in the same clients.
+pub fn create(name: &str, kind: Option<GroupKind>, account: &Account) -> Result<()> {
+    let id = account.with_writable_repository(|repository| {
+        let kind = match kind {
+            Some(kind) => kind,
+            None => repository
+                .read_all()?
+                .iter()
+                .find_map(Card::group_kind)
+                .unwrap_or(GroupKind::Vcard4),
+        };
+        let mut card = Group::new_card(name, kind);
+        repository.create(&mut card)?;
+        Ok(card.id)
+    })?;
+    println!(r#"Group "{}" created with id {}"#, name, id);
+    Ok(())
+}
+
+/// Adds cards to a group.
+pub fn add(group: &str, ids: &[&str], account: &Account) -> Result<()> {
+    let (name, added) = account.with_writable_repository(|repository| {
+        let mut cards = repository.read_all()?;
+        let mut card = cards.swap_remove(find_group(&cards, group)?);
+        let uids = member_uids(repository, ids, false)?;
+        let added = uids.iter().filter(|uid| card.add_member(uid)).count();
+        if added > 0 {
+            repository.update(&mut card)?;
+        }
+        Ok((card.full_name(), added))
+    })?;
+    println!(r#"{} member(s) added to group "{}""#, added, name);
+    Ok(())
+}
+
+/// Removes cards from a group.
+pub fn remove(group: &str, ids: &[&str], account: &Account) -> Result<()> {
+    let (name, removed) = account.with_writable_repository(|repository| {
+        let mut cards = repository.read_all()?;
+        let mut card = cards.swap_remove(find_group(&cards, group)?);
+        let uids = member_uids(repository, ids, true)?;
+        let removed = uids.iter().filter(|uid| card.remove_member(uid)).count();
+        if removed > 0 {
+            repository.update(&mut card)?;
+        }
+        Ok((card.full_name(), removed))
+    })?;
+    println!(r#"{} member(s) removed from group "{}""#, removed, name);
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! iCalendar module.
+//!
+//! This module exports the birthdays and anniversaries of cards as an iCalendar feed (RFC
+//! 5545), made of yearly recurring all-day events.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::{escape, fold, CardEvent, EventKind};
+
+/// Year used as start of the events whose year is unknown. It is a leap year, so that Feb 29
+/// exists.
+const UNKNOWN_YEAR: i32 = 2000;
+
+/// Returns the UID of the event, derived from the card id so that re-imports update the events
+/// instead of duplicating them.
+pub fn event_uid(event: &CardEvent) -> String {
+    let kind = match event.kind {
+        EventKind::Birthday => "birthday",
+        EventKind::Anniversary => "anniversary",
+    };
+    format!("{}-{}@cardamom", event.card_id, kind)
+}
+
+fn vevent(event: &CardEvent, alarm: Option<u32>, dtstamp: &str) -> Vec<String> {
+    let start = event.date.in_year(event.date.year.unwrap_or(UNKNOWN_YEAR));
+    let end = start + Duration::days(1);
+    let (summary, category) = match event.kind {
+        EventKind::Birthday => (format!("Birthday of {}", event.name), "BIRTHDAY"),
+        EventKind::Anniversary => (format!("Anniversary of {}", event.name), "ANNIVERSARY"),
+    };
+    // Feb 29 events fall on the last day of February in common years.
+    let rrule = if event.date.month == 2 && event.date.day == 29 {
+        "RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1"
+    } else {
+        "RRULE:FREQ=YEARLY"
+    };
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_owned(),
+        format!("UID:{}", event_uid(event)),
+        format!("DTSTAMP:{}", dtstamp),
+        format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")),
+        format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+        rrule.to_owned(),
+        format!("SUMMARY:{}", escape(&summary)),
+        format!("CATEGORIES:{}", category),
+        "TRANSP:TRANSPARENT".to_owned(),
+    ];
+    if let Some(year) = event.date.year {
+        lines.push(format!("DESCRIPTION:Since {}", year));
+    }
+    if let Some(days) = alarm {
+        let trigger = match days {
+            0 => "PT0S".to_owned(),
+            days => format!("-P{}D", days),
+        };
+        lines.extend(vec![
+            "BEGIN:VALARM".to_owned(),
+            "ACTION:DISPLAY".to_owned(),
+            format!("TRIGGER:{}", trigger),
+            format!("DESCRIPTION:{}", escape(&summary)),
+            "END:VALARM".to_owned(),
+        ]);
+    }
+    lines.push("END:VEVENT".to_owned());
+    lines
+}
+
+/// Builds the iCalendar feed of the events, with an optional reminder the given number of days
+/// before each of them.
+pub fn to_ics(events: &[CardEvent], alarm: Option<u32>, now: DateTime<Utc>) -> String {
+    let dtstamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        format!(
+            "PRODID:-//cardamom//cardamom {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+        "CALSCALE:GREGORIAN".to_owned(),
+        "METHOD:PUBLISH".to_owned(),
+        "X-WR-CALNAME:Birthdays".to_owned(),
+    ];
+    for event in events {
+        lines.extend(vevent(event, alarm, &dtstamp));
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
@@ -0,0 +1,68 @@
+//! Lookup CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to reverse
+//! lookups.
+
+use anyhow::{Context, Result};
+use log::{debug, trace};
+
+type Number<'a> = &'a str;
+type Refresh = bool;
+type MaxAge = u32;
+
+/// Represents the lookup commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the reverse phone lookup command.
+    Tel(Number<'a>, Refresh, MaxAge),
+}
+
+/// Defines the lookup command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    if let Some(m) = m.subcommand_matches("lookup-tel") {
+        debug!("lookup tel subcommand matched");
+        let number = m.value_of("number").unwrap();
+        trace!("number: {}", number);
+        let refresh = m.is_present("refresh");
+        trace!("refresh: {}", refresh);
+        let max_age = m.value_of("max-age").unwrap();
+        let max_age = max_age
+            .parse()
+            .with_context(|| format!(r#"cannot parse max age "{}""#, max_age))?;
+        trace!("max age: {}", max_age);
+        return Ok(Some(Cmd::Tel(number, refresh, max_age)));
+    }
+
+    Ok(None)
+}
+
+/// Contains lookup subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("lookup-tel")
+        .about("Finds the cards having a phone number, using a local index")
+        .arg(
+            clap::Arg::with_name("number")
+                .help("Specifies the phone number, in any format")
+                .value_name("NUMBER")
+                .required(true),
+        )
+        .arg(
+            clap::Arg::with_name("refresh")
+                .long("refresh")
+                .short("r")
+                .help("Rebuilds the index before the lookup"),
+        )
+        .arg(
+            clap::Arg::with_name("max-age")
+                .long("max-age")
+                .help("Rebuilds the index when it is older than this number of seconds")
+                .value_name("SECONDS")
+                .default_value("86400")
+                .validator(|max_age| {
+                    max_age
+                        .parse::<MaxAge>()
+                        .map(drop)
+                        .map_err(|err| format!("invalid number of seconds: {}", err))
+                }),
+        )]
+}
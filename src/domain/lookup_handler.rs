@@ -0,0 +1,65 @@
+//! Lookup handling module.
+//!
+//! This module gathers reverse lookup actions triggered by the CLI.
+
+use anyhow::{Error, Result};
+use chrono::{Duration, Utc};
+use log::{debug, warn};
+
+use crate::{
+    config::Account,
+    domain::{CardError, TelIndex, TEL_INDEX_FILE},
+    output::OutputFmt,
+};
+
+/// Loads the phone number index of the account, rebuilding it from the cards when it is
+/// missing, unreadable, too old or when asked to.
+fn load_index(account: &Account, refresh: bool, max_age: Duration) -> Result<TelIndex> {
+    let path = account.cache_dir()?.join(TEL_INDEX_FILE);
+    let now = Utc::now();
+
+    if !refresh && path.exists() {
+        match TelIndex::read(&path) {
+            Ok(index) if !index.is_older_than(max_age, now) => return Ok(index),
+            Ok(_) => debug!("phone number index {:?} is outdated", path),
+            Err(err) => warn!("{:#}", err),
+        }
+    }
+
+    debug!("build phone number index {:?}", path);
+    let cards = account.with_repository(|repository| Ok(repository.read_all()?))?;
+    let index = TelIndex::build(&cards, account.default_region(), now);
+    index.write(&path)?;
+    Ok(index)
+}
+
+/// Prints the cards having the given phone number.
+pub fn tel(
+    number: &str,
+    refresh: bool,
+    max_age: u32,
+    account: &Account,
+    output_fmt: OutputFmt,
+) -> Result<()> {
+    let index = load_index(account, refresh, Duration::seconds(i64::from(max_age)))?;
+    let entries = index.lookup(number, account.default_region());
+
+    if entries.is_empty() {
+        return Err(Error::new(CardError::NotFound(number.to_owned()))
+            .context(format!(r#"cannot find phone number "{}""#, number)));
+    }
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            for entry in entries {
+                match entry.org.as_deref() {
+                    Some(org) => println!("{} ({})", entry.name, org),
+                    None => println!("{}", entry.name),
+                }
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&entries)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Migrate CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to the migration
+//! of cards between a vdir and a SQLite account.
+
+use anyhow::Result;
+use log::{debug, trace};
+
+type Dir<'a> = &'a str;
+
+/// Represents the migrate commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the import from vdir command.
+    Import(Dir<'a>),
+    /// Represents the export to vdir command.
+    Export(Dir<'a>),
+}
+
+/// Defines the migrate command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    let m = match m.subcommand_matches("migrate") {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+
+    if let Some(m) = m.subcommand_matches("import") {
+        debug!("import vdir subcommand matched");
+        let dir = m.value_of("dir").unwrap();
+        trace!("dir: {}", dir);
+        return Ok(Some(Cmd::Import(dir)));
+    }
+
+    if let Some(m) = m.subcommand_matches("export") {
+        debug!("export vdir subcommand matched");
+        let dir = m.value_of("dir").unwrap();
+        trace!("dir: {}", dir);
+        return Ok(Some(Cmd::Export(dir)));
+    }
+
+    Ok(None)
+}
+
+/// Contains migrate subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("migrate")
+        .about("Migrates the cards of a SQLite account from or to a vdir")
+        .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            clap::SubCommand::with_name("import")
+                .about("Imports the cards of a vdir, replacing the ones having the same id")
+                .arg(dir_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("export")
+                .about("Exports the cards to a vdir, replacing the files having the same id")
+                .arg(dir_arg()),
+        )]
+}
+
+/// Defines the vdir directory argument.
+pub fn dir_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("dir")
+        .help("Specifies the vdir, a directory of .vcf files named after the card ids")
+        .value_name("DIR")
+        .required(true)
+}
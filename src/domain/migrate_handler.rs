@@ -0,0 +1,39 @@
+//! Migrate handling module.
+//!
+//! This module gathers migration actions triggered by the CLI.
+
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+use crate::{
+    config::{Account, SqliteAccount},
+    domain::{read_vdir, write_vdir, CardRepository},
+};
+
+fn sqlite_account(account: &Account) -> Result<&SqliteAccount> {
+    match account {
+        Account::Sqlite(account) => Ok(account),
+        account => Err(anyhow!(
+            r#"cannot migrate account "{}": only SQLite accounts can be migrated"#,
+            account.name()
+        )),
+    }
+}
+
+/// Imports the cards of the vdir into the SQLite account.
+pub fn import(dir: &str, account: &Account) -> Result<()> {
+    let repository = sqlite_account(account)?.repository()?;
+    let mut cards = read_vdir(Path::new(dir))?;
+    let count = repository.import(&mut cards)?;
+    println!("{} card(s) imported from {}", count, dir);
+    Ok(())
+}
+
+/// Exports the cards of the SQLite account to the vdir.
+pub fn export(dir: &str, account: &Account) -> Result<()> {
+    let repository = sqlite_account(account)?.repository()?;
+    let cards = repository.read_all()?;
+    let count = write_vdir(Path::new(dir), &cards)?;
+    println!("{} card(s) exported to {}", count, dir);
+    Ok(())
+}
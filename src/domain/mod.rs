@@ -1,15 +1,81 @@
+pub mod birthday_arg;
+pub mod birthday_handler;
 pub mod card_arg;
 pub mod card_handler;
+pub mod flush_arg;
+pub mod flush_handler;
+pub mod group_arg;
+pub mod group_handler;
+pub mod lookup_arg;
+pub mod lookup_handler;
+pub mod migrate_arg;
+pub mod migrate_handler;
+pub mod normalize_arg;
+pub mod normalize_handler;
+pub mod tag_arg;
+pub mod tag_handler;
+
+pub mod birthday_entity;
+pub use birthday_entity::*;
 
 pub mod card_entity;
 pub use card_entity::*;
 
+pub mod card_error;
+pub use card_error::*;
+
 pub mod card_repository;
 pub use card_repository::*;
 
+pub mod group_entity;
+pub use group_entity::*;
+
+pub mod ics_entity;
+pub use ics_entity::*;
+
+pub mod private_file_entity;
+pub use private_file_entity::*;
+
+pub mod search_entity;
+pub use search_entity::*;
+
+pub mod tag_entity;
+
+pub mod tel_entity;
+pub use tel_entity::*;
+
+pub mod tel_index_entity;
+pub use tel_index_entity::*;
+
+pub mod vcard_entity;
+pub use vcard_entity::*;
+
+pub mod vdir_entity;
+pub use vdir_entity::*;
+
+pub mod write_queue_entity;
+pub use write_queue_entity::*;
+
 pub mod card_repositories {
+    pub mod caching_card_repository;
+    pub use caching_card_repository::*;
+    pub mod digest_auth;
+    pub use digest_auth::*;
     pub mod local_card_repository;
+    pub mod multistatus;
     pub use local_card_repository::*;
+    pub mod queueing_card_repository;
+    pub use queueing_card_repository::*;
+    pub mod remote_auth;
+    pub use remote_auth::*;
     pub mod remote_card_repository;
     pub use remote_card_repository::*;
+    pub mod retry_policy;
+    pub use retry_policy::*;
+    pub mod sqlite_card_repository;
+    pub use sqlite_card_repository::*;
+    pub mod tel_index_invalidating_card_repository;
+    pub use tel_index_invalidating_card_repository::*;
+    pub mod tel_normalizing_card_repository;
+    pub use tel_normalizing_card_repository::*;
 }
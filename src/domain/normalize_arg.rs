@@ -0,0 +1,67 @@
+//! Normalize CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to the
+//! normalization of cards.
+
+use anyhow::Result;
+use log::{debug, trace};
+
+use crate::domain::card_arg::{target, target_args, target_group, Target};
+
+type Region<'a> = Option<&'a str>;
+type DryRun = bool;
+
+/// Represents the normalize commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the normalize phone numbers command.
+    Tel(Target<'a>, Region<'a>, DryRun),
+}
+
+/// Defines the normalize command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    if let Some(m) = m.subcommand_matches("normalize") {
+        if m.is_present("tel") {
+            debug!("normalize phone numbers subcommand matched");
+            let region = m.value_of("region");
+            trace!("region: {:?}", region);
+            let dry_run = m.is_present("dry-run");
+            trace!("dry run: {}", dry_run);
+            return Ok(Some(Cmd::Tel(target(m), region, dry_run)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Contains normalize subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("normalize")
+        .about("Normalizes the values of cards, every card by default")
+        .arg(
+            clap::Arg::with_name("tel")
+                .long("tel")
+                .help("Normalizes the phone numbers to E.164, like +33606060606"),
+        )
+        .group(
+            clap::ArgGroup::with_name("fields")
+                .args(&["tel"])
+                .multiple(true)
+                .required(true),
+        )
+        .arg(
+            clap::Arg::with_name("region")
+                .long("region")
+                .short("r")
+                .help("Reads national numbers as numbers of this region, like FR [default: the default-region of the account]")
+                .value_name("REGION"),
+        )
+        .arg(
+            clap::Arg::with_name("dry-run")
+                .long("dry-run")
+                .short("n")
+                .help("Shows the changes without writing them"),
+        )
+        .args(&target_args(false))
+        .group(target_group(false))]
+}
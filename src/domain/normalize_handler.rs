@@ -0,0 +1,107 @@
+//! Normalize handling module.
+//!
+//! This module gathers normalization actions triggered by the CLI.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::{
+    config::Account,
+    domain::{card_arg::Target, card_handler::select, parse_region, CardRepository, TelReport},
+    output::OutputFmt,
+};
+
+/// Represents the normalization of the phone numbers of a card.
+#[derive(Debug, Serialize)]
+struct CardTelReport {
+    id: String,
+    name: String,
+    #[serde(flatten)]
+    report: TelReport,
+}
+
+/// Represents the normalization of the phone numbers of several cards.
+#[derive(Debug, Serialize)]
+struct TelSummary {
+    cards: Vec<CardTelReport>,
+    updated: usize,
+    dry_run: bool,
+}
+
+/// Normalizes the phone numbers of the targeted cards to E.164, or only shows the changes
+/// in dry run mode.
+pub fn tel(
+    target: &Target,
+    region: Option<&str>,
+    dry_run: bool,
+    account: &Account,
+    output_fmt: OutputFmt,
+) -> Result<()> {
+    let region = match region {
+        Some(region) => Some(parse_region(region)?),
+        None => account.default_region(),
+    };
+
+    let normalize = |repository: &dyn CardRepository| {
+        let mut summary = TelSummary {
+            cards: vec![],
+            updated: 0,
+            dry_run,
+        };
+        for mut card in select(repository, target)? {
+            let report = card.normalize_tels(region);
+            if report.changes.is_empty() && report.skipped.is_empty() {
+                continue;
+            }
+            if !dry_run && !report.changes.is_empty() {
+                repository.update(&mut card).with_context(|| {
+                    format!(
+                        r#"cannot update card "{}" ({} card(s) already updated)"#,
+                        card.id, summary.updated
+                    )
+                })?;
+                summary.updated += 1;
+            }
+            summary.cards.push(CardTelReport {
+                id: card.id.clone(),
+                name: card.full_name(),
+                report,
+            });
+        }
+        Ok(summary)
+    };
+    // Dry runs are read-only, they leave the queued writes alone.
+    let summary = if dry_run {
+        account.with_repository(normalize)?
+    } else {
+        account.with_writable_repository(normalize)?
+    };
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            for card in &summary.cards {
+                println!("{} ({})", card.id, card.name);
+                for change in &card.report.changes {
+                    println!("- TEL:{}", change.old);
+                    println!("+ TEL:{}", change.new);
+                }
+                for skip in &card.report.skipped {
+                    println!("! TEL:{}  ({})", skip.value, skip.reason);
+                }
+            }
+            let changed = summary
+                .cards
+                .iter()
+                .filter(|card| !card.report.changes.is_empty())
+                .count();
+            if dry_run {
+                println!("{} card(s) would be updated", changed);
+            } else {
+                println!("{} card(s) updated", summary.updated);
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&summary)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Private file module.
+//!
+//! This module writes the files holding credentials or personal data, like the netrc file or
+//! the card cache, so that other users cannot read them.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Writes the file through a temporary file readable by the user only, renamed over the file so
+/// that its content is never exposed nor partially written.
+pub fn write_private_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let tmp = path.with_file_name(format!(
+        ".{}.tmp",
+        path.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    ));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    // A temporary file left by an interrupted write would make the creation fail.
+    fs::remove_file(&tmp).ok();
+    let mut file = options.open(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
@@ -0,0 +1,54 @@
+//! Search module.
+//!
+//! This module selects cards matching a search query and a tag.
+
+use crate::domain::Card;
+
+/// Properties looked up by search queries.
+const SEARCH_PROPS: [&str; 6] = ["FN", "N", "NICKNAME", "ORG", "EMAIL", "TEL"];
+
+/// Represents a card filter. An empty filter matches every card.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CardFilter {
+    /// Text to find, case-insensitively, in the id, names, organization, emails or phone
+    /// numbers of the card.
+    pub query: Option<String>,
+    /// Tag the card must have.
+    pub tag: Option<String>,
+}
+
+/// Returns the lowercase text search queries are looked up in: the id of the card and the
+/// values of its searched properties, one per line.
+pub fn search_text(card: &Card) -> String {
+    let mut lines = vec![card.id.to_lowercase()];
+    lines.extend(
+        card.props()
+            .iter()
+            .filter(|prop| SEARCH_PROPS.contains(&prop.name.as_str()))
+            .map(|prop| prop.text().to_lowercase()),
+    );
+    lines.join("\n")
+}
+
+impl CardFilter {
+    /// Checks if the card matches the filter.
+    pub fn matches(&self, card: &Card) -> bool {
+        if let Some(tag) = &self.tag {
+            if !card.has_tag(tag) {
+                return false;
+            }
+        }
+        match &self.query {
+            Some(query) => {
+                let query = query.to_lowercase();
+                card.id.to_lowercase().contains(&query)
+                    || card
+                        .props()
+                        .iter()
+                        .filter(|prop| SEARCH_PROPS.contains(&prop.name.as_str()))
+                        .any(|prop| prop.text().to_lowercase().contains(&query))
+            }
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Tag CLI module.
+//!
+//! This module provides subcommands, arguments and a command matcher related to the tags of
+//! cards.
+
+use anyhow::Result;
+use log::{debug, trace};
+
+use crate::domain::card_arg::{target, target_args, target_group, Target};
+
+type Tag<'a> = &'a str;
+type Tags<'a> = Vec<&'a str>;
+
+/// Represents the tag commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the list tags command.
+    List(Target<'a>),
+    /// Represents the add tags command.
+    Add(Tags<'a>, Target<'a>),
+    /// Represents the remove tags command.
+    Remove(Tags<'a>, Target<'a>),
+    /// Represents the rename tag command.
+    Rename(Tag<'a>, Tag<'a>, Target<'a>),
+}
+
+/// Defines the tag command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    let m = match m.subcommand_matches("tag") {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+
+    if let Some(m) = m.subcommand_matches("list") {
+        debug!("list tags subcommand matched");
+        return Ok(Some(Cmd::List(target(m))));
+    }
+
+    if let Some(m) = m.subcommand_matches("add") {
+        debug!("add tags subcommand matched");
+        let tags: Vec<&str> = m.values_of("tags").unwrap_or_default().collect();
+        trace!("tags: {:?}", tags);
+        return Ok(Some(Cmd::Add(tags, target(m))));
+    }
+
+    if let Some(m) = m.subcommand_matches("remove") {
+        debug!("remove tags subcommand matched");
+        let tags: Vec<&str> = m.values_of("tags").unwrap_or_default().collect();
+        trace!("tags: {:?}", tags);
+        return Ok(Some(Cmd::Remove(tags, target(m))));
+    }
+
+    if let Some(m) = m.subcommand_matches("rename") {
+        debug!("rename tag subcommand matched");
+        let from = m.value_of("from").unwrap();
+        trace!("from: {}", from);
+        let to = m.value_of("to").unwrap();
+        trace!("to: {}", to);
+        return Ok(Some(Cmd::Rename(from, to, target(m))));
+    }
+
+    Ok(None)
+}
+
+/// Contains tag subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("tag")
+        .aliases(&["tags"])
+        .about("Manages the tags of cards, stored as categories")
+        .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            clap::SubCommand::with_name("list")
+                .aliases(&["ls"])
+                .about("Lists the tags with their number of cards")
+                .args(&target_args(false))
+                .group(target_group(false)),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("add")
+                .about("Adds tags to cards")
+                .arg(tags_arg())
+                .args(&target_args(true))
+                .group(target_group(true)),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("remove")
+                .aliases(&["rm"])
+                .about("Removes tags from cards")
+                .arg(tags_arg())
+                .args(&target_args(true))
+                .group(target_group(true)),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("rename")
+                .about("Renames a tag, on every card by default")
+                .arg(
+                    clap::Arg::with_name("from")
+                        .help("Specifies the current tag")
+                        .value_name("TAG")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("to")
+                        .help("Specifies the new tag")
+                        .value_name("NEW_TAG")
+                        .required(true),
+                )
+                .args(&target_args(false))
+                .group(target_group(false)),
+        )]
+}
+
+/// Defines the tags argument.
+pub fn tags_arg<'a>() -> clap::Arg<'a, 'a> {
+    clap::Arg::with_name("tags")
+        .help("Specifies the tags")
+        .value_name("TAG")
+        .multiple(true)
+        .required(true)
+}
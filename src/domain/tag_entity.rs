@@ -0,0 +1,104 @@
+//! Tag module.
+//!
+//! This module reads and edits the tags of cards, stored in the `CATEGORIES` property as a
+//! comma-separated list. Tags are compared case-insensitively.
+
+use std::mem;
+
+use crate::domain::{escape, split_unescaped, unescape, Card};
+
+fn same_tag(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+impl Card {
+    /// Returns the tags of the card, from all its `CATEGORIES` properties, without duplicates.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+        for prop in self.props().iter().filter(|prop| prop.name == "CATEGORIES") {
+            for tag in split_unescaped(&prop.value, ',') {
+                let tag = unescape(&tag).trim().to_owned();
+                if !tag.is_empty() && !tags.iter().any(|t| same_tag(t, &tag)) {
+                    tags.push(tag);
+                }
+            }
+        }
+        tags
+    }
+
+    /// Checks if the card has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().iter().any(|t| same_tag(t, tag))
+    }
+
+    /// Replaces the tags of the card by a single `CATEGORIES` property, removed when there is no
+    /// tag left. The first property is rewritten in place, keeping its group and parameters.
+    pub fn set_tags(&mut self, tags: &[String]) {
+        if tags.is_empty() {
+            self.remove_props(|prop| prop.name == "CATEGORIES");
+            return;
+        }
+        let tags: Vec<String> = tags.iter().map(|tag| escape(tag)).collect();
+        let value = tags.join(",");
+        let mut first = true;
+        let replaced = self.replace_prop_values(|prop| {
+            if prop.name == "CATEGORIES" && first {
+                first = false;
+                Some(value.clone())
+            } else {
+                None
+            }
+        });
+        if replaced == 0 {
+            self.push_prop(&format!("CATEGORIES:{}", value));
+        } else {
+            let mut kept = false;
+            self.remove_props(|prop| prop.name == "CATEGORIES" && mem::replace(&mut kept, true));
+        }
+    }
+
+    /// Adds a tag to the card, unless it already has it. Returns whether the card changed.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let mut tags = self.tags();
+        if tag.trim().is_empty() || tags.iter().any(|t| same_tag(t, tag)) {
+            return false;
+        }
+        tags.push(tag.trim().to_owned());
+        self.set_tags(&tags);
+        true
+    }
+
+    /// Removes a tag from the card. Returns whether the card changed.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let mut tags = self.tags();
+        let len = tags.len();
+        tags.retain(|t| !same_tag(t, tag));
+        if tags.len() == len {
+            return false;
+        }
+        self.set_tags(&tags);
+        true
+    }
+
+    /// Renames a tag of the card, merging it with the new one if the card already has it.
+    /// Returns whether the card changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> bool {
+        let tags = self.tags();
+        if !tags.iter().any(|t| same_tag(t, from)) || to.trim().is_empty() {
+            return false;
+        }
+        let mut renamed: Vec<String> = vec![];
+        for tag in tags {
+            let tag = if same_tag(&tag, from) {
+                to.trim().to_owned()
+            } else {
+                tag
+            };
+            if !renamed.iter().any(|t| same_tag(t, &tag)) {
+                renamed.push(tag);
+            }
+        }
+        self.set_tags(&renamed);
+        true
+    }
+}
@@ -0,0 +1,84 @@
+//! Tag handling module.
+//!
+//! This module gathers tag actions triggered by the CLI.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    config::Account,
+    domain::{
+        card_arg::Target,
+        card_handler::{edit, select},
+        Card,
+    },
+    output::OutputFmt,
+};
+
+/// Represents a tag along with its number of cards.
+#[derive(Debug, Serialize)]
+struct TagCount {
+    tag: String,
+    cards: usize,
+}
+
+/// Lists the tags of the targeted cards, with their number of cards.
+pub fn list(target: &Target, account: &Account, output_fmt: OutputFmt) -> Result<()> {
+    let cards = account.with_repository(|repository| select(repository, target))?;
+
+    // Tags are grouped case-insensitively, the first spelling found wins.
+    let mut tags: BTreeMap<String, TagCount> = BTreeMap::new();
+    for tag in cards.iter().flat_map(Card::tags) {
+        tags.entry(tag.to_lowercase())
+            .or_insert(TagCount { tag, cards: 0 })
+            .cards += 1;
+    }
+    let tags: Vec<TagCount> = tags.into_values().collect();
+
+    match output_fmt {
+        OutputFmt::Plain => {
+            if tags.is_empty() {
+                println!("No tag found");
+            }
+            let width = tags
+                .iter()
+                .map(|tag| tag.tag.chars().count())
+                .max()
+                .unwrap_or(0);
+            for tag in tags {
+                println!("{:width$}  {} card(s)", tag.tag, tag.cards, width = width);
+            }
+        }
+        OutputFmt::Json => println!("{}", serde_json::to_string(&tags)?),
+    }
+
+    Ok(())
+}
+
+/// Adds tags to the targeted cards.
+pub fn add(tags: &[&str], target: &Target, account: &Account) -> Result<()> {
+    let updated = edit(target, account, |card| {
+        tags.iter()
+            .fold(false, |changed, tag| card.add_tag(tag) | changed)
+    })?;
+    println!("{} card(s) updated", updated);
+    Ok(())
+}
+
+/// Removes tags from the targeted cards.
+pub fn remove(tags: &[&str], target: &Target, account: &Account) -> Result<()> {
+    let updated = edit(target, account, |card| {
+        tags.iter()
+            .fold(false, |changed, tag| card.remove_tag(tag) | changed)
+    })?;
+    println!("{} card(s) updated", updated);
+    Ok(())
+}
+
+/// Renames a tag of the targeted cards.
+pub fn rename(from: &str, to: &str, target: &Target, account: &Account) -> Result<()> {
+    let updated = edit(target, account, |card| card.rename_tag(from, to))?;
+    println!("{} card(s) updated", updated);
+    Ok(())
+}
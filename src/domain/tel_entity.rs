@@ -0,0 +1,105 @@
+//! Phone number module.
+//!
+//! This module normalizes the `TEL` values of cards to E.164, like `+33606060606`, using the
+//! numbering plans of the libphonenumber metadata embedded in the binary. National numbers
+//! need a default region to be understood.
+
+use anyhow::{anyhow, Result};
+use phonenumber::{country, Mode};
+use serde::Serialize;
+
+use crate::domain::Card;
+
+/// Represents a region of the numbering plan, as an ISO 3166-1 alpha-2 code like `FR`.
+pub type Region = country::Id;
+
+/// Parses a region code, case-insensitively.
+pub fn parse_region(region: &str) -> Result<Region> {
+    region
+        .trim()
+        .to_uppercase()
+        .parse()
+        .map_err(|_| anyhow!(r#"cannot parse phone region "{}""#, region))
+}
+
+/// Represents a phone number rewritten to E.164.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelChange {
+    pub old: String,
+    pub new: String,
+}
+
+/// Represents a phone number left untouched, with the reason why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelSkip {
+    pub value: String,
+    pub reason: String,
+}
+
+/// Represents the outcome of the normalization of the phone numbers of a card.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TelReport {
+    pub changes: Vec<TelChange>,
+    pub skipped: Vec<TelSkip>,
+}
+
+/// Normalizes a `TEL` value to E.164, keeping the `tel:` scheme of vCard 4 URIs. Returns
+/// `None` when the value is already normalized, and the reason why when it cannot be.
+/// Numbers with an extension are left untouched, since E.164 cannot hold it.
+pub fn normalize_tel(value: &str, region: Option<Region>) -> Result<Option<String>, String> {
+    let (scheme, number) = match value.get(..4) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("tel:") => value.split_at(4),
+        _ => ("", value),
+    };
+    let number = number.trim();
+    if region.is_none() && !number.starts_with('+') {
+        return Err("national number without default region".into());
+    }
+
+    let parsed = phonenumber::parse(region, number)
+        .map_err(|err| format!("cannot parse number: {}", err))?;
+    if parsed.extension().is_some() {
+        return Err("number with extension".into());
+    }
+    if !parsed.is_valid() {
+        return Err("invalid number".into());
+    }
+
+    let normalized = format!("{}{}", scheme, parsed.format().mode(Mode::E164));
+    Ok(if normalized == value {
+        None
+    } else {
+        Some(normalized)
+    })
+}
+
+impl Card {
+    /// Normalizes the `TEL` values of the card to E.164. Values that cannot be normalized are
+    /// left untouched and reported as skipped.
+    pub fn normalize_tels(&mut self, region: Option<Region>) -> TelReport {
+        let mut report = TelReport::default();
+        self.replace_prop_values(|prop| {
+            if prop.name != "TEL" || prop.value.trim().is_empty() {
+                return None;
+            }
+            match normalize_tel(&prop.value, region) {
+                Ok(Some(new)) => {
+                    report.changes.push(TelChange {
+                        old: prop.value.clone(),
+                        new: new.clone(),
+                    });
+                    Some(new)
+                }
+                Ok(None) => None,
+                Err(reason) => {
+                    report.skipped.push(TelSkip {
+                        value: prop.value.clone(),
+                        reason,
+                    });
+                    None
+                }
+            }
+        });
+        report
+    }
+}
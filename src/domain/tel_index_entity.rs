@@ -0,0 +1,155 @@
+//! Phone number index module.
+//!
+//! This module provides an index of the phone numbers of the cards of an account, stored in
+//! its cache directory so that reverse lookups do not need to fetch every card.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+use crate::domain::{normalize_tel, write_private_file, Card, CardError, Region};
+
+/// File name of the index, in the cache directory of the account.
+pub const TEL_INDEX_FILE: &str = "tel-index.json";
+
+/// Minimum number of digits two numbers must share to match by suffix, so that short
+/// extensions do not match every number ending with them.
+const MIN_SUFFIX_LEN: usize = 7;
+
+/// Represents a phone number of a card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelIndexEntry {
+    /// Digits of the number, in E.164 when it could be normalized.
+    pub digits: String,
+    /// Value of the `TEL` property, as written in the card.
+    pub tel: String,
+    pub card_id: String,
+    pub name: String,
+    pub org: Option<String>,
+}
+
+/// Represents the phone number index of an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelIndex {
+    pub built_at: DateTime<Utc>,
+    pub entries: Vec<TelIndexEntry>,
+}
+
+/// Returns the digits identifying a phone number: the E.164 digits when it can be normalized,
+/// else its digits without the leading zeros of the national trunk prefix.
+pub fn tel_digits(value: &str, region: Option<Region>) -> String {
+    let value = match normalize_tel(value, region) {
+        Ok(Some(normalized)) => normalized,
+        _ => value.to_owned(),
+    };
+    value
+        .split(";ext=")
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>()
+        .trim_start_matches('0')
+        .to_owned()
+}
+
+impl TelIndex {
+    /// Builds the index of the phone numbers of the cards.
+    pub fn build(cards: &[Card], region: Option<Region>, now: DateTime<Utc>) -> Self {
+        let mut entries = vec![];
+        for card in cards {
+            let props = card.props();
+            let name = card.full_name();
+            let org = card.org();
+            for prop in props.iter().filter(|prop| prop.name == "TEL") {
+                let tel = prop.text();
+                let digits = tel_digits(&tel, region);
+                if digits.is_empty() {
+                    continue;
+                }
+                entries.push(TelIndexEntry {
+                    digits,
+                    tel,
+                    card_id: card.id.clone(),
+                    name: name.clone(),
+                    org: org.clone(),
+                });
+            }
+        }
+        Self {
+            built_at: now,
+            entries,
+        }
+    }
+
+    /// Finds the entries matching the number, one per card. Exact matches win over suffix
+    /// matches, which allow national numbers to match international ones.
+    pub fn lookup(&self, number: &str, region: Option<Region>) -> Vec<&TelIndexEntry> {
+        let digits = tel_digits(number, region);
+        if digits.is_empty() {
+            return vec![];
+        }
+
+        let exact: Vec<&TelIndexEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.digits == digits)
+            .collect();
+        let mut matches = if exact.is_empty() {
+            self.entries
+                .iter()
+                .filter(|entry| {
+                    let (short, long) = if entry.digits.len() < digits.len() {
+                        (&entry.digits, &digits)
+                    } else {
+                        (&digits, &entry.digits)
+                    };
+                    short.len() >= MIN_SUFFIX_LEN && long.ends_with(short.as_str())
+                })
+                .collect()
+        } else {
+            exact
+        };
+
+        let mut card_ids = vec![];
+        matches.retain(|entry| {
+            let is_new = !card_ids.contains(&&entry.card_id);
+            card_ids.push(&entry.card_id);
+            is_new
+        });
+        matches
+    }
+
+    /// Checks if the index was built more than the given duration ago.
+    pub fn is_older_than(&self, max_age: Duration, now: DateTime<Utc>) -> bool {
+        now - self.built_at > max_age
+    }
+
+    /// Reads the index from the given file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("cannot read phone number index {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("cannot parse phone number index {:?}", path))
+    }
+
+    /// Removes the index at the given path, if any.
+    pub fn invalidate(path: &Path) -> Result<(), CardError> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the index to the given file, creating its directory if needed.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create cache directory {:?}", dir))?;
+        }
+        // The index holds personal data, hidden from other users.
+        write_private_file(path, serde_json::to_string(self)?.as_bytes())
+            .with_context(|| format!("cannot write phone number index {:?}", path))
+    }
+}
@@ -0,0 +1,342 @@
+//! vCard module.
+//!
+//! This module provides a minimal reader of the content lines of a vCard (RFC 2426 and RFC
+//! 6350), enough to inspect the raw content of cards without a full vCard model.
+
+use crate::domain::Card;
+
+/// Represents a content line of a vCard, like `item1.TEL;TYPE=cell:+33 6 12 34 56 78`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VcardProp {
+    pub group: Option<String>,
+    /// Name of the property, in uppercase.
+    pub name: String,
+    /// Parameters of the property, names in uppercase and values unquoted.
+    pub params: Vec<(String, String)>,
+    /// Raw value of the property, still escaped.
+    pub value: String,
+}
+
+/// Unfolds the content lines of a vCard: lines starting with a space or a tab continue the
+/// previous one.
+pub fn unfold(raw: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in raw.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(cont), Some(last)) => last.push_str(cont),
+            _ if line.is_empty() => (),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+/// Folds a content line at 75 octets, without splitting UTF-8 chars, and ends it with CRLF.
+/// Also used for iCalendar lines, which follow the same rules.
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Unescapes a text value: `\n`, `\,`, `\;` and `\\`.
+pub fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some(c @ (',' | ';' | ':' | '\\'))) => {
+                unescaped.push(c);
+                chars.next();
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Escapes a text value, the opposite of [`unescape`].
+pub fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// Splits a value at the given separator, ignoring escaped ones.
+pub fn split_unescaped(value: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            parts.last_mut().unwrap().push(c);
+            parts.last_mut().unwrap().extend(chars.next());
+        } else if c == sep {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+    parts
+}
+
+impl VcardProp {
+    /// Parses an unfolded content line.
+    pub fn parse(line: &str) -> Option<Self> {
+        // The value starts at the first colon outside of a quoted parameter value.
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+        let mut parts = split_quoted(head, ';').into_iter();
+        let name = parts.next()?;
+        let (group, name) = match name.split_once('.') {
+            Some((group, name)) => (Some(group.to_owned()), name),
+            None => (None, name.as_str()),
+        };
+        if name.is_empty() {
+            return None;
+        }
+
+        let params = parts
+            .map(|param| match param.split_once('=') {
+                Some((key, val)) => (key.trim().to_uppercase(), val.replace('"', "")),
+                // vCard 2.1 style parameters, like `TEL;CELL:…`.
+                None => ("TYPE".into(), param),
+            })
+            .collect();
+
+        Some(Self {
+            group,
+            name: name.trim().to_uppercase(),
+            params,
+            value: value.to_owned(),
+        })
+    }
+
+    /// Returns the value of the given parameter, if any.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val.as_str())
+    }
+
+    /// Checks if the given parameter contains the given value, like `TYPE=work,voice`.
+    pub fn has_param(&self, name: &str, value: &str) -> bool {
+        self.params
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .flat_map(|(_, val)| val.split(','))
+            .any(|val| val.trim().eq_ignore_ascii_case(value))
+    }
+
+    /// Returns the unescaped text value.
+    pub fn text(&self) -> String {
+        unescape(&self.value)
+    }
+}
+
+/// Splits at the given separator, ignoring the ones inside double quotes.
+fn split_quoted(text: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                parts.last_mut().unwrap().push(c);
+            }
+            c if c == sep && !quoted => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+impl Card {
+    /// Parses the properties of the card, skipping malformed lines.
+    pub fn props(&self) -> Vec<VcardProp> {
+        unfold(&self.raw)
+            .iter()
+            .filter_map(|line| VcardProp::parse(line))
+            .collect()
+    }
+
+    /// Finds the first property of the given name.
+    pub fn prop(&self, name: &str) -> Option<VcardProp> {
+        self.props()
+            .into_iter()
+            .find(|prop| prop.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the display name of the card: the formatted name, else the structured name, else
+    /// the id.
+    pub fn full_name(&self) -> String {
+        if let Some(name) = self.prop("FN").map(|prop| prop.text()) {
+            if !name.trim().is_empty() {
+                return name.trim().to_owned();
+            }
+        }
+        if let Some(name) = self.prop("N") {
+            // N is family;given;additional;prefixes;suffixes.
+            let parts: Vec<String> = split_unescaped(&name.value, ';')
+                .iter()
+                .map(|part| unescape(part))
+                .collect();
+            let name = [3, 1, 2, 0, 4]
+                .iter()
+                .filter_map(|i| parts.get(*i))
+                .filter(|part| !part.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !name.is_empty() {
+                return name;
+            }
+        }
+        self.id.clone()
+    }
+
+    /// Returns the organization name of the card, without its units, if any.
+    pub fn org(&self) -> Option<String> {
+        self.prop("ORG")
+            .and_then(|prop| split_unescaped(&prop.value, ';').into_iter().next())
+            .map(|org| unescape(&org).trim().to_owned())
+            .filter(|org| !org.is_empty())
+    }
+}
+
+/// Splits the raw content of a card into content lines, each one made of its physical lines,
+/// along with the line ending used by the card.
+fn split_raw(raw: &str) -> (&'static str, Vec<Vec<&str>>) {
+    let eol = if raw.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<Vec<&str>> = vec![];
+    for line in raw.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match lines.last_mut() {
+            Some(last) if line.starts_with(' ') || line.starts_with('\t') => last.push(line),
+            _ => lines.push(vec![line]),
+        }
+    }
+    (eol, lines)
+}
+
+fn join_raw<S: AsRef<str>>(eol: &str, lines: Vec<Vec<S>>) -> String {
+    lines
+        .iter()
+        .flatten()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(eol)
+}
+
+impl Card {
+    /// Returns the UID of the card, if any.
+    pub fn uid(&self) -> Option<String> {
+        self.prop("UID")
+            .map(|prop| prop.text().trim().to_owned())
+            .filter(|uid| !uid.is_empty())
+    }
+
+    /// Removes the properties matching the predicate, keeping the rest of the raw content
+    /// untouched, and returns how many were removed.
+    pub fn remove_props<F: FnMut(&VcardProp) -> bool>(&mut self, mut f: F) -> usize {
+        let (eol, lines) = split_raw(&self.raw);
+        let len = lines.len();
+        let lines: Vec<Vec<&str>> = lines
+            .into_iter()
+            .filter(|line| match VcardProp::parse(&unfold_line(line)) {
+                Some(prop) => !f(&prop),
+                None => true,
+            })
+            .collect();
+        let removed = len - lines.len();
+        if removed > 0 {
+            self.raw = join_raw(eol, lines);
+        }
+        removed
+    }
+
+    /// Replaces the value of the properties for which the closure returns a new one, keeping
+    /// their group and parameters, and returns how many were replaced.
+    pub fn replace_prop_values<F: FnMut(&VcardProp) -> Option<String>>(
+        &mut self,
+        mut f: F,
+    ) -> usize {
+        let (eol, lines) = split_raw(&self.raw);
+        let mut replaced = 0;
+        let lines: Vec<Vec<String>> = lines
+            .into_iter()
+            .map(|line| {
+                let unfolded = unfold_line(&line);
+                let value = VcardProp::parse(&unfolded)
+                    .and_then(|prop| Some((f(&prop)?, prop.value.len())));
+                match value {
+                    Some((value, len)) => {
+                        replaced += 1;
+                        let head = &unfolded[..unfolded.len() - len];
+                        let folded = fold(&format!("{}{}", head, value));
+                        folded
+                            .trim_end_matches("\r\n")
+                            .split("\r\n")
+                            .map(String::from)
+                            .collect()
+                    }
+                    None => line.into_iter().map(String::from).collect(),
+                }
+            })
+            .collect();
+        if replaced > 0 {
+            self.raw = join_raw(eol, lines);
+        }
+        replaced
+    }
+
+    /// Adds a content line at the end of the card, right before `END:VCARD`.
+    pub fn push_prop(&mut self, line: &str) {
+        let (eol, mut lines) = split_raw(&self.raw);
+        let folded = fold(line);
+        let folded: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+        let end = lines
+            .iter()
+            .rposition(|line| unfold_line(line).trim().eq_ignore_ascii_case("END:VCARD"))
+            .unwrap_or(lines.len());
+        lines.insert(end, folded);
+        self.raw = join_raw(eol, lines);
+    }
+}
+
+/// Unfolds the physical lines of a content line.
+fn unfold_line(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| if i == 0 { line } else { &line[1..] })
+        .collect()
+}
@@ -0,0 +1,86 @@
+//! Vdir module.
+//!
+//! This module reads and writes the vdir layout used by local accounts and tools like
+//! vdirsyncer: a directory holding one `.vcf` file per card, named after the card id.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use crate::domain::Card;
+
+/// Extension of the card files.
+const VCF_EXT: &str = "vcf";
+
+/// Checks if the card id can be used as a file name, without escaping its directory.
+pub fn is_card_file_name(id: &str) -> bool {
+    !id.is_empty() && id != "." && id != ".." && !id.contains(['/', '\\'])
+}
+
+/// Reads the cards of the vdir, ordered by id. The date of a card is the modification date of
+/// its file.
+pub fn read_vdir(dir: &Path) -> Result<Vec<Card>> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("cannot read vdir directory {:?}", dir))?;
+
+    let mut cards = vec![];
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("cannot read vdir directory {:?}", dir))?
+            .path();
+        if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) != Some(VCF_EXT) {
+            continue;
+        }
+        let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(id) if !id.starts_with('.') => id.to_owned(),
+            _ => continue,
+        };
+        let raw =
+            fs::read_to_string(&path).with_context(|| format!("cannot read card {:?}", path))?;
+        let date = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now());
+        cards.push(Card {
+            id,
+            etag: None,
+            date,
+            raw,
+        });
+    }
+
+    cards.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(cards)
+}
+
+/// Writes the cards to the vdir, creating it if needed and replacing the files of the cards
+/// having the same id. Each file is written to a temporary file first, then renamed, so that
+/// readers never see a partial card. The modification date of a file is the date of its card.
+/// Returns the number of cards written.
+pub fn write_vdir(dir: &Path, cards: &[Card]) -> Result<usize> {
+    fs::create_dir_all(dir).with_context(|| format!("cannot create vdir directory {:?}", dir))?;
+
+    for card in cards {
+        if !is_card_file_name(&card.id) {
+            return Err(anyhow!(
+                r#"cannot write card "{}": invalid file name"#,
+                card.id
+            ));
+        }
+        let path = dir.join(format!("{}.{}", card.id, VCF_EXT));
+        let tmp = dir.join(format!(".{}.{}.tmp", card.id, VCF_EXT));
+        let mut file =
+            File::create(&tmp).with_context(|| format!("cannot write card {:?}", tmp))?;
+        file.write_all(card.raw.as_bytes())
+            .and_then(|()| file.set_modified(card.date.into()))
+            .with_context(|| format!("cannot write card {:?}", tmp))?;
+        drop(file);
+        fs::rename(&tmp, &path).with_context(|| format!("cannot write card {:?}", path))?;
+    }
+
+    Ok(cards.len())
+}
@@ -0,0 +1,131 @@
+//! Write queue module.
+//!
+//! This module provides the journal of the writes made while the server of a remote account
+//! could not be reached. Each write keeps the etag the card had when it was made, so that its
+//! replay fails instead of overwriting a card modified in the meantime.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::Path};
+
+use crate::domain::{Card, CardError};
+
+/// File name of the write queue, in the cache directory of the account.
+pub const WRITE_QUEUE_FILE: &str = "write-queue.json";
+
+/// Represents the kind of a queued write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WriteOp {
+    Create,
+    Update,
+    Delete,
+}
+
+impl fmt::Display for WriteOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Create => write!(f, "creation"),
+            Self::Update => write!(f, "update"),
+            Self::Delete => write!(f, "deletion"),
+        }
+    }
+}
+
+/// Represents a write waiting for the server to be reachable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedWrite {
+    pub op: WriteOp,
+    pub card_id: String,
+    /// Etag of the card when the write was made, sent as `If-Match` on replay.
+    pub base_etag: Option<String>,
+    /// Content of the card, empty for deletions.
+    pub raw: String,
+    pub queued_at: DateTime<Local>,
+}
+
+impl QueuedWrite {
+    pub fn new(op: WriteOp, card: &Card) -> Self {
+        Self {
+            op,
+            card_id: card.id.clone(),
+            base_etag: card.etag.clone(),
+            raw: match op {
+                WriteOp::Delete => String::new(),
+                _ => card.raw.clone(),
+            },
+            queued_at: Local::now(),
+        }
+    }
+
+    /// Builds the card to send on replay, using the given etag as precondition.
+    pub fn to_card(&self, etag: Option<String>) -> Card {
+        Card {
+            id: self.card_id.clone(),
+            etag,
+            date: self.queued_at,
+            raw: self.raw.clone(),
+        }
+    }
+}
+
+/// Represents a queued write rejected by the server on replay, because the card was modified
+/// or deleted in the meantime.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WriteConflict {
+    pub write: QueuedWrite,
+    pub reason: String,
+    /// File holding the local version of the card, so that it is not lost.
+    pub saved_to: Option<String>,
+}
+
+/// Represents the outcome of the replay of the write queue.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct FlushReport {
+    pub flushed: Vec<QueuedWrite>,
+    pub conflicts: Vec<WriteConflict>,
+    /// Writes left in the queue because the server could not be reached.
+    pub pending: Vec<QueuedWrite>,
+}
+
+impl FlushReport {
+    pub fn is_empty(&self) -> bool {
+        self.flushed.is_empty() && self.conflicts.is_empty() && self.pending.is_empty()
+    }
+}
+
+/// Represents the write queue of an account, oldest writes first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteQueue {
+    pub writes: Vec<QueuedWrite>,
+}
+
+impl WriteQueue {
+    /// Reads the queue from the given file. A missing file is an empty queue.
+    pub fn read(path: &Path) -> Result<Self, CardError> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|err| CardError::parse(format!("write queue {:?}", path), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the queue to the given file, creating its directory if needed. An empty queue
+    /// removes the file.
+    pub fn write(&self, path: &Path) -> Result<(), CardError> {
+        if self.writes.is_empty() {
+            return match fs::remove_file(path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| CardError::parse(format!("write queue {:?}", path), err))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
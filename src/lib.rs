@@ -1,2 +1,5 @@
+pub mod compl;
 pub mod config;
 pub mod domain;
+pub mod man;
+pub mod output;
@@ -1,12 +1,18 @@
 use anyhow::Result;
-use clap;
-use env_logger;
-use std::convert::TryFrom;
-use std::env;
+use std::{convert::TryFrom, env, io, process};
 
 use cardamom::{
-    config::{config_arg, Account, Config},
-    domain::{card_arg, card_handler},
+    compl::{compl_arg, compl_handler},
+    config::{
+        account_arg, account_handler, config_arg, config_handler, doctor_handler, Account, Config,
+    },
+    domain::{
+        birthday_arg, birthday_handler, card_arg, card_handler, flush_arg, flush_handler,
+        group_arg, group_handler, lookup_arg, lookup_handler, migrate_arg, migrate_handler,
+        normalize_arg, normalize_handler, tag_arg, tag_handler, CardFilter,
+    },
+    man::{man_arg, man_handler},
+    output::{output_arg, print_error, OutputFmt},
 };
 
 fn create_app<'a>() -> clap::App<'a, 'a> {
@@ -16,10 +22,22 @@ fn create_app<'a>() -> clap::App<'a, 'a> {
         .author(env!("CARGO_PKG_AUTHORS"))
         .global_setting(clap::AppSettings::GlobalVersion)
         .args(&config_arg::args())
+        .args(&output_arg::args())
+        .subcommands(config_arg::subcmds())
+        .subcommands(account_arg::subcmds())
         .subcommands(card_arg::subcmds())
+        .subcommands(birthday_arg::subcmds())
+        .subcommands(group_arg::subcmds())
+        .subcommands(tag_arg::subcmds())
+        .subcommands(normalize_arg::subcmds())
+        .subcommands(lookup_arg::subcmds())
+        .subcommands(flush_arg::subcmds())
+        .subcommands(migrate_arg::subcmds())
+        .subcommands(compl_arg::subcmds())
+        .subcommands(man_arg::subcmds())
 }
 
-fn main() -> Result<()> {
+fn main() {
     // Inits env logger
     env_logger::init_from_env(
         env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "off"),
@@ -27,21 +45,157 @@ fn main() -> Result<()> {
 
     let app = create_app();
     let m = app.get_matches();
+    let output_fmt = OutputFmt::try_from(m.value_of("output")).unwrap_or_default();
 
+    if let Err(err) = run(&m) {
+        process::exit(print_error(output_fmt, &err));
+    }
+}
+
+fn run(m: &clap::ArgMatches) -> Result<()> {
     // Check completion command BEFORE entities and services initialization.
-    // match compl_arg::matches(&m)? {
-    //     Some(compl_arg::Command::Generate(shell)) => {
-    //         return compl_handler::generate(create_app(), shell);
-    //     }
-    //     _ => (),
-    // }
+    let compl_cmd = compl_arg::matches(m)?;
+    if let Some(compl_arg::Cmd::Generate(shell)) = compl_cmd {
+        return compl_handler::generate(create_app(), shell, &mut io::stdout());
+    }
+
+    // Check man command BEFORE entities and services initialization.
+    if let Some(man_arg::Cmd::Generate(page, dir)) = man_arg::matches(m)? {
+        return man_handler::generate(create_app(), page, dir, &mut io::stdout());
+    }
+
+    // Check config commands BEFORE loading the config, which may not exist yet.
+    match config_arg::matches(m)? {
+        Some(config_arg::Cmd::Check) => {
+            let output_fmt = OutputFmt::try_from(m.value_of("output"))?;
+            return config_handler::check(m.value_of("config"), output_fmt);
+        }
+        Some(config_arg::Cmd::Init) => {
+            let (stdin, stdout) = (io::stdin(), io::stdout());
+            return config_handler::init(
+                m.value_of("config"),
+                &mut stdin.lock(),
+                &mut stdout.lock(),
+            );
+        }
+        None => (),
+    }
 
     // Inits entities and repositories.
     let config = Config::try_from(m.value_of("config"))?;
+
+    if let Some(compl_arg::Cmd::Values(compl_arg::Values::Accounts)) = compl_cmd {
+        return compl_handler::accounts(&config);
+    }
+
+    // Check account listing commands BEFORE selecting an account.
+    let output_fmt = OutputFmt::try_from(m.value_of("output"))?;
+    match account_arg::matches(m)? {
+        Some(account_arg::Cmd::List) => return account_handler::list(&config, output_fmt),
+        Some(account_arg::Cmd::Show(name)) => {
+            return account_handler::show(name, &config, output_fmt)
+        }
+        _ => (),
+    }
+
     let account = Account::try_from((&config, m.value_of("account")))?;
 
+    if let Some(compl_arg::Cmd::Values(compl_arg::Values::Ids)) = compl_cmd {
+        return compl_handler::ids(&account);
+    }
+
+    // Check account commands.
+    match account_arg::matches(m)? {
+        Some(account_arg::Cmd::SetPasswd(backend)) => {
+            return account_handler::set_passwd(backend, &account);
+        }
+        Some(account_arg::Cmd::Doctor) => {
+            return doctor_handler::doctor(&account, output_fmt);
+        }
+        _ => (),
+    }
+
+    // Check birthday commands.
+    match birthday_arg::matches(m)? {
+        Some(birthday_arg::Cmd::List(days)) => {
+            return birthday_handler::list(days, &account, output_fmt);
+        }
+        Some(birthday_arg::Cmd::Ics(alarm)) => {
+            return birthday_handler::ics(alarm, &account);
+        }
+        _ => (),
+    }
+
+    // Check group commands.
+    match group_arg::matches(m)? {
+        Some(group_arg::Cmd::List) => {
+            return group_handler::list(&account, output_fmt);
+        }
+        Some(group_arg::Cmd::Show(group)) => {
+            return group_handler::show(group, &account, output_fmt);
+        }
+        Some(group_arg::Cmd::Create(name, kind)) => {
+            return group_handler::create(name, kind, &account);
+        }
+        Some(group_arg::Cmd::Add(group, ids)) => {
+            return group_handler::add(group, &ids, &account);
+        }
+        Some(group_arg::Cmd::Remove(group, ids)) => {
+            return group_handler::remove(group, &ids, &account);
+        }
+        None => (),
+    }
+
+    // Check tag commands.
+    match tag_arg::matches(m)? {
+        Some(tag_arg::Cmd::List(target)) => {
+            return tag_handler::list(&target, &account, output_fmt);
+        }
+        Some(tag_arg::Cmd::Add(tags, target)) => {
+            return tag_handler::add(&tags, &target, &account);
+        }
+        Some(tag_arg::Cmd::Remove(tags, target)) => {
+            return tag_handler::remove(&tags, &target, &account);
+        }
+        Some(tag_arg::Cmd::Rename(from, to, target)) => {
+            return tag_handler::rename(from, to, &target, &account);
+        }
+        None => (),
+    }
+
+    // Check normalize commands.
+    if let Some(normalize_arg::Cmd::Tel(target, region, dry_run)) = normalize_arg::matches(m)? {
+        return normalize_handler::tel(&target, region, dry_run, &account, output_fmt);
+    }
+
+    // Check lookup commands.
+    if let Some(lookup_arg::Cmd::Tel(number, refresh, max_age)) = lookup_arg::matches(m)? {
+        return lookup_handler::tel(number, refresh, max_age, &account, output_fmt);
+    }
+
+    // Check flush commands.
+    match flush_arg::matches(m)? {
+        Some(flush_arg::Cmd::Flush(true)) => return flush_handler::list(&account, output_fmt),
+        Some(flush_arg::Cmd::Flush(false)) => return flush_handler::flush(&account, output_fmt),
+        None => (),
+    }
+
+    // Check migrate commands.
+    match migrate_arg::matches(m)? {
+        Some(migrate_arg::Cmd::Import(dir)) => return migrate_handler::import(dir, &account),
+        Some(migrate_arg::Cmd::Export(dir)) => return migrate_handler::export(dir, &account),
+        None => (),
+    }
+
     // Check card commands.
-    match card_arg::matches(&m)? {
+    match card_arg::matches(m)? {
+        Some(card_arg::Cmd::List(query, tag)) => {
+            let filter = CardFilter {
+                query: query.map(String::from),
+                tag: tag.map(String::from),
+            };
+            return card_handler::list(&filter, &account, output_fmt);
+        }
         Some(card_arg::Cmd::Create(raw_card)) => {
             return card_handler::create(raw_card, &account);
         }
@@ -0,0 +1,50 @@
+//! Man CLI module.
+//!
+//! This module provides subcommands and a command matcher related to man pages.
+
+use anyhow::Result;
+use log::{debug, trace};
+
+type Page<'a> = Option<&'a str>;
+type Dir<'a> = Option<&'a str>;
+
+/// Represents the man commands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Cmd<'a> {
+    /// Represents the generate man pages command.
+    Generate(Page<'a>, Dir<'a>),
+}
+
+/// Defines the man command matcher.
+pub fn matches<'a>(m: &'a clap::ArgMatches) -> Result<Option<Cmd<'a>>> {
+    if let Some(m) = m.subcommand_matches("man") {
+        debug!("man subcommand matched");
+        let page = m.value_of("page");
+        trace!("page: {:?}", page);
+        let dir = m.value_of("dir");
+        trace!("dir: {:?}", dir);
+        return Ok(Some(Cmd::Generate(page, dir)));
+    }
+
+    Ok(None)
+}
+
+/// Contains man subcommands.
+pub fn subcmds<'a>() -> Vec<clap::App<'a, 'a>> {
+    vec![clap::SubCommand::with_name("man")
+        .about("Generates the man pages")
+        .after_help("Try `cardamom man | man -l -`, or `cardamom man --dir /usr/local/share/man`.")
+        .arg(
+            clap::Arg::with_name("page")
+                .help("Prints this page, like cardamom-read or cardamom.toml [default: cardamom]")
+                .value_name("PAGE")
+                .conflicts_with("dir"),
+        )
+        .arg(
+            clap::Arg::with_name("dir")
+                .long("dir")
+                .short("d")
+                .help("Writes all the pages in the man1 and man5 sections of this directory")
+                .value_name("DIR"),
+        )]
+}
@@ -0,0 +1,557 @@
+//! Man handling module.
+//!
+//! This module generates roff man pages: one in section 1 per command, built from the long help
+//! printed by clap, plus the `cardamom.toml(5)` page describing the config file.
+
+use anyhow::{anyhow, Context, Result};
+use clap::{App, AppSettings, ErrorKind};
+use std::{fs, io::Write, path::PathBuf};
+
+/// Represents a man page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManPage {
+    /// Name of the page, like `cardamom-read`.
+    pub name: String,
+    pub section: u8,
+    /// Roff source of the page.
+    pub content: String,
+}
+
+impl ManPage {
+    /// Returns the file name of the page, like `cardamom-read.1`.
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.name, self.section)
+    }
+}
+
+/// Escapes text for roff: backslashes and dashes, plus leading control characters.
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bold(text: &str) -> String {
+    format!("\\fB{}\\fR", escape(text))
+}
+
+fn italic(text: &str) -> String {
+    format!("\\fI{}\\fR", escape(text))
+}
+
+fn header(name: &str, section: u8, version: &str) -> String {
+    let manual = match section {
+        5 => "File Formats",
+        _ => "User Commands",
+    };
+    format!(
+        ".TH {} {} \"\" \"cardamom {}\" \"{}\"\n",
+        escape(&name.to_uppercase()),
+        section,
+        escape(version),
+        manual
+    )
+}
+
+fn section(title: &str) -> String {
+    format!(".SH {}\n", title)
+}
+
+fn item(tag: &str, body: &str) -> String {
+    format!(".TP\n{}\n{}\n", tag, body)
+}
+
+/// Represents the long help of a command, as printed by clap.
+#[derive(Debug, Default)]
+struct Help {
+    /// Version of the command, from the first line.
+    version: String,
+    /// About of the command, the long one when given.
+    about: String,
+    usage: Vec<String>,
+    /// Flags, options and positional arguments, as tag and description.
+    args: Vec<(String, String)>,
+    /// Visible subcommands, as name and about.
+    subcmds: Vec<(String, String)>,
+    /// Text following the help, like examples.
+    after: String,
+}
+
+impl Help {
+    /// Parses the long help of a command, printed with the help of each item on its own line.
+    fn parse(help: &str) -> Self {
+        let mut lines = help.lines();
+        let mut about = vec![];
+        let mut after = vec![];
+        let mut parsed = Self {
+            version: lines
+                .next()
+                .and_then(|line| line.trim().split_once(' '))
+                .map(|(_, version)| version.to_owned())
+                .unwrap_or_default(),
+            ..Self::default()
+        };
+
+        let mut section = "";
+        for line in lines {
+            if matches!(
+                line,
+                "USAGE:" | "FLAGS:" | "OPTIONS:" | "ARGS:" | "SUBCOMMANDS:"
+            ) {
+                section = line;
+                continue;
+            }
+            if !section.is_empty() && !line.is_empty() && !line.starts_with(' ') {
+                section = "AFTER";
+            }
+
+            let items = match section {
+                "" => {
+                    about.push(line);
+                    continue;
+                }
+                "AFTER" => {
+                    after.push(line);
+                    continue;
+                }
+                "USAGE:" => {
+                    if !line.trim().is_empty() {
+                        parsed.usage.push(line.trim().to_owned());
+                    }
+                    continue;
+                }
+                "SUBCOMMANDS:" => &mut parsed.subcmds,
+                _ => &mut parsed.args,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.strip_prefix("            ") {
+                Some(desc) => {
+                    if let Some((_, body)) = items.last_mut() {
+                        if !body.is_empty() {
+                            body.push('\n');
+                        }
+                        body.push_str(desc);
+                    }
+                }
+                None => items.push((line.trim().to_owned(), String::new())),
+            }
+        }
+
+        parsed.about = about.join("\n").trim().to_owned();
+        parsed.after = after.join("\n").trim().to_owned();
+        parsed
+    }
+}
+
+/// Returns the long help of the command at the given path, as printed by clap without colors,
+/// wrapping, nor the `help` subcommand.
+fn long_help(app: &App, path: &[&str]) -> Result<Help> {
+    let app = app
+        .clone()
+        .global_settings(&[
+            AppSettings::NextLineHelp,
+            AppSettings::ColorNever,
+            AppSettings::DisableHelpSubcommand,
+        ])
+        .set_term_width(0);
+    match app.get_matches_from_safe(path.iter().copied().chain(Some("--help"))) {
+        Err(err) if err.kind == ErrorKind::HelpDisplayed => Ok(Help::parse(&err.message)),
+        Err(err) => Err(anyhow!(err.message)),
+        Ok(_) => Err(anyhow!(r#"cannot get help of "{}""#, path.join(" "))),
+    }
+}
+
+/// Renders a usage or the tag of an argument, like `-c, --config <PATH>`: options in bold and
+/// values in italics.
+fn words(text: &str) -> String {
+    fn flush(word: &mut String, out: &mut String) {
+        if word.starts_with('-') {
+            out.push_str(&bold(word));
+        } else if !word.is_empty() {
+            out.push_str(&italic(word));
+        }
+        word.clear();
+    }
+
+    let mut out = String::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+/// Renders the description of an argument, moving the default and possible values printed by
+/// clap at its end on their own lines.
+fn arg_desc(desc: &str) -> String {
+    let mut text = desc.trim_end();
+    let mut specs = vec![];
+    while text.ends_with(']') {
+        match text.rfind('[') {
+            Some(i)
+                if ["default:", "possible values:", "aliases:"]
+                    .iter()
+                    .any(|spec| text[i + 1..].starts_with(spec)) =>
+            {
+                specs.insert(0, escape(&text[i..]));
+                text = text[..i].trim_end();
+            }
+            _ => break,
+        }
+    }
+    if !text.is_empty() {
+        specs.insert(0, escape(text));
+    }
+    specs.join("\n")
+}
+
+/// Builds the synopsis of a command from its usage lines, the command path being in bold.
+fn synopsis(path: &[&str], usage: &[String]) -> String {
+    let cmd = path.join(" ");
+    let lines: Vec<_> = usage
+        .iter()
+        .map(|line| {
+            let args = line.strip_prefix(&cmd).unwrap_or(line);
+            format!("{}{}", bold(&cmd), words(args))
+        })
+        .collect();
+    format!("{}\n", lines.join("\n.br\n"))
+}
+
+/// Builds the page of a command, then the pages of its subcommands.
+fn command_pages(
+    app: &App,
+    path: &[&str],
+    about: &str,
+    version: &str,
+    pages: &mut Vec<ManPage>,
+) -> Result<()> {
+    let help = long_help(app, path)?;
+    let name = path.join("-");
+    let mut content = header(&name, 1, version);
+
+    content.push_str(&section("NAME"));
+    if about.is_empty() {
+        content.push_str(&format!("{}\n", escape(&name)));
+    } else {
+        content.push_str(&format!("{} \\- {}\n", escape(&name), escape(about)));
+    }
+
+    content.push_str(&section("SYNOPSIS"));
+    content.push_str(&synopsis(path, &help.usage));
+
+    content.push_str(&section("DESCRIPTION"));
+    content.push_str(&escape(if help.about.is_empty() {
+        about
+    } else {
+        &help.about
+    }));
+    content.push('\n');
+    if !help.after.is_empty() {
+        content.push_str(".PP\n");
+        content.push_str(&escape(&help.after));
+        content.push('\n');
+    }
+    if path.len() > 1 {
+        content.push_str(".PP\nThe global options are described in ");
+        content.push_str(&format!("{}(1).\n", bold(path[0])));
+    }
+
+    if !help.args.is_empty() {
+        content.push_str(&section("OPTIONS"));
+        for (tag, desc) in help.args.iter() {
+            // Angle brackets are dropped since values are in italics.
+            let tag = words(&tag.replace(['<', '>'], ""));
+            content.push_str(&item(&tag, &arg_desc(desc)));
+        }
+    }
+
+    let mut see_also = vec![];
+    if !help.subcmds.is_empty() {
+        content.push_str(&section("SUBCOMMANDS"));
+        for (sc, sc_about) in help.subcmds.iter() {
+            let sc_name = format!("{}-{}", name, sc);
+            content.push_str(&item(&format!("{}(1)", bold(&sc_name)), &escape(sc_about)));
+            see_also.push(format!("{}(1)", bold(&sc_name)));
+        }
+    }
+
+    if path.len() == 1 {
+        content.push_str(MAIN_EXTRA_SECTIONS);
+        see_also.push(format!("{}(5)", bold("cardamom.toml")));
+    } else {
+        see_also.insert(0, format!("{}(1)", bold(path[0])));
+    }
+
+    content.push_str(&section("SEE ALSO"));
+    content.push_str(&see_also.join(",\n"));
+    content.push('\n');
+
+    pages.push(ManPage {
+        name: name.clone(),
+        section: 1,
+        content,
+    });
+
+    for (sc, sc_about) in help.subcmds.iter() {
+        let mut path = path.to_vec();
+        path.push(sc);
+        command_pages(app, &path, sc_about, version, pages)?;
+    }
+
+    Ok(())
+}
+
+/// Environment, files and exit status sections of the main page.
+const MAIN_EXTRA_SECTIONS: &str = r#".SH ENVIRONMENT
+.TP
+\fBCARDAMOM_CONFIG\fR
+Path of the config file, overridden by \fB\-\-config\fR.
+.TP
+\fBCARDAMOM_ACCOUNT_\fR\fINAME\fR\fB_URL\fR, \fB_PATH\fR, \fB_LOGIN\fR, \fB_PASSWD_CMD\fR
+Override the entries of the account \fINAME\fR, in uppercase with non\-alphanumeric characters
+replaced by underscores. An unknown account with a \fB_URL\fR variable is created on the fly.
+.TP
+\fBNETRC\fR
+Path of the netrc file used by accounts with \fBnetrc = true\fR.
+.TP
+\fBRUST_LOG\fR
+Log level, like \fBdebug\fR or \fBtrace\fR.
+.SH FILES
+.TP
+\fI$XDG_CONFIG_HOME/cardamom/config.toml\fR
+.TQ
+\fI~/.config/cardamom/config.toml\fR
+.TQ
+\fI~/.cardamomrc\fR
+Config file, the first one found being used. See \fBcardamom.toml\fR(5).
+.TP
+\fI$XDG_CACHE_HOME/cardamom/ACCOUNT/\fR
+.TQ
+\fI~/.cache/cardamom/ACCOUNT/\fR
+Cache directory of the account, holding the phone number index, the card cache and the offline write queue.
+.SH EXIT STATUS
+.TP
+\fB0\fR
+Success.
+.TP
+\fB1\fR
+Any other error.
+.TP
+\fB3\fR
+The card does not exist.
+.TP
+\fB4\fR
+The card has been modified in the meantime.
+.TP
+\fB5\fR
+The credentials are invalid or access is denied.
+.TP
+\fB6\fR
+The server cannot be reached.
+.TP
+\fB7\fR
+The server replied with an unexpected status.
+"#;
+
+/// Represents a group of entries of the config file: title, introduction and entries, as key,
+/// type and description.
+type ConfigSection = (
+    &'static str,
+    &'static str,
+    &'static [(&'static str, &'static str, &'static str)],
+);
+
+const CONFIG_SECTIONS: &[ConfigSection] = &[
+    (
+        "LOCAL ACCOUNTS",
+        "Local accounts store cards as vCard files in a directory. They are declared by a [\\fINAME\\fR.local] table.",
+        &[
+            ("default", "boolean", "Uses this account when \\fB\\-\\-account\\fR is not given. Required when there are several accounts."),
+            ("path", "string", "Directory of the cards. Shell variables and \\fB~\\fR are expanded."),
+            ("default-region", "string", "Region of the national phone numbers, as a country code like \\fBFR\\fR."),
+            ("normalize-tel", "boolean", "Normalizes the phone numbers to E.164 when cards are created or updated."),
+        ],
+    ),
+    (
+        "REMOTE ACCOUNTS",
+        "Remote accounts store cards on a CardDAV server. They are declared by a [\\fINAME\\fR.remote] table. Only one of \\fBpasswd\\-cmd\\fR, \\fBpasswd\\-keyring\\fR and \\fBnetrc\\fR can be set.",
+        &[
+            ("default", "boolean", "Uses this account when \\fB\\-\\-account\\fR is not given. Required when there are several accounts."),
+            ("url", "string", "URL of the CardDAV server, the addressbook being discovered from it."),
+            ("login", "string", "Login of the user."),
+            ("passwd-cmd", "string or array", "Command printing the password, either a shell command line or a list of arguments run without shell."),
+            ("passwd-keyring", "string", "Entry of the system keyring holding the password, set with \\fBcardamom account set\\-password\\fR."),
+            ("netrc", "boolean", "Reads the login and the password from the netrc file."),
+            ("proxy", "string", "Proxy URL used for every request, like \\fBhttp://proxy:3128\\fR or \\fBsocks5://proxy:1080\\fR."),
+            ("connect-timeout", "integer", "Connection timeout, in seconds."),
+            ("timeout", "integer", "Request timeout, in seconds, 30 by default."),
+            ("default-region", "string", "Region of the national phone numbers, as a country code like \\fBFR\\fR."),
+            ("normalize-tel", "boolean", "Normalizes the phone numbers to E.164 when cards are created or updated."),
+            ("cache", "boolean", "Keeps a copy of the cards in the cache directory. Reads are served from it while the addressbook is unchanged, and when the server cannot be reached."),
+            ("offline-queue", "boolean", "Queues the writes made while the server cannot be reached. They are replayed by \\fBcardamom flush\\fR or by the next command writing cards once the server can be reached. Writes on cards modified in the meantime are dropped, the local version of the card being saved in the \\fIconflicts\\fR subdirectory of the cache directory."),
+        ],
+    ),
+    (
+        "SQLITE ACCOUNTS",
+        "SQLite accounts store cards in a single database file, with a full-text index of their names, emails, phone numbers and organization. Queries of at least 3 characters are answered by the index, shorter ones scan every card. They are declared by a [\\fINAME\\fR.sqlite] table. Cards can be imported from or exported to a directory of vCard files with \\fBcardamom migrate\\fR.",
+        &[
+            ("default", "boolean", "Uses this account when \\fB\\-\\-account\\fR is not given. Required when there are several accounts."),
+            ("path", "string", "Database file, created if missing. Shell variables and \\fB~\\fR are expanded."),
+            ("default-region", "string", "Region of the national phone numbers, as a country code like \\fBFR\\fR."),
+            ("normalize-tel", "boolean", "Normalizes the phone numbers to E.164 when cards are created or updated."),
+        ],
+    ),
+    (
+        "AUTHENTICATION",
+        "The optional [\\fINAME\\fR.remote.auth] table takes precedence over the login and password entries of the account. Without it, the first request is sent without credentials, then basic or digest authentication is used depending on what the server asks for.",
+        &[
+            ("type", "string", "One of \\fBbasic\\fR, \\fBdigest\\fR or \\fBoauth2\\fR. The basic and digest types accept the \\fBlogin\\fR, \\fBpasswd\\-cmd\\fR, \\fBpasswd\\-keyring\\fR and \\fBnetrc\\fR entries described above."),
+            ("client-id", "string", "OAuth2 client identifier."),
+            ("client-secret-cmd", "string or array", "Command printing the OAuth2 client secret, for providers that require one."),
+            ("auth-url", "string", "OAuth2 authorization endpoint, used by the authorization code flow."),
+            ("device-auth-url", "string", "OAuth2 device authorization endpoint, used by the device code flow."),
+            ("token-url", "string", "OAuth2 token endpoint."),
+            ("scopes", "array", "OAuth2 scopes to request."),
+            ("flow", "string", "Either \\fBauthorization\\-code\\fR (default) or \\fBdevice\\-code\\fR."),
+            ("redirect-port", "integer", "Port of the local redirect listener. A random port is used by default."),
+            ("redirect-timeout", "integer", "Time given to authorize cardamom in the browser, in seconds. Defaults to 300."),
+            ("refresh-token-cmd", "string or array", "Command printing the stored refresh token. An empty output triggers the flow."),
+            ("refresh-token-store-cmd", "string or array", "Command storing a new refresh token, given on its standard input."),
+        ],
+    ),
+    (
+        "TLS",
+        "The optional [\\fINAME\\fR.remote.tls] table tunes the TLS connections of the account.",
+        &[
+            ("ca-file", "string", "PEM bundle of additional trusted root certificates."),
+            ("client-cert", "string", "Client certificate, either PKCS#12 (when no key is given) or PEM."),
+            ("client-key", "string", "PEM PKCS#8 private key of the client certificate."),
+            ("client-cert-passwd-cmd", "string or array", "Command printing the password of the PKCS#12 client certificate."),
+            ("pinned-fingerprints", "array", "SHA\\-256 fingerprints of the accepted server certificates, like \\fBsha256:ab:cd:...\\fR. The system roots are then ignored: a pinned certificate issued by a CA needs this CA in \\fBca\\-file\\fR, while a pinned self\\-signed one needs none."),
+            ("insecure", "boolean", "Disables the verification of the server certificate and hostname."),
+        ],
+    ),
+    (
+        "RETRY",
+        "The optional [\\fINAME\\fR.remote.retry] table defines how idempotent requests are retried.",
+        &[
+            ("max-retries", "integer", "Maximum number of retries after the first attempt. Defaults to 2."),
+            ("initial-delay", "integer", "Delay before the first retry, in milliseconds, doubled after each attempt. Defaults to 500."),
+            ("max-delay", "integer", "Upper bound of the delay between two attempts, in milliseconds. Defaults to 10000."),
+        ],
+    ),
+];
+
+const CONFIG_EXAMPLE: &str = r#"[personal.local]
+default = true
+path = "~/.local/share/contacts"
+
+[work.remote]
+url = "https://dav.example.com"
+login = "alice"
+passwd-cmd = ["pass", "show", "dav.example.com"]
+
+[work.remote.retry]
+max-retries = 5
+"#;
+
+/// Builds the page of the config file.
+fn config_page(version: &str) -> ManPage {
+    let mut content = header("cardamom.toml", 5, version);
+
+    content.push_str(&section("NAME"));
+    content.push_str("cardamom.toml \\- cardamom configuration file\n");
+
+    content.push_str(&section("DESCRIPTION"));
+    content.push_str(
+        "The config file of \\fBcardamom\\fR(1) is a TOML file declaring one table per account, \
+         named after the account and containing either a \\fBlocal\\fR, a \\fBremote\\fR or a \
+         \\fBsqlite\\fR table.\n\
+         .PP\n\
+         Run \\fBcardamom config check\\fR to report the issues of the file, and \
+         \\fBcardamom config init\\fR to add an account interactively.\n",
+    );
+
+    for (title, intro, entries) in CONFIG_SECTIONS {
+        content.push_str(&section(title));
+        content.push_str(intro);
+        content.push('\n');
+        for (key, kind, desc) in entries.iter() {
+            content.push_str(&item(&format!("{} ({})", bold(key), kind), desc));
+        }
+    }
+
+    content.push_str(&section("EXAMPLE"));
+    content.push_str(".PP\n.nf\n.RS\n");
+    content.push_str(&escape(CONFIG_EXAMPLE));
+    content.push_str("\n.RE\n.fi\n");
+
+    content.push_str(&section("SEE ALSO"));
+    content.push_str(&format!("{}(1)\n", bold("cardamom")));
+
+    ManPage {
+        name: "cardamom.toml".into(),
+        section: 5,
+        content,
+    }
+}
+
+/// Builds all the man pages: the command pages, built from the help printed by clap, then the
+/// config file page.
+pub fn pages(app: &App) -> Result<Vec<ManPage>> {
+    let help = long_help(app, &[app.get_name()])?;
+    let about = help.about.lines().next().unwrap_or_default();
+    let mut pages = vec![];
+    command_pages(app, &[app.get_name()], about, &help.version, &mut pages)?;
+    pages.push(config_page(&help.version));
+    Ok(pages)
+}
+
+/// Prints the given page, or writes all the pages in the given directory.
+pub fn generate<W: Write>(
+    app: App,
+    page: Option<&str>,
+    dir: Option<&str>,
+    output: &mut W,
+) -> Result<()> {
+    let pages = pages(&app)?;
+
+    if let Some(dir) = dir {
+        for page in pages {
+            let mut path = PathBuf::from(dir);
+            path.push(format!("man{}", page.section));
+            fs::create_dir_all(&path)
+                .with_context(|| format!("cannot create directory {:?}", path))?;
+            path.push(page.file_name());
+            fs::write(&path, page.content)
+                .with_context(|| format!("cannot write man page {:?}", path))?;
+            writeln!(output, "{}", path.display())?;
+        }
+        return Ok(());
+    }
+
+    let name = page.unwrap_or_else(|| app.get_name());
+    let page = pages
+        .iter()
+        .find(|page| page.name == name || page.file_name() == name)
+        .ok_or_else(|| anyhow!(r#"cannot find man page "{}""#, name))?;
+    output.write_all(page.content.as_bytes())?;
+    Ok(())
+}
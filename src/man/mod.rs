@@ -0,0 +1,4 @@
+//! Module related to man pages.
+
+pub mod man_arg;
+pub mod man_handler;
@@ -0,0 +1,9 @@
+//! Module related to the output of the CLI.
+
+pub mod output_arg;
+
+pub mod output_entity;
+pub use output_entity::*;
+
+pub mod output_error;
+pub use output_error::*;
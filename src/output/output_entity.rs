@@ -0,0 +1,23 @@
+use anyhow::{anyhow, Error, Result};
+use std::convert::TryFrom;
+
+/// Represents the output format.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFmt {
+    #[default]
+    Plain,
+    Json,
+}
+
+impl TryFrom<Option<&str>> for OutputFmt {
+    type Error = Error;
+
+    fn try_from(fmt: Option<&str>) -> Result<Self, Self::Error> {
+        match fmt {
+            Some(fmt) if fmt.eq_ignore_ascii_case("json") => Ok(Self::Json),
+            Some(fmt) if fmt.eq_ignore_ascii_case("plain") => Ok(Self::Plain),
+            None => Ok(Self::Plain),
+            Some(fmt) => Err(anyhow!(r#"cannot parse output format "{}""#, fmt)),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Output error module.
+//!
+//! This module maps errors to documented exit codes and prints them either as plain text or as a
+//! JSON object on stderr.
+//!
+//! | Code | Kind        | Meaning                                         |
+//! |------|-------------|-------------------------------------------------|
+//! | 0    |             | Success                                         |
+//! | 1    | `error`     | Any other error                                 |
+//! | 3    | `not-found` | The card does not exist                         |
+//! | 4    | `conflict`  | The card has been modified in the meantime      |
+//! | 5    | `auth`      | The credentials are invalid or access is denied |
+//! | 6    | `network`   | The server cannot be reached                    |
+//! | 7    | `server`    | The server replied with an unexpected status    |
+
+use anyhow::Error;
+use serde::Serialize;
+
+use crate::{domain::CardError, output::OutputFmt};
+
+/// Represents the kind of an error, from the CLI point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    Error,
+    NotFound,
+    Conflict,
+    Auth,
+    Network,
+    Server,
+}
+
+impl ErrorKind {
+    /// Returns the exit code associated to the error kind.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Error => 1,
+            Self::NotFound => 3,
+            Self::Conflict => 4,
+            Self::Auth => 5,
+            Self::Network => 6,
+            Self::Server => 7,
+        }
+    }
+}
+
+impl From<&CardError> for ErrorKind {
+    fn from(err: &CardError) -> Self {
+        match err {
+            CardError::NotFound(_) => Self::NotFound,
+            CardError::PreconditionFailed { .. } => Self::Conflict,
+            CardError::Unauthorized | CardError::Auth(_) | CardError::Forbidden => Self::Auth,
+            CardError::UntrustedCertificate(_) | CardError::Network(_) | CardError::Offline => {
+                Self::Network
+            }
+            CardError::ServerError { .. } => Self::Server,
+            CardError::Parse(..) | CardError::Io(_) | CardError::Database(_) => Self::Error,
+        }
+    }
+}
+
+impl From<&Error> for ErrorKind {
+    /// Finds the first typed error of the chain and maps it to an error kind.
+    fn from(err: &Error) -> Self {
+        err.chain()
+            .find_map(|err| err.downcast_ref::<CardError>())
+            .map(ErrorKind::from)
+            .unwrap_or(ErrorKind::Error)
+    }
+}
+
+/// Represents an error printed in JSON format.
+#[derive(Debug, Serialize)]
+struct JsonError {
+    kind: ErrorKind,
+    code: i32,
+    message: String,
+    causes: Vec<String>,
+}
+
+/// Prints the given error on stderr using the given output format, then returns the exit code
+/// matching the error.
+pub fn print_error(fmt: OutputFmt, err: &Error) -> i32 {
+    let kind = ErrorKind::from(err);
+
+    match fmt {
+        OutputFmt::Plain => eprintln!("Error: {:?}", err),
+        OutputFmt::Json => {
+            let err = JsonError {
+                kind,
+                code: kind.exit_code(),
+                message: err.to_string(),
+                causes: err.chain().skip(1).map(ToString::to_string).collect(),
+            };
+            match serde_json::to_string(&err) {
+                Ok(err) => eprintln!("{}", err),
+                Err(_) => eprintln!("Error: {}", err.message),
+            }
+        }
+    }
+
+    kind.exit_code()
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDate;
+
+use cardamom::domain::{upcoming_events, CardEvent, EventKind, PartialDate};
+
+mod common;
+use common::card;
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd(year, month, day)
+}
+
+#[test]
+fn test_parse_partial_date() {
+    let full = Some(PartialDate {
+        year: Some(1996),
+        month: 4,
+        day: 15,
+    });
+    assert_eq!(PartialDate::parse("1996-04-15"), full);
+    assert_eq!(PartialDate::parse("19960415"), full);
+    assert_eq!(PartialDate::parse("1996-04-15T08:30:00Z"), full);
+
+    let partial = Some(PartialDate {
+        year: None,
+        month: 4,
+        day: 15,
+    });
+    assert_eq!(PartialDate::parse("--0415"), partial);
+    assert_eq!(PartialDate::parse("--04-15"), partial);
+    assert!(PartialDate::parse("--0229").is_some());
+
+    assert_eq!(PartialDate::parse("1996"), None);
+    assert_eq!(PartialDate::parse("---15"), None);
+    assert_eq!(PartialDate::parse("1996-02-30"), None);
+    assert_eq!(PartialDate::parse("circa 1800"), None);
+}
+
+#[test]
+fn test_next_occurrence() {
+    let bday = PartialDate::parse("1990-04-15").unwrap();
+    assert_eq!(bday.next_occurrence(date(2026, 4, 15)), date(2026, 4, 15));
+    assert_eq!(bday.next_occurrence(date(2026, 4, 16)), date(2027, 4, 15));
+
+    let leap = PartialDate::parse("2000-02-29").unwrap();
+    assert_eq!(leap.next_occurrence(date(2026, 1, 1)), date(2026, 2, 28));
+    assert_eq!(leap.next_occurrence(date(2027, 3, 1)), date(2028, 2, 29));
+}
+
+#[test]
+fn test_card_events() {
+    let events = CardEvent::from_card(&card(
+        "jane",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:3.0",
+            "FN:Jane Doe",
+            "BDAY;X-APPLE-OMIT-YEAR=1604:1604-10-20",
+            "X-ANNIVERSARY:2010-06-12",
+            "END:VCARD",
+        ],
+    ));
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].kind, EventKind::Birthday);
+    assert_eq!(events[0].date.year, None);
+    assert_eq!(events[0].name, "Jane Doe");
+    assert_eq!(events[1].kind, EventKind::Anniversary);
+    assert_eq!(events[1].date.year, Some(2010));
+
+    let events = CardEvent::from_card(&card(
+        "old",
+        &["FN:Old", "BDAY;VALUE=text:circa 1800", "ANNIVERSARY:--0101"],
+    ));
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Anniversary);
+}
+
+#[test]
+fn test_upcoming_events() {
+    let cards = vec![
+        card("a", &["FN:Alice", "BDAY:1990-10-25"]),
+        card("b", &["FN:Bob", "BDAY:--1019"]),
+        card("c", &["FN:Carol", "BDAY:1985-12-01"]),
+        card("d", &["FN:Dave", "EMAIL:dave@example.com"]),
+    ];
+    let events = upcoming_events(&cards, date(2026, 10, 18), 30);
+
+    let summary: Vec<_> = events
+        .iter()
+        .map(|event| (event.event.name.as_str(), event.days, event.years))
+        .collect();
+    assert_eq!(summary, vec![("Bob", 1, None), ("Alice", 7, Some(36))]);
+}
@@ -0,0 +1,131 @@
+use chrono::Local;
+use std::{fs, path::PathBuf};
+
+use cardamom::domain::{
+    card_repositories::{CachingCardRepository, CARD_CACHE_FILE},
+    Card, CardError, CardRepository,
+};
+
+mod common;
+use common::{temp_dir, FakeRepository};
+
+fn cache_path(name: &str) -> PathBuf {
+    temp_dir(&format!("cache-{}", name)).join(CARD_CACHE_FILE)
+}
+
+#[test]
+fn test_read_all_from_cache() {
+    let path = cache_path("read-all");
+    let remote = FakeRepository::default();
+    remote.put("jane", "FN:Jane");
+    remote.put("john", "FN:John");
+    let repository = CachingCardRepository::new(Some(&remote), path.clone());
+
+    assert_eq!(repository.read_all().unwrap().len(), 2);
+    assert_eq!(remote.transfers.get(), 1);
+    assert!(path.exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // Unchanged ctag: served from cache.
+    let cards = repository.read_all().unwrap();
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0].raw, "FN:Jane");
+    assert_eq!(remote.transfers.get(), 1);
+
+    // Changed ctag: fetched again.
+    remote.put("jane", "FN:Jane Doe");
+    let cards = repository.read_all().unwrap();
+    assert_eq!(cards[0].raw, "FN:Jane Doe");
+    assert_eq!(remote.transfers.get(), 2);
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_read_revalidates() {
+    let path = cache_path("read");
+    let remote = FakeRepository::default();
+    remote.put("jane", "FN:Jane");
+    remote.put("john", "FN:John");
+    let repository = CachingCardRepository::new(Some(&remote), path.clone());
+
+    assert_eq!(repository.read("jane").unwrap().raw, "FN:Jane");
+    assert_eq!(remote.transfers.get(), 1);
+
+    // Same etag: not transferred again, even though the ctag changed.
+    remote.put("john", "FN:John Doe");
+    assert_eq!(repository.read("jane").unwrap().raw, "FN:Jane");
+    assert_eq!(remote.transfers.get(), 1);
+
+    remote.put("jane", "FN:Jane Doe");
+    assert_eq!(repository.read("jane").unwrap().raw, "FN:Jane Doe");
+    assert_eq!(remote.transfers.get(), 2);
+
+    // Deleted cards leave the cache.
+    remote.delete(&remote.read("jane").unwrap()).unwrap();
+    assert!(matches!(
+        repository.read("jane"),
+        Err(CardError::NotFound(_))
+    ));
+    assert!(!repository.load().cards.contains_key("jane"));
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_writes_update_cache() {
+    let path = cache_path("writes");
+    let remote = FakeRepository::default();
+    let repository = CachingCardRepository::new(Some(&remote), path.clone());
+
+    let mut card = Card {
+        id: "jane".into(),
+        etag: None,
+        date: Local::now(),
+        raw: "FN:Jane".into(),
+    };
+    repository.create(&mut card).unwrap();
+    let cache = repository.load();
+    assert_eq!(cache.cards["jane"].etag, card.etag);
+
+    repository.delete(&card).unwrap();
+    assert!(repository.load().cards.is_empty());
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_offline_fallback() {
+    let path = cache_path("offline");
+    let remote = FakeRepository::default();
+    remote.put("jane", "FN:Jane");
+    let repository = CachingCardRepository::new(Some(&remote), path.clone());
+
+    // Nothing cached yet: the error is returned.
+    remote.offline.set(true);
+    assert!(matches!(repository.read_all(), Err(CardError::Offline)));
+
+    remote.offline.set(false);
+    repository.read_all().unwrap();
+    remote.offline.set(true);
+    assert_eq!(repository.read_all().unwrap().len(), 1);
+    assert_eq!(repository.read("jane").unwrap().raw, "FN:Jane");
+    assert!(matches!(repository.read("john"), Err(CardError::Offline)));
+
+    let mut card = repository.read("jane").unwrap();
+    assert!(matches!(
+        repository.update(&mut card),
+        Err(CardError::Offline)
+    ));
+
+    // Server not even discovered.
+    let repository = CachingCardRepository::new(None, path.clone());
+    assert_eq!(repository.read_all().unwrap().len(), 1);
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
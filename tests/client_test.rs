@@ -0,0 +1,113 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use cardamom::config::RemoteAccount;
+
+/// Spawns a server accepting a single connection, then never replying.
+fn stalled_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (_stream, _) = listener.accept().unwrap();
+        thread::sleep(Duration::from_secs(120));
+    });
+    format!("http://{}/", addr)
+}
+
+#[test]
+fn test_client_timeout() {
+    let account = RemoteAccount {
+        url: stalled_server(),
+        timeout: Some(Duration::from_secs(1)),
+        ..RemoteAccount::default()
+    };
+    let start = Instant::now();
+    let err = account
+        .client()
+        .unwrap()
+        .get(&account.url)
+        .send()
+        .unwrap_err();
+    assert!(err.is_timeout());
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+/// Tests that an account without timeout keeps the 30 seconds default of the client instead of
+/// waiting forever.
+fn test_client_default_timeout() {
+    let account = RemoteAccount {
+        url: stalled_server(),
+        ..RemoteAccount::default()
+    };
+    let start = Instant::now();
+    let err = account
+        .client()
+        .unwrap()
+        .get(&account.url)
+        .send()
+        .unwrap_err();
+    assert!(err.is_timeout());
+    assert!(start.elapsed() < Duration::from_secs(60));
+}
+
+#[test]
+/// Tests that requests go through the proxy, connecting to it within the connect timeout.
+fn test_client_proxy() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let proxy = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let n = stream.read(&mut buf).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        String::from_utf8_lossy(&buf[..n]).into_owned()
+    });
+
+    let account = RemoteAccount {
+        url: "http://dav.example.invalid/cards/".into(),
+        proxy: Some(format!("http://{}", addr)),
+        connect_timeout: Some(Duration::from_secs(1)),
+        ..RemoteAccount::default()
+    };
+    let res = account.client().unwrap().get(&account.url).send().unwrap();
+    assert!(res.status().is_success());
+    let req = proxy.join().unwrap();
+    assert!(req.starts_with("GET http://dav.example.invalid/cards/ HTTP/1.1\r\n"));
+}
+
+#[test]
+/// Tests that the connect timeout applies to the proxy, against a listener whose accept queue is
+/// full so that new connections hang.
+fn test_client_proxy_connect_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut backlog = vec![];
+    while let Ok(stream) = TcpStream::connect_timeout(&addr, Duration::from_millis(100)) {
+        backlog.push(stream);
+    }
+
+    let account = RemoteAccount {
+        url: "http://dav.example.invalid/cards/".into(),
+        proxy: Some(format!("http://{}", addr)),
+        connect_timeout: Some(Duration::from_millis(500)),
+        timeout: Some(Duration::from_secs(60)),
+        ..RemoteAccount::default()
+    };
+    let start = Instant::now();
+    let err = account
+        .client()
+        .unwrap()
+        .get(&account.url)
+        .send()
+        .unwrap_err();
+    assert!(err.is_connect() || err.is_timeout());
+    assert!(start.elapsed() < Duration::from_secs(10));
+    drop(listener);
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use cardamom::config::Cmd;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Entry {
+    passwd_cmd: Cmd,
+}
+
+#[test]
+fn test_cmd_deserialize() {
+    let entry: Entry = toml::from_str(r#"passwd-cmd = "pass show cardamom""#).unwrap();
+    assert_eq!(entry.passwd_cmd, Cmd::Shell("pass show cardamom".into()));
+
+    let entry: Entry = toml::from_str(r#"passwd-cmd = ["pass", "show", "cardamom"]"#).unwrap();
+    assert_eq!(
+        entry.passwd_cmd,
+        Cmd::Argv(vec!["pass".into(), "show".into(), "cardamom".into()])
+    );
+}
+
+#[test]
+fn test_cmd_run() {
+    assert_eq!(Cmd::from("echo secret").run().unwrap(), "secret\n");
+    assert_eq!(
+        Cmd::Argv(vec!["echo".into(), "a; b".into()]).run().unwrap(),
+        "a; b\n"
+    );
+    assert_eq!(Cmd::from("cat").run_with_input("input").unwrap(), "input");
+}
+
+#[test]
+fn test_cmd_run_failure() {
+    let err = Cmd::from("echo $((40 + 2)); echo 'no such entry' >&2; exit 1")
+        .run()
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("no such entry"));
+    assert!(!err.contains("42"));
+
+    let err = Cmd::Argv(vec![]).run().unwrap_err().to_string();
+    assert_eq!(err, "cannot run empty cmd");
+
+    let err = Cmd::from("sleep 5")
+        .run_with_timeout(None, Duration::from_millis(100))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("timed out"));
+}
@@ -0,0 +1,157 @@
+//! Helpers shared by the integration tests. Each test crate uses only part of them.
+#![allow(dead_code)]
+
+pub mod tls;
+
+use chrono::Local;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    env, fs,
+    path::PathBuf,
+};
+
+use cardamom::domain::{Card, CardError, CardRepository};
+
+/// Returns an empty temporary directory for the given test, unique to the test process. The
+/// directory itself is not created.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("cardamom-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+/// In-memory repository checking etags like a CardDAV server, and counting the requests that
+/// transfer cards.
+#[derive(Default)]
+pub struct FakeRepository {
+    cards: RefCell<BTreeMap<String, (String, String)>>,
+    version: Cell<u32>,
+    pub offline: Cell<bool>,
+    pub transfers: Cell<u32>,
+}
+
+impl FakeRepository {
+    /// Stores the card as another client would, changing its etag and the ctag.
+    pub fn put(&self, id: &str, raw: &str) -> String {
+        self.version.set(self.version.get() + 1);
+        let etag = format!("\"{}\"", self.version.get());
+        self.cards
+            .borrow_mut()
+            .insert(id.to_owned(), (etag.clone(), raw.to_owned()));
+        etag
+    }
+
+    /// Returns the stored content of the card, if any.
+    pub fn raw(&self, id: &str) -> Option<String> {
+        self.cards.borrow().get(id).map(|(_, raw)| raw.clone())
+    }
+
+    fn card(&self, id: &str) -> Result<Card, CardError> {
+        let cards = self.cards.borrow();
+        let (etag, raw) = cards
+            .get(id)
+            .ok_or_else(|| CardError::NotFound(id.to_owned()))?;
+        Ok(Card {
+            id: id.to_owned(),
+            etag: Some(etag.clone()),
+            date: Local::now(),
+            raw: raw.clone(),
+        })
+    }
+
+    fn check_online(&self) -> Result<(), CardError> {
+        if self.offline.get() {
+            Err(CardError::Offline)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check(&self, id: &str, etag: Option<&str>) -> Result<(), CardError> {
+        self.check_online()?;
+        let cards = self.cards.borrow();
+        let current = cards.get(id).map(|(etag, _)| etag.as_str());
+        match (etag, current) {
+            (_, None) => Err(CardError::NotFound(id.to_owned())),
+            (Some(etag), Some(current)) if etag != current => Err(CardError::PreconditionFailed {
+                id: id.to_owned(),
+                current_etag: Some(current.to_owned()),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl CardRepository for FakeRepository {
+    fn create(&self, card: &mut Card) -> Result<(), CardError> {
+        self.check_online()?;
+        if self.cards.borrow().contains_key(&card.id) {
+            return Err(CardError::PreconditionFailed {
+                id: card.id.clone(),
+                current_etag: None,
+            });
+        }
+        card.etag = Some(self.put(&card.id, &card.raw));
+        Ok(())
+    }
+
+    fn read(&self, id: &str) -> Result<Card, CardError> {
+        self.check(id, None)?;
+        self.transfers.set(self.transfers.get() + 1);
+        self.card(id)
+    }
+
+    fn read_all(&self) -> Result<Vec<Card>, CardError> {
+        self.check_online()?;
+        self.transfers.set(self.transfers.get() + 1);
+        let ids: Vec<String> = self.cards.borrow().keys().cloned().collect();
+        ids.iter().map(|id| self.card(id)).collect()
+    }
+
+    fn update(&self, card: &mut Card) -> Result<(), CardError> {
+        self.check(&card.id, card.etag.as_deref())?;
+        card.etag = Some(self.put(&card.id, &card.raw));
+        Ok(())
+    }
+
+    fn delete(&self, card: &Card) -> Result<(), CardError> {
+        self.check(&card.id, card.etag.as_deref())?;
+        self.cards.borrow_mut().remove(&card.id);
+        self.version.set(self.version.get() + 1);
+        Ok(())
+    }
+
+    fn ctag(&self) -> Result<Option<String>, CardError> {
+        self.check_online()?;
+        Ok(Some(self.version.get().to_string()))
+    }
+
+    fn read_if_none_match(&self, id: &str, etag: &str) -> Result<Option<Card>, CardError> {
+        self.check_online()?;
+        let card = self.card(id)?;
+        if card.etag.as_deref() == Some(etag) {
+            return Ok(None);
+        }
+        self.transfers.set(self.transfers.get() + 1);
+        Ok(Some(card))
+    }
+}
+
+/// Builds a card from its content lines.
+pub fn card(id: &str, lines: &[&str]) -> Card {
+    Card {
+        id: id.into(),
+        etag: None,
+        date: Local::now(),
+        raw: lines.join("\r\n") + "\r\n",
+    }
+}
+
+/// Builds a vCard 3.0 card from its properties, adding the lines delimiting it.
+pub fn vcard(id: &str, props: &[&str]) -> Card {
+    let mut lines = vec!["BEGIN:VCARD", "VERSION:3.0"];
+    lines.extend(props);
+    lines.push("END:VCARD");
+    card(id, &lines)
+}
@@ -0,0 +1,122 @@
+//! HTTPS servers presenting the TLS fixtures, shared by the TLS tests.
+
+use openssl::{
+    ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode},
+    x509::X509,
+};
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use cardamom::{
+    config::{RemoteAccount, TlsConfig},
+    domain::{
+        card_repositories::{fingerprint, Auth, RemoteCardRepository, RetryPolicy},
+        CardError,
+    },
+};
+
+/// Returns the path of a file of the TLS fixtures.
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Returns the pinned fingerprint of a fixture certificate, in the colon-separated form.
+pub fn pin(name: &str) -> String {
+    let cert = X509::from_pem(&fs::read(fixture(&format!("{}.pem", name))).unwrap()).unwrap();
+    let fingerprint = fingerprint(&cert.to_der().unwrap()).to_uppercase();
+    let bytes: Vec<_> = fingerprint
+        .as_bytes()
+        .chunks(2)
+        .map(|byte| String::from_utf8_lossy(byte).into_owned())
+        .collect();
+    format!("sha256:{}", bytes.join(":"))
+}
+
+/// Spawns an HTTPS server presenting the given certificates in turn, one per connection, the
+/// last one being kept. When a CA is given, clients must present a certificate it issued.
+pub fn tls_server(certs: &[&str], client_ca: Option<&str>) -> String {
+    recording_tls_server(certs, client_ca).0
+}
+
+/// Spawns the same server as [`tls_server`], also returning the heads of the requests received
+/// once the handshake succeeded.
+pub fn recording_tls_server(
+    certs: &[&str],
+    client_ca: Option<&str>,
+) -> (String, Arc<Mutex<Vec<String>>>) {
+    let acceptors: Vec<_> = certs
+        .iter()
+        .map(|name| {
+            let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+            builder
+                .set_certificate_file(fixture(&format!("{}.pem", name)), SslFiletype::PEM)
+                .unwrap();
+            builder
+                .set_private_key_file(fixture(&format!("{}.key", name)), SslFiletype::PEM)
+                .unwrap();
+            if let Some(ca) = client_ca {
+                builder.set_ca_file(fixture(ca)).unwrap();
+                builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            }
+            builder.build()
+        })
+        .collect();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    thread::spawn(move || {
+        for (i, stream) in listener.incoming().enumerate() {
+            let acceptor = &acceptors[i.min(acceptors.len() - 1)];
+            let mut stream = match acceptor.accept(stream.unwrap()) {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let mut req = vec![];
+            let mut buf = [0; 1024];
+            while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => req.extend_from_slice(&buf[..n]),
+                }
+            }
+            received
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&req).into_owned());
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nDAV: 1, addressbook\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+        }
+    });
+
+    (format!("https://localhost:{}", port), requests)
+}
+
+pub fn account(url: String, tls: TlsConfig) -> RemoteAccount {
+    RemoteAccount {
+        url,
+        tls,
+        ..RemoteAccount::default()
+    }
+}
+
+/// Fetches the DAV capabilities of the server with the given authentication, through a card
+/// repository checking the pinned fingerprints of the account.
+pub fn capabilities_with(account: &RemoteAccount, auth: Auth) -> Result<Vec<String>, CardError> {
+    let client = account.client().unwrap();
+    RemoteCardRepository::undiscovered(&account.url, &client, auth, RetryPolicy::none())
+        .with_pinned_fingerprints(account.tls.fingerprints())
+        .fetch_dav_capabilities("/")
+}
+
+/// Fetches the DAV capabilities of the server without authentication.
+pub fn capabilities(account: &RemoteAccount) -> Result<Vec<String>, CardError> {
+    capabilities_with(account, Auth::None)
+}
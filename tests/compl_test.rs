@@ -0,0 +1,50 @@
+use cardamom::{
+    compl::{compl_arg, compl_handler},
+    config::{account_arg, config_arg},
+    domain::card_arg,
+};
+
+fn app<'a>() -> clap::App<'a, 'a> {
+    clap::App::new("cardamom")
+        .args(&config_arg::args())
+        .subcommands(config_arg::subcmds())
+        .subcommands(account_arg::subcmds())
+        .subcommands(card_arg::subcmds())
+        .subcommands(compl_arg::subcmds())
+}
+
+fn generate(shell: &str) -> String {
+    let mut script = vec![];
+    compl_handler::generate(app(), shell, &mut script).unwrap();
+    String::from_utf8(script).unwrap()
+}
+
+#[test]
+fn test_generate_static_scripts() {
+    for shell in clap::Shell::variants().iter() {
+        assert!(!generate(shell).is_empty(), "empty {} script", shell);
+    }
+    assert!(compl_handler::generate(app(), "tcsh", &mut vec![]).is_err());
+}
+
+#[test]
+fn test_generate_dynamic_scripts() {
+    let bash = generate("bash");
+    assert!(bash.contains("_cardamom() {"));
+    assert!(bash.contains("complete-values accounts"));
+    assert!(bash.contains("read|update|delete)"));
+    assert!(bash
+        .trim_end()
+        .ends_with("complete -F _cardamom_dynamic -o bashdefault -o default cardamom"));
+
+    let zsh = generate("zsh");
+    assert!(zsh.starts_with("#compdef cardamom\n"));
+    assert!(zsh.find("_cardamom_values() {") < zsh.find("_cardamom \"$@\""));
+    assert!(zsh
+        .contains("'--account=[Selects a specific account]:account:_cardamom_values accounts' \\"));
+    assert!(zsh.contains("':id -- Specifies the card id:_cardamom_values ids' \\"));
+    assert!(!zsh.contains("card id:_files"));
+
+    let fish = generate("fish");
+    assert!(fish.contains("-l account -x -a '(cardamom (__cardamom_opts) complete-values accounts"));
+}
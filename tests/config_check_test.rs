@@ -0,0 +1,102 @@
+use cardamom::config::{Config, ConfigIssue, ConfigPosition};
+
+fn pos(line: usize, col: usize) -> Option<ConfigPosition> {
+    Some(ConfigPosition { line, col })
+}
+
+#[test]
+fn test_config_parse_syntax_error() {
+    let err =
+        Config::parse("[work.remote]\nurl = \"https://example.com\"\nlogin = \n").unwrap_err();
+    match err {
+        ConfigIssue::Syntax { line, col, .. } => assert_eq!((line, col), (3, 9)),
+        issue => panic!("unexpected issue {:?}", issue),
+    }
+}
+
+#[test]
+fn test_config_check_issues() {
+    let (config, mut issues) = Config::parse(
+        r#"
+        [work.remote]
+        default = true
+        url = "https://dav.example.com"
+        pasword-cmd = "pass show work"
+
+        [personal.remote]
+        default = true
+        url = "dav.example.com"
+
+        [other.remote]
+        url = "ftp://dav.example.com"
+
+        [cards.local]
+        path = "/does/not/exist"
+
+        [broken.remote]
+        url = 42
+        "#,
+    )
+    .unwrap();
+    issues.extend(config.validate());
+
+    assert_eq!(
+        issues,
+        vec![
+            ConfigIssue::InvalidAccount {
+                account: "broken".into(),
+                msg: "invalid type: integer `42`, expected a string".into(),
+                pos: pos(18, 15),
+            },
+            ConfigIssue::UnknownKey {
+                account: "work".into(),
+                key: "pasword-cmd".into(),
+                pos: pos(5, 9),
+            },
+            ConfigIssue::DuplicateDefaults(vec!["personal".into(), "work".into()]),
+            ConfigIssue::MissingPath {
+                account: "cards".into(),
+                path: "/does/not/exist".into(),
+                pos: pos(15, 9),
+            },
+            ConfigIssue::InvalidUrl {
+                account: "other".into(),
+                url: "ftp://dav.example.com".into(),
+                reason: r#"unsupported scheme "ftp""#.into(),
+                pos: pos(12, 9),
+            },
+            ConfigIssue::InvalidUrl {
+                account: "personal".into(),
+                url: "dav.example.com".into(),
+                reason: "relative URL without a base".into(),
+                pos: pos(9, 9),
+            },
+        ]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        r#"cannot parse account "broken" at line 18, column 15: invalid type: integer `42`, expected a string"#
+    );
+    assert!(issues[0].is_error());
+    assert!(!issues[1].is_error());
+}
+
+#[test]
+fn test_config_check_missing_default() {
+    let (config, _) = Config::parse(
+        r#"
+        [a.remote]
+        url = "https://a.example.com"
+        [b.remote]
+        url = "https://b.example.com"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.validate(),
+        vec![ConfigIssue::MissingDefault(vec!["a".into(), "b".into()])]
+    );
+
+    let (config, _) = Config::parse("[a.remote]\nurl = \"https://a.example.com\"").unwrap();
+    assert_eq!(config.validate(), vec![]);
+}
@@ -0,0 +1,115 @@
+use cardamom::config::{
+    AuthConfig, Cmd, Config, ConfigAccountEntry, LocalConfigAccountEntry, PasswdAuthConfig,
+    RemoteConfigAccountEntry,
+};
+
+fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(key, val)| (key.to_string(), val.to_string()))
+        .collect()
+}
+
+fn remote<'a>(config: &'a Config, name: &str) -> &'a RemoteConfigAccountEntry {
+    match config.accounts.get(name) {
+        Some(ConfigAccountEntry::Remote(entry)) => entry,
+        entry => panic!("unexpected account entry {:?}", entry),
+    }
+}
+
+#[test]
+fn test_config_merge_env_overrides() {
+    let mut config: Config = toml::from_str(
+        r#"
+        [my-work.remote]
+        default = true
+        url = "https://dav.example.com"
+        login = "alice"
+        passwd-keyring = "work"
+
+        [digest.remote]
+        url = "https://digest.example.com"
+        auth = { type = "digest", login = "bob", passwd-cmd = "pass show bob" }
+
+        [local.local]
+        path = "/tmp/cards"
+        "#,
+    )
+    .unwrap();
+
+    config
+        .merge_env(vars(&[
+            ("CARDAMOM_ACCOUNT_MY_WORK_LOGIN", "carol"),
+            ("CARDAMOM_ACCOUNT_MY_WORK_PASSWD_CMD", "echo secret"),
+            ("CARDAMOM_ACCOUNT_DIGEST_LOGIN", "dave"),
+            ("CARDAMOM_ACCOUNT_LOCAL_PATH", "/tmp/other"),
+            ("CARDAMOM_CONFIG", "/dev/null"),
+            ("HOME", "/root"),
+        ]))
+        .unwrap();
+
+    let entry = remote(&config, "my-work");
+    assert_eq!(entry.url, "https://dav.example.com");
+    assert_eq!(entry.login, "carol");
+    assert_eq!(entry.passwd_cmd, Cmd::from("echo secret"));
+    assert_eq!(entry.passwd_keyring, None);
+
+    assert_eq!(
+        remote(&config, "digest").auth,
+        Some(AuthConfig::Digest(PasswdAuthConfig {
+            login: "dave".into(),
+            passwd_cmd: "pass show bob".into(),
+            ..PasswdAuthConfig::default()
+        }))
+    );
+
+    assert_eq!(
+        config.accounts.get("local"),
+        Some(&ConfigAccountEntry::Local(LocalConfigAccountEntry {
+            default: None,
+            path: "/tmp/other".into(),
+            ..LocalConfigAccountEntry::default()
+        }))
+    );
+
+    let err = config
+        .merge_env(vars(&[(
+            "CARDAMOM_ACCOUNT_LOCAL_URL",
+            "https://example.com",
+        )]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"cannot override url of account "local" from env"#
+    );
+}
+
+#[test]
+fn test_config_merge_env_only() {
+    let mut config = Config::default();
+    config
+        .merge_env(vars(&[
+            ("CARDAMOM_ACCOUNT_CI_PASSWD_CMD", "echo secret"),
+            ("CARDAMOM_ACCOUNT_CI_URL", "https://dav.example.com"),
+            ("CARDAMOM_ACCOUNT_CI_LOGIN", "ci"),
+        ]))
+        .unwrap();
+
+    assert_eq!(
+        remote(&config, "ci"),
+        &RemoteConfigAccountEntry {
+            default: Some(true),
+            url: "https://dav.example.com".into(),
+            login: "ci".into(),
+            passwd_cmd: "echo secret".into(),
+            ..RemoteConfigAccountEntry::default()
+        }
+    );
+
+    let err = Config::default()
+        .merge_env(vars(&[("CARDAMOM_ACCOUNT_CI_LOGIN", "ci")]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot find account matching env var CARDAMOM_ACCOUNT_CI_LOGIN"
+    );
+}
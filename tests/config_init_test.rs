@@ -0,0 +1,51 @@
+use std::{env, fs, io::Cursor};
+
+use cardamom::config::{config_handler, Config, ConfigAccountEntry};
+
+#[test]
+fn test_config_init() {
+    let dir = env::temp_dir().join(format!("cardamom-init-{}", std::process::id()));
+    let cards_dir = dir.join("cards");
+    let path = dir.join("config.toml");
+    let path_str = path.to_str().unwrap();
+
+    let mut output = vec![];
+    let answers = format!("\nlocal\n{}\n\n", cards_dir.display());
+    config_handler::init(Some(path_str), &mut Cursor::new(answers), &mut output).unwrap();
+    assert!(cards_dir.is_dir());
+
+    // The server is unreachable, the account is saved anyway.
+    let answers = "work\nnope\nremote\nhttp://127.0.0.1:1\nalice\necho secret\ny\n";
+    config_handler::init(Some(path_str), &mut Cursor::new(answers), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(r#"Unknown account type "nope"."#));
+    assert!(output.contains("cannot discover addressbooks"));
+
+    let (config, issues) = Config::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(issues, vec![]);
+    assert_eq!(config.validate(), vec![]);
+    match config.accounts.get("default") {
+        Some(ConfigAccountEntry::Local(entry)) => {
+            assert_eq!(entry.default, Some(true));
+            assert_eq!(entry.path, cards_dir.to_str().unwrap());
+        }
+        entry => panic!("unexpected account entry {:?}", entry),
+    }
+    match config.accounts.get("work") {
+        Some(ConfigAccountEntry::Remote(entry)) => {
+            assert_eq!(entry.default, None);
+            assert_eq!(entry.url, "http://127.0.0.1:1");
+            assert_eq!(entry.login, "alice");
+            assert_eq!(entry.passwd_cmd, "echo secret".into());
+        }
+        entry => panic!("unexpected account entry {:?}", entry),
+    }
+
+    // An existing account name is asked again, and the account is not saved on refusal.
+    let answers = "work\nhome\nremote\nhttp://127.0.0.1:1\n\n\nn\n";
+    let err =
+        config_handler::init(Some(path_str), &mut Cursor::new(answers), &mut vec![]).unwrap_err();
+    assert_eq!(err.to_string(), "account not saved");
+
+    fs::remove_dir_all(dir).unwrap();
+}
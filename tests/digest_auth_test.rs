@@ -0,0 +1,142 @@
+use reqwest::blocking::Client;
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use cardamom::domain::card_repositories::{
+    Auth, DigestAlgorithm, DigestAuth, DigestChallenge, RemoteCardRepository, RetryPolicy,
+};
+
+/// Builds the challenge of the RFC 7616 example (section 3.9.1).
+fn rfc7616_challenge(algorithm: &str) -> DigestChallenge {
+    DigestChallenge::parse(&format!(
+        r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm={}, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+        algorithm
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_digest_challenge_parse() {
+    let challenge = rfc7616_challenge("SHA-256");
+
+    assert_eq!(challenge.realm, "http-auth@example.org");
+    assert_eq!(
+        challenge.nonce,
+        "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v"
+    );
+    assert_eq!(
+        challenge.opaque.as_deref(),
+        Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS")
+    );
+    assert_eq!(challenge.algorithm, DigestAlgorithm::Sha256);
+    assert!(challenge.qop_auth);
+    assert!(!challenge.stale);
+
+    assert_eq!(DigestChallenge::parse(r#"Basic realm="test""#), None);
+    assert_eq!(
+        DigestChallenge::parse(r#"Digest realm="test", nonce="n", algorithm=unknown"#),
+        None
+    );
+}
+
+#[test]
+fn test_digest_response() {
+    let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+    let response = |algorithm| {
+        rfc7616_challenge(algorithm).response(
+            "Mufasa",
+            "Circle of Life",
+            "GET",
+            "/dir/index.html",
+            1,
+            cnonce,
+        )
+    };
+
+    assert_eq!(response("MD5"), "8ca523f5e9506fed4657c9700eebdbec");
+    assert_eq!(
+        response("SHA-256"),
+        "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+    );
+
+    let header = rfc7616_challenge("MD5").authorization(
+        "Mufasa",
+        "Circle of Life",
+        "GET",
+        "/dir/index.html",
+        1,
+        cnonce,
+    );
+    assert!(header.starts_with(r#"Digest username="Mufasa", realm="http-auth@example.org""#));
+    assert!(header.contains(
+        r#"qop=auth, nc=00000001, cnonce="f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ""#
+    ));
+    assert!(header.ends_with(r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#));
+}
+
+/// Spawns a server asking for credentials with the given challenge, and accepting any
+/// credentials of the same scheme. Returns the authorization headers received, if any.
+fn challenging_server(challenge: &'static str) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(vec![]));
+    let authorizations = received.clone();
+    let scheme = challenge.split(' ').next().unwrap().to_owned();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap_or(0);
+            let req = String::from_utf8_lossy(&buf[..n]).into_owned();
+            let authorization = req
+                .lines()
+                .find(|line| line.to_lowercase().starts_with("authorization:"))
+                .map(|line| line["authorization:".len()..].trim().to_owned());
+            let res = match authorization.as_deref() {
+                Some(header) if header.starts_with(&scheme) => {
+                    "HTTP/1.1 200 OK\r\nDAV: 1, addressbook\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned()
+                }
+                _ => format!(
+                    "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    challenge
+                ),
+            };
+            received.lock().unwrap().push(authorization);
+            let _ = stream.write_all(res.as_bytes());
+        }
+    });
+    (url, authorizations)
+}
+
+#[test]
+/// Tests that a negotiating authentication sends no credentials before the server asks for
+/// them, then uses the scheme of the challenge.
+fn test_digest_negotiate() {
+    let client = Client::new();
+    for challenge in &[r#"Basic realm="test""#, r#"Digest realm="test", nonce="n""#] {
+        let (url, authorizations) = challenging_server(challenge);
+        let auth = Auth::Digest(DigestAuth::negotiate("user".into(), "secret".into()));
+        let repository =
+            RemoteCardRepository::undiscovered(&url, &client, auth, RetryPolicy::none());
+        assert_eq!(
+            repository.fetch_dav_capabilities("/").unwrap(),
+            vec!["1", "addressbook"]
+        );
+
+        let authorizations = authorizations.lock().unwrap();
+        assert_eq!(authorizations.len(), 2);
+        assert_eq!(authorizations[0], None);
+        let scheme = challenge.split(' ').next().unwrap();
+        assert!(authorizations[1].as_deref().unwrap().starts_with(scheme));
+    }
+
+    let (url, authorizations) = challenging_server(r#"Basic realm="test""#);
+    let auth = Auth::Digest(DigestAuth::new("user".into(), "secret".into()));
+    let repository = RemoteCardRepository::undiscovered(&url, &client, auth, RetryPolicy::none());
+    assert!(repository.fetch_dav_capabilities("/").is_err());
+    assert_eq!(*authorizations.lock().unwrap(), vec![None]);
+}
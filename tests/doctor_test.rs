@@ -0,0 +1,201 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use cardamom::{
+    config::{
+        doctor_handler::diagnose, Cmd, DoctorStatus, PasswdScheme, PasswdSource, RemoteAccount,
+        DOCTOR_STEPS,
+    },
+    domain::card_repositories::RetryPolicy,
+};
+
+const BASIC_AUTH: &str = "Basic YWxpY2U6c2VjcmV0"; // alice:secret
+
+fn multistatus(responses: &str) -> String {
+    format!(
+        r#"<?xml version="1.0"?>
+        <D:multistatus xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">{}</D:multistatus>"#,
+        responses
+    )
+}
+
+fn response(href: &str, prop: &str) -> String {
+    format!(
+        "<D:response><D:href>{}</D:href><D:propstat><D:prop>{}</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+        href, prop
+    )
+}
+
+/// Answers a request of the mock CardDAV server.
+fn answer(method: &str, path: &str, body: &str) -> (&'static str, Vec<&'static str>, String) {
+    let ok = "207 Multi-Status";
+    match (method, path) {
+        ("PROPFIND", "/") if body.contains("current-user-principal") => (
+            ok,
+            vec![],
+            multistatus(&response(
+                "/",
+                "<D:current-user-principal><D:href>/principals/alice/</D:href></D:current-user-principal>",
+            )),
+        ),
+        ("PROPFIND", "/principals/alice/") => (
+            ok,
+            vec![],
+            multistatus(&response(
+                "/principals/alice/",
+                "<C:addressbook-home-set><D:href>/alice/</D:href></C:addressbook-home-set>",
+            )),
+        ),
+        ("PROPFIND", "/alice/") => (
+            ok,
+            vec![],
+            multistatus(&format!(
+                "{}{}",
+                response("/alice/", "<D:resourcetype><D:collection/></D:resourcetype>"),
+                response(
+                    "/alice/contacts/",
+                    "<D:resourcetype><D:collection/><C:addressbook/></D:resourcetype>",
+                ),
+            )),
+        ),
+        ("OPTIONS", "/alice/contacts/") => ("200 OK", vec!["DAV: 1, 2, 3, addressbook"], "".into()),
+        ("PROPFIND", "/alice/contacts/") => (
+            ok,
+            vec![],
+            multistatus(&response(
+                "/alice/contacts/",
+                "<D:supported-report-set><D:supported-report><D:report><D:sync-collection/></D:report></D:supported-report></D:supported-report-set>",
+            )),
+        ),
+        _ => ("404 Not Found", vec![], "".into()),
+    }
+}
+
+fn serve(stream: TcpStream) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut len = 0;
+    let mut authorization = String::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, val) = header.split_once(':').unwrap();
+        match name.to_lowercase().as_str() {
+            "content-length" => len = val.trim().parse().unwrap(),
+            "authorization" => authorization = val.trim().to_owned(),
+            _ => (),
+        }
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).unwrap();
+
+    let (status, headers, body) = if authorization == BASIC_AUTH {
+        answer(&method, &path, &String::from_utf8_lossy(&body))
+    } else {
+        (
+            "401 Unauthorized",
+            vec![r#"WWW-Authenticate: Basic realm="test""#],
+            "".into(),
+        )
+    };
+    let mut res = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+    for header in headers {
+        res.push_str(header);
+        res.push_str("\r\n");
+    }
+    res.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    stream.write_all(res.as_bytes()).ok();
+}
+
+/// Spawns the mock CardDAV server, returning its URL.
+fn spawn_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || serve(stream));
+        }
+    });
+    format!("http://{}", addr)
+}
+
+fn account(url: &str, passwd: &str) -> RemoteAccount {
+    RemoteAccount {
+        name: "test".into(),
+        url: url.into(),
+        login: "alice".into(),
+        passwd_source: PasswdSource::Cmd(Cmd::Argv(vec!["echo".into(), passwd.into()])),
+        passwd_scheme: PasswdScheme::Basic,
+        retry: RetryPolicy::none(),
+        ..RemoteAccount::default()
+    }
+}
+
+fn statuses(account: &RemoteAccount) -> Vec<(&'static str, DoctorStatus)> {
+    diagnose(account)
+        .into_iter()
+        .map(|check| (check.step, check.status))
+        .collect()
+}
+
+#[test]
+fn test_doctor_all_steps() {
+    let url = spawn_server();
+    let checks = diagnose(&account(&url, "secret"));
+
+    let steps: Vec<&str> = checks.iter().map(|check| check.step).collect();
+    assert_eq!(steps, DOCTOR_STEPS);
+
+    let failed: Vec<_> = checks.iter().filter(|check| check.is_fail()).collect();
+    assert!(failed.is_empty(), "{:#?}", failed);
+    assert_eq!(checks[2].status, DoctorStatus::Warn);
+    assert!(checks[2].hint.is_some());
+    assert_eq!(checks[4].detail, "found at /principals/alice/");
+    assert_eq!(checks[5].detail, "found at /alice/");
+    assert_eq!(checks[6].detail, "found /alice/contacts/");
+    assert_eq!(checks[7].detail, "DAV: 1, 2, 3, addressbook");
+    assert_eq!(checks[8].status, DoctorStatus::Pass);
+}
+
+#[test]
+fn test_doctor_rejected_credentials() {
+    let url = spawn_server();
+    assert_eq!(
+        statuses(&account(&url, "wrong")),
+        vec![
+            ("dns", DoctorStatus::Pass),
+            ("connect", DoctorStatus::Pass),
+            ("tls", DoctorStatus::Warn),
+            ("auth", DoctorStatus::Fail),
+            ("current-user-principal", DoctorStatus::Skip),
+            ("addressbook-home-set", DoctorStatus::Skip),
+            ("addressbooks", DoctorStatus::Skip),
+            ("dav-capabilities", DoctorStatus::Skip),
+            ("sync-collection", DoctorStatus::Skip),
+        ]
+    );
+}
+
+#[test]
+fn test_doctor_unreachable_server() {
+    let checks = diagnose(&account("http://127.0.0.1:1", "secret"));
+    assert_eq!(checks[0].status, DoctorStatus::Pass);
+    assert_eq!(checks[1].status, DoctorStatus::Fail);
+    assert!(checks[1].hint.as_deref().unwrap().contains("port 1"));
+    assert!(checks[2..]
+        .iter()
+        .all(|check| check.status == DoctorStatus::Skip));
+}
@@ -0,0 +1,114 @@
+use cardamom::domain::{strip_urn_uuid, Group, GroupKind};
+
+mod common;
+use common::card;
+
+#[test]
+fn test_vcard4_group() {
+    let mut group = card(
+        "friends",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "UID:urn:uuid:friends",
+            "KIND:group",
+            "FN:Friends",
+            "MEMBER:urn:uuid:jane",
+            "END:VCARD",
+        ],
+    );
+    let jane = card(
+        "jane",
+        &["BEGIN:VCARD", "UID:urn:uuid:jane", "FN:Jane", "END:VCARD"],
+    );
+    let john = card("john.vcf", &["BEGIN:VCARD", "FN:John", "END:VCARD"]);
+
+    let parsed = Group::from_card(&group).unwrap();
+    assert_eq!(parsed.kind, GroupKind::Vcard4);
+    assert_eq!(parsed.name, "Friends");
+    assert_eq!(parsed.members, vec!["jane"]);
+    assert!(parsed.has_member(&jane));
+    assert!(!parsed.has_member(&john));
+
+    assert!(!group.add_member(&jane.member_uid()));
+    assert!(group.add_member(&john.member_uid()));
+    assert!(group
+        .raw
+        .ends_with("MEMBER:urn:uuid:jane\r\nMEMBER:urn:uuid:john.vcf\r\nEND:VCARD\r\n"));
+
+    assert!(group.remove_member("urn:uuid:JANE"));
+    assert!(!group.remove_member("jane"));
+    assert_eq!(Group::from_card(&group).unwrap().members, vec!["john.vcf"]);
+}
+
+#[test]
+fn test_apple_group() {
+    let mut group = card(
+        "family",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:3.0",
+            "PRODID:-//Apple Inc.//macOS 12.0//EN",
+            "N:Family",
+            "FN:Family",
+            "X-ADDRESSBOOKSERVER-KIND:group",
+            "X-ADDRESSBOOKSERVER-MEMBER:urn:uuid:4D6A4E2C-0C5B-4C7E-9F0C-0A1B2C3D4E5F-ABCDE",
+            " F01234",
+            "X-ADDRESSBOOKSERVER-MEMBER:urn:uuid:jane",
+            "END:VCARD",
+        ],
+    );
+
+    let parsed = Group::from_card(&group).unwrap();
+    assert_eq!(parsed.kind, GroupKind::Apple);
+    assert_eq!(
+        parsed.members,
+        vec!["4D6A4E2C-0C5B-4C7E-9F0C-0A1B2C3D4E5F-ABCDEF01234", "jane"]
+    );
+
+    assert!(group.add_member("john"));
+    assert!(group
+        .raw
+        .contains("X-ADDRESSBOOKSERVER-MEMBER:urn:uuid:john\r\nEND:VCARD"));
+
+    // Folded member lines are removed as a whole.
+    assert!(group.remove_member("4D6A4E2C-0C5B-4C7E-9F0C-0A1B2C3D4E5F-ABCDEF01234"));
+    assert!(!group.raw.contains("F01234"));
+    assert!(group
+        .raw
+        .starts_with("BEGIN:VCARD\r\nVERSION:3.0\r\nPRODID:"));
+}
+
+#[test]
+fn test_not_a_group() {
+    let mut contact = card(
+        "jane",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "KIND:individual",
+            "FN:Jane",
+            "END:VCARD",
+        ],
+    );
+    assert_eq!(Group::from_card(&contact), None);
+    assert!(!contact.add_member("john"));
+    assert!(!contact.raw.contains("MEMBER"));
+}
+
+#[test]
+fn test_new_group_card() {
+    let card = Group::new_card("Work, Team", GroupKind::Vcard4);
+    let group = Group::from_card(&card).unwrap();
+    assert_eq!(group.kind, GroupKind::Vcard4);
+    assert_eq!(group.name, "Work, Team");
+    assert!(group.members.is_empty());
+    assert_eq!(card.member_uid(), card.id);
+    assert!(card.raw.contains("FN:Work\\, Team\r\n"));
+
+    let card = Group::new_card("Family", GroupKind::Apple);
+    let group = Group::from_card(&card).unwrap();
+    assert_eq!(group.kind, GroupKind::Apple);
+    assert!(card.raw.contains("VERSION:3.0\r\n"));
+    assert_eq!(strip_urn_uuid(&card.uid().unwrap()), card.id);
+}
@@ -0,0 +1,87 @@
+use chrono::{Local, TimeZone, Utc};
+
+use cardamom::domain::{event_uid, fold, to_ics, Card, CardEvent, EventKind, PartialDate};
+
+fn event(card_id: &str, name: &str, kind: EventKind, date: &str) -> CardEvent {
+    CardEvent {
+        card_id: card_id.into(),
+        name: name.into(),
+        kind,
+        date: PartialDate::parse(date).unwrap(),
+    }
+}
+
+fn unfolded(ics: &str) -> Vec<String> {
+    ics.replace("\r\n ", "")
+        .split("\r\n")
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn test_event_uid_is_stable() {
+    let card = Card {
+        id: "jane".into(),
+        etag: None,
+        date: Local::now(),
+        raw: ["BEGIN:VCARD", "FN:Jane", "BDAY:1990-04-15", "END:VCARD"].join("\r\n"),
+    };
+    let events = CardEvent::from_card(&card);
+    assert_eq!(event_uid(&events[0]), "jane-birthday@cardamom");
+
+    let renamed = event("jane", "Jane Doe", EventKind::Birthday, "1991-05-16");
+    assert_eq!(event_uid(&events[0]), event_uid(&renamed));
+}
+
+#[test]
+fn test_to_ics() {
+    let now = Utc.ymd(2021, 6, 1).and_hms(12, 0, 0);
+    let events = vec![
+        event("jane", "Jane, Doe", EventKind::Birthday, "1990-04-15"),
+        event("john", "John", EventKind::Anniversary, "--0229"),
+    ];
+    let ics = to_ics(&events, None, now);
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    let lines = unfolded(&ics);
+
+    assert_eq!(lines[0], "BEGIN:VCALENDAR");
+    assert!(lines.contains(&"VERSION:2.0".into()));
+    assert_eq!(lines.iter().filter(|l| *l == "BEGIN:VEVENT").count(), 2);
+    assert!(lines.contains(&"UID:jane-birthday@cardamom".into()));
+    assert!(lines.contains(&"DTSTAMP:20210601T120000Z".into()));
+    assert!(lines.contains(&"DTSTART;VALUE=DATE:19900415".into()));
+    assert!(lines.contains(&"DTEND;VALUE=DATE:19900416".into()));
+    assert!(lines.contains(&"RRULE:FREQ=YEARLY".into()));
+    assert!(lines.contains(&"SUMMARY:Birthday of Jane\\, Doe".into()));
+    assert!(lines.contains(&"DESCRIPTION:Since 1990".into()));
+
+    assert!(lines.contains(&"UID:john-anniversary@cardamom".into()));
+    assert!(lines.contains(&"DTSTART;VALUE=DATE:20000229".into()));
+    assert!(lines.contains(&"DTEND;VALUE=DATE:20000301".into()));
+    assert!(lines.contains(&"RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1".into()));
+    assert!(!lines.iter().any(|l| l == "BEGIN:VALARM"));
+}
+
+#[test]
+fn test_to_ics_alarm() {
+    let now = Utc.ymd(2021, 6, 1).and_hms(12, 0, 0);
+    let events = vec![event("jane", "Jane", EventKind::Birthday, "--0415")];
+
+    let lines = unfolded(&to_ics(&events, Some(3), now));
+    assert!(lines.contains(&"BEGIN:VALARM".into()));
+    assert!(lines.contains(&"TRIGGER:-P3D".into()));
+    assert!(!lines.iter().any(|l| l.starts_with("DESCRIPTION:Since")));
+
+    let lines = unfolded(&to_ics(&events, Some(0), now));
+    assert!(lines.contains(&"TRIGGER:PT0S".into()));
+}
+
+#[test]
+fn test_fold() {
+    assert_eq!(fold("SUMMARY:short"), "SUMMARY:short\r\n");
+
+    let line = format!("SUMMARY:{}", "é".repeat(50));
+    let folded = fold(&line);
+    assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+    assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", line));
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    convert::{TryFrom, TryInto},
+    env,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+};
+use zbus::{
+    blocking::{Connection, ConnectionBuilder},
+    dbus_interface, fdo,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    ObjectServer,
+};
+
+use cardamom::config::Keyring;
+
+const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/login";
+const SESSION_PATH: &str = "/org/freedesktop/secrets/session/1";
+
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+type Items = Arc<Mutex<Vec<(HashMap<String, String>, Vec<u8>)>>>;
+
+fn path(path: &str) -> OwnedObjectPath {
+    ObjectPath::try_from(path.to_owned()).unwrap().into()
+}
+
+fn item_path(index: usize) -> OwnedObjectPath {
+    path(&format!("{}/{}", COLLECTION_PATH, index))
+}
+
+/// Minimal Secret Service stand-in, supporting plain sessions only.
+struct MockService {
+    items: Items,
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Service")]
+impl MockService {
+    fn open_session(
+        &self,
+        algorithm: &str,
+        _input: Value<'_>,
+    ) -> fdo::Result<(OwnedValue, OwnedObjectPath)> {
+        match algorithm {
+            "plain" => Ok((Value::from("").into(), path(SESSION_PATH))),
+            _ => Err(fdo::Error::NotSupported(algorithm.to_owned())),
+        }
+    }
+
+    fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+    ) -> (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) {
+        let items = self.items.lock().unwrap();
+        let unlocked = items
+            .iter()
+            .enumerate()
+            .filter(|(_, (attrs, _))| attributes.iter().all(|(k, v)| attrs.get(k) == Some(v)))
+            .map(|(index, _)| item_path(index))
+            .collect();
+        (unlocked, vec![])
+    }
+
+    fn unlock(&self, objects: Vec<OwnedObjectPath>) -> (Vec<OwnedObjectPath>, OwnedObjectPath) {
+        (objects, path("/"))
+    }
+
+    fn read_alias(&self, _name: &str) -> OwnedObjectPath {
+        path(COLLECTION_PATH)
+    }
+}
+
+struct MockCollection {
+    items: Items,
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Collection")]
+impl MockCollection {
+    async fn create_item(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        properties: HashMap<String, OwnedValue>,
+        secret: Secret,
+        replace: bool,
+    ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+        let attrs: HashMap<String, String> = properties
+            .get("org.freedesktop.Secret.Item.Attributes")
+            .cloned()
+            .and_then(|attrs| attrs.try_into().ok())
+            .ok_or_else(|| fdo::Error::InvalidArgs("missing attributes".into()))?;
+
+        let index = {
+            let mut items = self.items.lock().unwrap();
+            match items.iter().position(|(a, _)| replace && *a == attrs) {
+                Some(index) => {
+                    items[index].1 = secret.2;
+                    return Ok((item_path(index), path("/")));
+                }
+                None => {
+                    items.push((attrs, secret.2));
+                    items.len() - 1
+                }
+            }
+        };
+
+        let item = MockItem {
+            items: self.items.clone(),
+            index,
+        };
+        server.at(item_path(index), item).await?;
+        Ok((item_path(index), path("/")))
+    }
+
+    #[dbus_interface(property)]
+    fn locked(&self) -> bool {
+        false
+    }
+}
+
+struct MockItem {
+    items: Items,
+    index: usize,
+}
+
+#[dbus_interface(name = "org.freedesktop.Secret.Item")]
+impl MockItem {
+    fn get_secret(&self, session: OwnedObjectPath) -> Secret {
+        let items = self.items.lock().unwrap();
+        let value = items[self.index].1.clone();
+        (session, vec![], value, "text/plain".into())
+    }
+
+    #[dbus_interface(property)]
+    fn locked(&self) -> bool {
+        false
+    }
+}
+
+/// Spawns a private session bus, returning its process and address.
+fn spawn_bus() -> Option<(Child, String)> {
+    let mut bus = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address=1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut addr = String::new();
+    BufReader::new(bus.stdout.as_mut()?)
+        .read_line(&mut addr)
+        .ok()?;
+    Some((bus, addr.trim().to_owned()))
+}
+
+#[test]
+fn test_keyring() {
+    let (mut bus, addr) = match spawn_bus() {
+        Some(bus) => bus,
+        None => {
+            eprintln!("dbus-daemon not found, skipping keyring test");
+            return;
+        }
+    };
+    env::set_var("DBUS_SESSION_BUS_ADDRESS", &addr);
+
+    let items = Items::default();
+    let _service: Connection = ConnectionBuilder::address(addr.as_str())
+        .unwrap()
+        .name("org.freedesktop.secrets")
+        .unwrap()
+        .serve_at(
+            "/org/freedesktop/secrets",
+            MockService {
+                items: items.clone(),
+            },
+        )
+        .unwrap()
+        .serve_at(
+            COLLECTION_PATH,
+            MockCollection {
+                items: items.clone(),
+            },
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let keyring = Keyring::new("personal");
+    assert!(keyring.get_passwd().is_err());
+
+    keyring.set_passwd("s3cret").unwrap();
+    assert_eq!(keyring.get_passwd().unwrap(), "s3cret");
+
+    keyring.set_passwd("n3w s3cret").unwrap();
+    assert_eq!(keyring.get_passwd().unwrap(), "n3w s3cret");
+    assert_eq!(items.lock().unwrap().len(), 1);
+    assert_eq!(
+        items.lock().unwrap()[0]
+            .0
+            .get("service")
+            .map(String::as_str),
+        Some("cardamom")
+    );
+
+    assert!(Keyring::new("other").get_passwd().is_err());
+
+    bus.kill().ok();
+}
@@ -0,0 +1,157 @@
+use serde::{
+    de::{self, value, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+use std::{env, fs};
+
+use cardamom::{
+    compl::compl_arg,
+    config::{
+        account_arg, config_arg, LocalConfigAccountEntry, OAuth2Config, PasswdAuthConfig,
+        RemoteConfigAccountEntry, RetryConfig, SqliteConfigAccountEntry, TlsConfig,
+    },
+    domain::card_arg,
+    man::{man_arg, man_handler},
+    output::output_arg,
+};
+
+fn app<'a>() -> clap::App<'a, 'a> {
+    clap::App::new("cardamom")
+        .version("1.2.3")
+        .about("CLI to manage contacts")
+        .args(&config_arg::args())
+        .args(&output_arg::args())
+        .subcommands(config_arg::subcmds())
+        .subcommands(account_arg::subcmds())
+        .subcommands(card_arg::subcmds())
+        .subcommands(compl_arg::subcmds())
+        .subcommands(man_arg::subcmds())
+}
+
+/// Deserializer recording the names of the fields of the struct it is asked for.
+struct FieldNames<'a>(&'a mut Vec<&'static str>);
+
+impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.extend(fields);
+        Err(de::Error::custom("fields recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+fn fields<'de, T: Deserialize<'de>>() -> Vec<&'static str> {
+    let mut fields = vec![];
+    T::deserialize(FieldNames(&mut fields)).ok();
+    fields
+}
+
+#[test]
+fn test_man_pages() {
+    let pages = man_handler::pages(&app()).unwrap();
+    let names: Vec<String> = pages.iter().map(|page| page.file_name()).collect();
+    assert_eq!(names[0], "cardamom.1");
+    assert!(names.contains(&"cardamom-config-check.1".to_owned()));
+    assert!(names.contains(&"cardamom-read.1".to_owned()));
+    assert!(names.contains(&"cardamom.toml.5".to_owned()));
+    assert!(!names.iter().any(|name| name.contains("complete-values")));
+
+    let main = &pages[0].content;
+    assert!(main.starts_with(".TH CARDAMOM 1 \"\" \"cardamom 1.2.3\" \"User Commands\"\n"));
+    assert!(main.contains("cardamom \\- CLI to manage contacts\n"));
+    assert!(main.contains("\\fB\\-c\\fR, \\fB\\-\\-config\\fR \\fIPATH\\fR\n"));
+    assert!(main.contains("[default: plain]\n[possible values: plain, json]\n"));
+    assert!(main.contains("\\fBcardamom\\-read\\fR(1)\nReads a card\n"));
+    assert!(main.contains(".SH EXIT STATUS\n"));
+
+    let read = pages
+        .iter()
+        .find(|page| page.name == "cardamom-read")
+        .unwrap();
+    assert!(read
+        .content
+        .contains(".SH SYNOPSIS\n\\fBcardamom read\\fR <\\fIID\\fR>\n"));
+
+    let config = pages.last().unwrap();
+    assert_eq!(config.section, 5);
+    assert!(config
+        .content
+        .contains("\\fBpasswd\\-keyring\\fR (string)\n"));
+    assert!(config
+        .content
+        .contains("\\fBpinned\\-fingerprints\\fR (array)\n"));
+}
+
+#[test]
+/// Tests that every entry of the config file is documented in the section of its table.
+fn test_man_config_entries() {
+    let pages = man_handler::pages(&app()).unwrap();
+    let config = &pages.last().unwrap().content;
+    let section = |title: &str| -> &str {
+        let start = config.find(&format!(".SH {}\n", title)).unwrap();
+        let end = config[start + 1..]
+            .find(".SH ")
+            .map_or(config.len(), |end| start + 1 + end);
+        &config[start..end]
+    };
+
+    let tables = [
+        ("LOCAL ACCOUNTS", fields::<LocalConfigAccountEntry>()),
+        ("SQLITE ACCOUNTS", fields::<SqliteConfigAccountEntry>()),
+        ("REMOTE ACCOUNTS", fields::<RemoteConfigAccountEntry>()),
+        // The basic and digest tables refer to the password entries of the account.
+        ("REMOTE ACCOUNTS", fields::<PasswdAuthConfig>()),
+        ("AUTHENTICATION", fields::<OAuth2Config>()),
+        ("AUTHENTICATION", vec!["type"]),
+        ("TLS", fields::<TlsConfig>()),
+        ("RETRY", fields::<RetryConfig>()),
+    ];
+    for (title, keys) in tables {
+        assert!(!keys.is_empty(), "no entry found for {}", title);
+        for key in keys {
+            // Sub-tables have their own section.
+            if ["auth", "tls", "retry"].contains(&key) && title == "REMOTE ACCOUNTS" {
+                continue;
+            }
+            let item = format!(".TP\n\\fB{}\\fR (", key.replace('-', "\\-"));
+            assert!(
+                section(title).contains(&item),
+                "{} missing from {}",
+                key,
+                title
+            );
+        }
+    }
+}
+
+#[test]
+fn test_man_generate() {
+    let mut output = vec![];
+    man_handler::generate(app(), Some("cardamom.toml"), None, &mut output).unwrap();
+    assert!(String::from_utf8(output)
+        .unwrap()
+        .starts_with(".TH CARDAMOM.TOML 5"));
+
+    assert!(man_handler::generate(app(), Some("unknown"), None, &mut vec![]).is_err());
+
+    let dir = env::temp_dir().join(format!("cardamom-man-{}", std::process::id()));
+    man_handler::generate(app(), None, dir.to_str(), &mut vec![]).unwrap();
+    assert!(dir.join("man1").join("cardamom-doctor.1").is_file());
+    assert!(dir.join("man5").join("cardamom.toml.5").is_file());
+    fs::remove_dir_all(dir).unwrap();
+}
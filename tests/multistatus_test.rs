@@ -0,0 +1,98 @@
+use anyhow::Result;
+
+use cardamom::domain::card_repositories::multistatus::{
+    FromProps, Multistatus, MultistatusError, Props, DAV,
+};
+
+#[derive(Debug)]
+struct EtagProp {
+    getetag: String,
+}
+
+impl FromProps for EtagProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        Ok(Self {
+            getetag: props.text(DAV, "getetag")?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct DisplayNameProp {
+    _displayname: String,
+}
+
+impl FromProps for DisplayNameProp {
+    fn from_props(props: &Props) -> Result<Self, MultistatusError> {
+        Ok(Self {
+            _displayname: props.text(DAV, "displayname")?,
+        })
+    }
+}
+
+#[test]
+/// Tests that a response containing both a 200 and a 404 propstat keeps the found properties and
+/// reports the missing ones, whatever the namespace prefix used by the server.
+fn test_multistatus_multiple_propstats() -> Result<()> {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <d:multistatus xmlns:d="DAV:">
+            <d:response>
+                <d:href>/addressbook/card.vcf</d:href>
+                <d:propstat>
+                    <d:prop><d:getetag>"abc"</d:getetag></d:prop>
+                    <d:status>HTTP/1.1 200 OK</d:status>
+                </d:propstat>
+                <d:propstat>
+                    <d:prop><d:displayname /></d:prop>
+                    <d:status>HTTP/1.1 404 Not Found</d:status>
+                </d:propstat>
+            </d:response>
+        </d:multistatus>"#;
+    let res: Multistatus = xml.parse()?;
+    assert_eq!(res.responses.len(), 1);
+    assert_eq!(res.responses[0].propstats.len(), 2);
+
+    let prop: EtagProp = res.responses[0].prop()?;
+    assert_eq!(prop.getetag, r#""abc""#);
+
+    let err = res.responses[0].prop::<DisplayNameProp>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"cannot find property "displayname" in response "/addressbook/card.vcf" (reported as 404 Not Found)"#
+    );
+
+    Ok(())
+}
+
+#[test]
+/// Tests a sync collection report, with a default namespace, a deleted member and a sync token.
+fn test_multistatus_sync_report() -> Result<()> {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <multistatus xmlns="DAV:">
+            <response>
+                <href>/addressbook/new.vcf</href>
+                <propstat>
+                    <prop><getetag>"new"</getetag></prop>
+                    <status>HTTP/1.1 200 OK</status>
+                </propstat>
+            </response>
+            <response>
+                <href>/addressbook/deleted.vcf</href>
+                <status>HTTP/1.1 404 Not Found</status>
+            </response>
+            <sync-token>http://example.com/sync/2</sync-token>
+        </multistatus>"#;
+    let res: Multistatus = xml.parse()?;
+    assert_eq!(res.responses.len(), 2);
+    assert!(!res.responses[0].is_not_found());
+    assert!(res.responses[1].is_not_found());
+    assert_eq!(res.sync_token.as_deref(), Some("http://example.com/sync/2"));
+
+    let err = res.responses[1].prop::<EtagProp>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"cannot find property "getetag" in response "/addressbook/deleted.vcf""#
+    );
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::{env, fs};
+
+use cardamom::config::{Netrc, NetrcMachine};
+
+const NETRC: &str = r#"# personal servers
+machine dav.example.com login alice password "p4ss word"
+machine other.example.com
+  login bob
+  port 2121
+  password secret # work
+
+macdef init
+cd /pub
+binary
+
+default login anonymous password guest
+"#;
+
+#[test]
+fn test_netrc_parse() {
+    let netrc: Netrc = NETRC.parse().unwrap();
+
+    assert_eq!(
+        netrc.find("dav.example.com"),
+        Some(&NetrcMachine {
+            host: Some("dav.example.com".into()),
+            login: Some("alice".into()),
+            passwd: Some("p4ss word".into()),
+            account: None,
+        })
+    );
+    assert_eq!(
+        netrc
+            .find("other.example.com")
+            .and_then(|m| m.passwd.as_deref()),
+        Some("secret")
+    );
+    assert_eq!(
+        netrc
+            .find("unknown.example.com")
+            .and_then(|m| m.login.as_deref()),
+        Some("anonymous")
+    );
+    assert_eq!(netrc.macros[0].name, "init");
+    assert_eq!(netrc.macros[0].body, "cd /pub\nbinary\n");
+
+    assert_eq!(netrc.to_string(), NETRC);
+
+    assert!("login alice".parse::<Netrc>().is_err());
+    assert!("machine".parse::<Netrc>().is_err());
+}
+
+#[test]
+fn test_netrc_set() {
+    let mut netrc: Netrc = NETRC.parse().unwrap();
+    netrc.set("dav.example.com", "alice", "new pass").unwrap();
+    netrc.set("other.example.com", "bob", "s3cret").unwrap();
+    netrc.set("new.example.com", "carol", "secret").unwrap();
+
+    // Only the changed tokens are rewritten.
+    assert_eq!(
+        netrc.to_string(),
+        NETRC
+            .replace(r#""p4ss word""#, r#""new pass""#)
+            .replace("password secret", "password s3cret")
+            .replace(
+                "default login",
+                "machine new.example.com login carol password secret\ndefault login"
+            )
+    );
+
+    let netrc: Netrc = netrc.to_string().parse().unwrap();
+    assert_eq!(netrc.machines.len(), 4);
+    assert_eq!(
+        netrc
+            .find("dav.example.com")
+            .and_then(|m| m.passwd.as_deref()),
+        Some("new pass")
+    );
+    assert_eq!(
+        netrc
+            .find("new.example.com")
+            .and_then(|m| m.login.as_deref()),
+        Some("carol")
+    );
+    assert_eq!(netrc.machines[3].host, None);
+    assert_eq!(netrc.macros[0].body, "cd /pub\nbinary\n");
+}
+
+#[test]
+fn test_netrc_set_missing_tokens() {
+    let mut netrc: Netrc = "machine dav.example.com\nmachine other.example.com login bob"
+        .parse()
+        .unwrap();
+    netrc.set("dav.example.com", "alice", "pass").unwrap();
+    netrc.set("new.example.com", "carol", "secret").unwrap();
+    assert_eq!(
+        netrc.to_string(),
+        "machine dav.example.com login alice password pass\n\
+         machine other.example.com login bob\n\
+         machine new.example.com login carol password secret\n"
+    );
+}
+
+#[test]
+/// Tests that tokens starting like a comment are quoted, so that they read back as is.
+fn test_netrc_set_comment_like_token() {
+    let mut netrc: Netrc = "machine dav.example.com login alice password old\n\
+                            machine other.example.com login bob password secret\n"
+        .parse()
+        .unwrap();
+    netrc.set("dav.example.com", "#alice", "#s3cret").unwrap();
+    assert!(netrc
+        .to_string()
+        .contains(r##"login "#alice" password "#s3cret""##));
+
+    let netrc: Netrc = netrc.to_string().parse().unwrap();
+    let dav = netrc.find("dav.example.com").unwrap();
+    assert_eq!(dav.login.as_deref(), Some("#alice"));
+    assert_eq!(dav.passwd.as_deref(), Some("#s3cret"));
+    let other = netrc.find("other.example.com").unwrap();
+    assert_eq!(other.passwd.as_deref(), Some("secret"));
+}
+
+#[test]
+fn test_netrc_write() {
+    let dir = env::temp_dir().join(format!("cardamom-netrc-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("netrc");
+    fs::write(&path, NETRC).unwrap();
+    env::set_var("NETRC", &path);
+
+    let mut netrc = Netrc::read().unwrap();
+    netrc.set("dav.example.com", "alice", "new pass").unwrap();
+    netrc.write().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), netrc.to_string());
+    assert!(!dir.join(".netrc.tmp").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    env::remove_var("NETRC");
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use reqwest::blocking::Client;
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use cardamom::{
+    config::{OAuth2Config, OAuth2Flow, OAuth2TokenProvider},
+    domain::{card_repositories::TokenProvider, CardError},
+};
+
+/// Spawns a local OAuth2 stand-in replying the given responses in order, and returning the
+/// request lines and bodies it received.
+fn spawn_server(responses: Vec<(u16, &'static str)>) -> (SocketAddr, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = thread::spawn(move || {
+        let mut requests = vec![];
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(val) = line.to_lowercase().strip_prefix("content-length:") {
+                    len = val.trim().parse().unwrap();
+                }
+            }
+            let mut req_body = vec![0; len];
+            reader.read_exact(&mut req_body).unwrap();
+            requests.push(format!(
+                "{} {}",
+                request_line.trim(),
+                String::from_utf8(req_body).unwrap()
+            ));
+            write!(
+                stream,
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+        requests
+    });
+    (addr, handle)
+}
+
+#[test]
+/// Tests that the access token is refreshed with the stored refresh token, that the new refresh
+/// token is stored and that the access token is cached.
+fn test_oauth2_refresh() -> Result<()> {
+    let (addr, server) = spawn_server(vec![(
+        200,
+        r#"{"access_token":"at1","expires_in":3600,"refresh_token":"rt2"}"#,
+    )]);
+    let store = env::temp_dir().join(format!("cardamom-oauth2-{}", addr.port()));
+
+    let config = OAuth2Config {
+        client_id: "cardamom".into(),
+        token_url: format!("http://{}/token", addr),
+        refresh_token_cmd: "echo rt1".into(),
+        refresh_token_store_cmd: Some(format!("cat > {}", store.display()).into()),
+        ..OAuth2Config::default()
+    };
+    let provider = OAuth2TokenProvider::new(config, Client::new());
+
+    assert_eq!(provider.token()?, "at1");
+    assert_eq!(provider.token()?, "at1");
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("POST /token"));
+    assert!(requests[0].contains("grant_type=refresh_token&refresh_token=rt1&client_id=cardamom"));
+    assert_eq!(fs::read_to_string(&store)?, "rt2");
+
+    fs::remove_file(store)?;
+    Ok(())
+}
+
+#[test]
+/// Tests the device code flow, triggered when no refresh token is stored.
+fn test_oauth2_device_code_flow() -> Result<()> {
+    let (addr, server) = spawn_server(vec![
+        (
+            200,
+            r#"{"device_code":"dc","user_code":"UC","verification_uri":"http://verify","expires_in":60,"interval":0}"#,
+        ),
+        (400, r#"{"error":"authorization_pending"}"#),
+        (200, r#"{"access_token":"at","expires_in":3600}"#),
+    ]);
+
+    let config = OAuth2Config {
+        client_id: "cardamom".into(),
+        device_auth_url: Some(format!("http://{}/device", addr)),
+        token_url: format!("http://{}/token", addr),
+        scopes: vec!["carddav".into()],
+        flow: OAuth2Flow::DeviceCode,
+        refresh_token_cmd: "true".into(),
+        ..OAuth2Config::default()
+    };
+    let provider = OAuth2TokenProvider::new(config, Client::new());
+
+    assert_eq!(provider.token()?, "at");
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("POST /device"));
+    assert!(requests[0].contains("client_id=cardamom&scope=carddav"));
+    assert!(requests[2].contains("device_code=dc"));
+    Ok(())
+}
+
+#[test]
+/// Tests that the authorization code flow gives up when the browser never reaches the redirect
+/// listener.
+fn test_oauth2_redirect_timeout() {
+    let config = OAuth2Config {
+        client_id: "cardamom".into(),
+        auth_url: Some("http://127.0.0.1:9/authorize".into()),
+        token_url: "http://127.0.0.1:9/token".into(),
+        redirect_timeout: Some(1),
+        refresh_token_cmd: "true".into(),
+        ..OAuth2Config::default()
+    };
+    let provider = OAuth2TokenProvider::new(config, Client::new());
+
+    let start = Instant::now();
+    let err = provider.token().unwrap_err();
+    assert!(matches!(err, CardError::Auth(_)));
+    assert!(err.to_string().contains("within 1 seconds"));
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn test_oauth2_redirect_ignores_stray_requests() {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = OAuth2Config {
+        client_id: "cardamom".into(),
+        auth_url: Some("http://127.0.0.1:9/authorize".into()),
+        token_url: "http://127.0.0.1:9/token".into(),
+        redirect_port: Some(port),
+        redirect_timeout: Some(10),
+        refresh_token_cmd: "true".into(),
+        ..OAuth2Config::default()
+    };
+    let provider = OAuth2TokenProvider::new(config, Client::new());
+
+    let browser = thread::spawn(move || {
+        let connect = || loop {
+            match std::net::TcpStream::connect(("127.0.0.1", port)) {
+                Ok(stream) => return stream,
+                Err(_) => thread::sleep(Duration::from_millis(50)),
+            }
+        };
+        // A preconnect closed without any request.
+        drop(connect());
+
+        let mut favicon = connect();
+        write!(
+            favicon,
+            "GET /favicon.ico HTTP/1.1\r\nHost: localhost\r\n\r\n"
+        )
+        .unwrap();
+        let mut status = String::new();
+        BufReader::new(&favicon).read_line(&mut status).unwrap();
+
+        let mut redirect = connect();
+        write!(
+            redirect,
+            "GET /?error=access_denied HTTP/1.1\r\nHost: localhost\r\n\r\n"
+        )
+        .unwrap();
+        let mut reply = String::new();
+        BufReader::new(&redirect).read_line(&mut reply).unwrap();
+        (status, reply)
+    });
+
+    let err = provider.token().unwrap_err();
+    assert!(matches!(err, CardError::Auth(_)));
+    assert!(err.to_string().contains("access_denied"));
+    let (status, reply) = browser.join().unwrap();
+    assert!(status.starts_with("HTTP/1.1 404"));
+    assert!(reply.starts_with("HTTP/1.1 200"));
+}
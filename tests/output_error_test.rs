@@ -0,0 +1,37 @@
+use anyhow::{anyhow, Context, Error};
+
+use cardamom::{
+    domain::CardError,
+    output::{ErrorKind, OutputFmt},
+};
+use std::convert::TryFrom;
+
+#[test]
+/// Tests that errors wrapped with context are mapped to the exit code of their typed cause.
+fn test_error_kind_from_chain() {
+    let err = Error::from(CardError::NotFound("id".into())).context("cannot read card");
+    assert_eq!(ErrorKind::from(&err), ErrorKind::NotFound);
+    assert_eq!(ErrorKind::from(&err).exit_code(), 3);
+
+    let res: Result<(), CardError> = Err(CardError::PreconditionFailed {
+        id: "id".into(),
+        current_etag: Some(r#""etag""#.into()),
+    });
+    let err = res.context("cannot update card").unwrap_err();
+    assert_eq!(ErrorKind::from(&err), ErrorKind::Conflict);
+    assert_eq!(ErrorKind::from(&err).exit_code(), 4);
+
+    let err = Error::from(CardError::Unauthorized);
+    assert_eq!(ErrorKind::from(&err).exit_code(), 5);
+
+    let err = anyhow!("cannot find default account");
+    assert_eq!(ErrorKind::from(&err), ErrorKind::Error);
+    assert_eq!(ErrorKind::from(&err).exit_code(), 1);
+}
+
+#[test]
+fn test_output_fmt() {
+    assert_eq!(OutputFmt::try_from(None).unwrap(), OutputFmt::Plain);
+    assert_eq!(OutputFmt::try_from(Some("json")).unwrap(), OutputFmt::Json);
+    assert!(OutputFmt::try_from(Some("xml")).is_err());
+}
@@ -2,7 +2,10 @@ use anyhow::Result;
 use chrono::Local;
 use reqwest::blocking::Client;
 
-use cardamom::domain::{card_repositories::RemoteCardRepository, Card, CardRepository};
+use cardamom::domain::{
+    card_repositories::{Auth, RemoteCardRepository, RetryPolicy},
+    Card, CardError, CardRepository,
+};
 
 #[test]
 /// Tests the remote card repository methods by running a simple flow create -> read -> update ->
@@ -10,7 +13,12 @@ use cardamom::domain::{card_repositories::RemoteCardRepository, Card, CardReposi
 fn test_remote_card_repository() -> Result<()> {
     let host = "http://localhost:5232";
     let client = Client::new();
-    let repository = RemoteCardRepository::new(host, &client)?;
+    let auth = Auth::Basic {
+        login: "user".into(),
+        passwd: "".into(),
+    };
+    let repository =
+        RemoteCardRepository::with_options(host, &client, auth, RetryPolicy::default())?;
 
     let id = "4d60020b-7ee8-4a36-8d3a-eec1323def45";
     let mut card = Card {
@@ -70,10 +78,7 @@ fn test_remote_card_repository() -> Result<()> {
 
     // Checks that the card has been deleted.
     let res = repository.read(id);
-    assert_eq!(
-        res.unwrap_err().to_string(),
-        format!(r#"cannot read card "{}""#, id)
-    );
+    assert!(matches!(res, Err(CardError::NotFound(ref res_id)) if res_id == id));
 
     Ok(())
 }
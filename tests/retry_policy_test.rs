@@ -0,0 +1,57 @@
+use anyhow::Result;
+use reqwest::blocking::Client;
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+use cardamom::domain::card_repositories::RetryPolicy;
+
+#[test]
+fn test_retry_policy_delay() {
+    let policy = RetryPolicy {
+        max_retries: 5,
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(500),
+    };
+    assert_eq!(policy.delay(0), Duration::from_millis(100));
+    assert_eq!(policy.delay(1), Duration::from_millis(200));
+    assert_eq!(policy.delay(2), Duration::from_millis(400));
+    assert_eq!(policy.delay(3), Duration::from_millis(500));
+    assert_eq!(policy.delay(64), Duration::from_millis(500));
+}
+
+#[test]
+/// Tests that a request is retried after a transient status, against a server replying 503 then
+/// 200.
+fn test_retry_policy_send() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let server = thread::spawn(move || {
+        for status in &["503 Service Unavailable", "200 OK"] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).unwrap();
+            let res = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            stream.write_all(res.as_bytes()).unwrap();
+        }
+    });
+
+    let policy = RetryPolicy {
+        max_retries: 2,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(10),
+    };
+    let client = Client::new();
+    let req = client.get(format!("http://{}/", addr)).build()?;
+    let res = policy.execute(&client, req)?;
+    assert!(res.status().is_success());
+
+    server.join().unwrap();
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use chrono::{Local, TimeZone};
+use std::{convert::TryFrom, env, fs};
+
+use cardamom::{
+    config::{Account, Config},
+    domain::{
+        card_repositories::SqliteCardRepository, read_vdir, write_vdir, Card, CardError,
+        CardFilter, CardRepository,
+    },
+};
+
+mod common;
+use common::vcard;
+
+#[test]
+fn test_sqlite_repository() {
+    let repository = SqliteCardRepository::open_in_memory().unwrap();
+
+    let mut jane = vcard("jane", &["FN:Jane Doe", "EMAIL:jane@example.com"]);
+    repository.create(&mut jane).unwrap();
+    assert!(jane.etag.is_some());
+    assert!(matches!(
+        repository.create(&mut vcard("jane", &["FN:Jane"])),
+        Err(CardError::PreconditionFailed { .. })
+    ));
+
+    let read = repository.read("jane").unwrap();
+    assert_eq!(read.raw, jane.raw);
+    assert_eq!(read.etag, jane.etag);
+
+    // Updates with an outdated etag are rejected.
+    let mut outdated = repository.read("jane").unwrap();
+    jane.raw = jane.raw.replace("Jane Doe", "Jane Smith");
+    repository.update(&mut jane).unwrap();
+    outdated.raw = outdated.raw.replace("Jane Doe", "Jane D.");
+    assert!(matches!(
+        repository.update(&mut outdated),
+        Err(CardError::PreconditionFailed { .. })
+    ));
+    assert!(matches!(
+        repository.delete(&outdated),
+        Err(CardError::PreconditionFailed { .. })
+    ));
+
+    repository.delete(&jane).unwrap();
+    assert!(matches!(
+        repository.read("jane"),
+        Err(CardError::NotFound(_))
+    ));
+    assert!(repository.read_all().unwrap().is_empty());
+}
+
+#[test]
+fn test_sqlite_search() {
+    let repository = SqliteCardRepository::open_in_memory().unwrap();
+    repository
+        .create(&mut vcard(
+            "jane",
+            &["FN:Jane Doe", "ORG:Acme;Sales", "TEL:+33606060606"],
+        ))
+        .unwrap();
+    repository
+        .create(&mut vcard("john", &["FN:John", "EMAIL:JOHN@ÉCOLE.FR"]))
+        .unwrap();
+
+    let ids = |query: &str| -> Vec<String> {
+        let filter = CardFilter {
+            query: Some(query.into()),
+            tag: None,
+        };
+        repository
+            .search(&filter)
+            .unwrap()
+            .into_iter()
+            .filter(|card| filter.matches(card))
+            .map(|card| card.id)
+            .collect()
+    };
+
+    assert_eq!(ids("acme"), vec!["jane"]);
+    assert_eq!(ids("0606"), vec!["jane"]);
+    assert_eq!(ids("école"), vec!["john"]);
+    assert_eq!(ids("j"), vec!["jane", "john"]);
+    assert!(ids("nobody").is_empty());
+    assert!(ids(r#"jo"hn"#).is_empty());
+    assert_eq!(repository.search(&CardFilter::default()).unwrap().len(), 2);
+
+    // The full-text index follows updates and deletions.
+    let mut jane = repository.read("jane").unwrap();
+    jane.raw = jane.raw.replace("Acme", "Initech");
+    repository.update(&mut jane).unwrap();
+    assert!(ids("acme").is_empty());
+    assert_eq!(ids("initech"), vec!["jane"]);
+    repository
+        .delete(&repository.read("john").unwrap())
+        .unwrap();
+    assert!(ids("école").is_empty());
+    assert_eq!(ids("j"), vec!["jane"]);
+}
+
+#[test]
+/// Tests that the full-text index still matches the cards once the database is vacuumed, which
+/// may renumber implicit rowids.
+fn test_sqlite_vacuum() {
+    let repository = SqliteCardRepository::open_in_memory().unwrap();
+    for (id, name) in [
+        ("jane", "Jane Doe"),
+        ("john", "John Smith"),
+        ("joe", "Joe Bloggs"),
+    ] {
+        let mut card = vcard(id, &[&format!("FN:{}", name)]);
+        repository.create(&mut card).unwrap();
+    }
+    repository
+        .delete(&repository.read("jane").unwrap())
+        .unwrap();
+    repository.conn.execute_batch("VACUUM").unwrap();
+
+    let filter = CardFilter {
+        query: Some("bloggs".into()),
+        tag: None,
+    };
+    let found = repository.search(&filter).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, "joe");
+}
+
+#[test]
+fn test_sqlite_vdir_migration() {
+    let dir = env::temp_dir().join(format!("cardamom-sqlite-{}", std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    let vdir = dir.join("contacts");
+    let config = Config::parse(&format!(
+        "[cards.sqlite]\npath = {:?}\n",
+        dir.join("cards.db").to_string_lossy()
+    ))
+    .unwrap()
+    .0;
+    let account = Account::try_from((&config, None)).unwrap();
+
+    let date = Local.ymd(2021, 6, 1).and_hms(12, 0, 0);
+    let cards: Vec<Card> = vec![vcard("jane", &["FN:Jane"]), vcard("john", &["FN:John"])]
+        .into_iter()
+        .map(|card| Card { date, ..card })
+        .collect();
+    assert_eq!(write_vdir(&vdir, &cards).unwrap(), 2);
+    fs::write(vdir.join("notes.txt"), "not a card").unwrap();
+
+    let repository = match &account {
+        Account::Sqlite(account) => account.repository().unwrap(),
+        account => panic!("unexpected account {:?}", account),
+    };
+    let mut imported = read_vdir(&vdir).unwrap();
+    assert_eq!(repository.import(&mut imported).unwrap(), 2);
+    drop(repository);
+
+    let ids = account
+        .with_repository(|repository| {
+            let mut jane = repository.read("jane")?;
+            jane.raw = jane.raw.replace("FN:Jane", "FN:Jane Doe");
+            repository.update(&mut jane)?;
+            Ok(repository
+                .read_all()?
+                .into_iter()
+                .map(|card| card.id)
+                .collect::<Vec<_>>())
+        })
+        .unwrap();
+    assert_eq!(ids, vec!["jane", "john"]);
+
+    let exported = dir.join("exported");
+    let cards = account
+        .with_repository(|repository| Ok(repository.read_all()?))
+        .unwrap();
+    write_vdir(&exported, &cards).unwrap();
+    let exported = read_vdir(&exported).unwrap();
+    assert_eq!(exported.len(), 2);
+    assert!(exported[0].raw.contains("FN:Jane Doe"));
+    assert_eq!(exported[1].raw, cards[1].raw);
+    // Cards left untouched keep their date through the round trip.
+    assert_eq!(exported[1].date, date);
+    assert!(exported[0].date > date);
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,128 @@
+use cardamom::domain::{
+    card_arg::Target,
+    tag_arg::{self, Cmd},
+    Card, CardFilter,
+};
+
+mod common;
+use common::card;
+
+fn jane() -> Card {
+    card(
+        "jane",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "FN:Jane Doe",
+            "EMAIL:jane@example.org",
+            "CATEGORIES:Clients,vendors\\, EU",
+            "CATEGORIES:clients,on-call",
+            "END:VCARD",
+        ],
+    )
+}
+
+#[test]
+fn test_tags() {
+    let card = jane();
+    assert_eq!(card.tags(), vec!["Clients", "vendors, EU", "on-call"]);
+    assert!(card.has_tag("CLIENTS"));
+    assert!(!card.has_tag("vendors"));
+}
+
+#[test]
+fn test_edit_tags() {
+    let mut card = jane();
+    assert!(!card.add_tag("clients"));
+    assert!(card.add_tag("VIP"));
+    assert!(card
+        .raw
+        .contains("CATEGORIES:Clients,vendors\\, EU,on-call,VIP\r\nEND:VCARD"));
+    assert_eq!(card.raw.matches("CATEGORIES").count(), 1);
+
+    assert!(card.rename_tag("on-call", "clients"));
+    assert_eq!(card.tags(), vec!["Clients", "vendors, EU", "VIP"]);
+    assert!(!card.rename_tag("on-call", "support"));
+
+    assert!(card.remove_tag("vip"));
+    assert!(!card.remove_tag("vip"));
+    assert!(card.remove_tag("Clients"));
+    assert!(card.remove_tag("vendors, EU"));
+    assert!(card.tags().is_empty());
+    assert!(!card.raw.contains("CATEGORIES"));
+    assert!(card
+        .raw
+        .ends_with("EMAIL:jane@example.org\r\nEND:VCARD\r\n"));
+}
+
+#[test]
+/// Tests that editing the tags keeps the group and parameters of the first `CATEGORIES`
+/// property, along with its position.
+fn test_edit_tags_keeps_params() {
+    let mut card = card(
+        "john",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "item1.CATEGORIES;PID=1.1;TYPE=work:Clients",
+            "FN:John",
+            "CATEGORIES:on-call",
+            "END:VCARD",
+        ],
+    );
+    assert!(card.add_tag("VIP"));
+    assert_eq!(
+        card.raw,
+        "BEGIN:VCARD\r\nVERSION:4.0\r\nitem1.CATEGORIES;PID=1.1;TYPE=work:Clients,on-call,VIP\r\nFN:John\r\nEND:VCARD\r\n"
+    );
+}
+
+#[test]
+fn test_card_filter() {
+    let card = jane();
+    assert!(CardFilter::default().matches(&card));
+    let filter = |query: Option<&str>, tag: Option<&str>| CardFilter {
+        query: query.map(String::from),
+        tag: tag.map(String::from),
+    };
+    assert!(filter(Some("doe"), None).matches(&card));
+    assert!(filter(Some("@EXAMPLE.org"), None).matches(&card));
+    assert!(filter(Some("doe"), Some("on-call")).matches(&card));
+    assert!(!filter(Some("doe"), Some("vip")).matches(&card));
+    assert!(!filter(Some("john"), None).matches(&card));
+    assert!(!filter(Some("clients"), None).matches(&card));
+}
+
+#[test]
+fn test_tag_arg() {
+    let app = || clap::App::new("cardamom").subcommands(tag_arg::subcmds());
+    let matches = |args: &[&str]| {
+        let m = app().get_matches_from_safe(args.iter()).unwrap();
+        format!("{:?}", tag_arg::matches(&m).unwrap())
+    };
+
+    assert_eq!(
+        matches(&["cardamom", "tag", "add", "a", "b", "--id", "jane"]),
+        format!("{:?}", Some(Cmd::Add(vec!["a", "b"], Target::Id("jane"))))
+    );
+    assert_eq!(
+        matches(&["cardamom", "tag", "rm", "a", "-s", "doe"]),
+        format!("{:?}", Some(Cmd::Remove(vec!["a"], Target::Search("doe"))))
+    );
+    assert_eq!(
+        matches(&["cardamom", "tag", "rename", "a", "b"]),
+        format!("{:?}", Some(Cmd::Rename("a", "b", Target::All)))
+    );
+    assert_eq!(
+        matches(&["cardamom", "tags", "ls"]),
+        format!("{:?}", Some(Cmd::List(Target::All)))
+    );
+
+    // Adding or removing tags needs an explicit target, and only one.
+    assert!(app()
+        .get_matches_from_safe(["cardamom", "tag", "add", "a"])
+        .is_err());
+    assert!(app()
+        .get_matches_from_safe(["cardamom", "tag", "add", "a", "-i", "x", "-s", "y"])
+        .is_err());
+}
@@ -0,0 +1,180 @@
+use chrono::{Duration, TimeZone, Utc};
+use std::{env, fs};
+
+use cardamom::{
+    config::Config,
+    domain::{
+        card_repositories::{SqliteCardRepository, TelIndexInvalidatingCardRepository},
+        lookup_arg::{self, Cmd},
+        tel_digits, CardRepository, Region, TelIndex,
+    },
+};
+
+mod common;
+use common::card;
+
+fn index() -> TelIndex {
+    let cards = vec![
+        card(
+            "jane",
+            &[
+                "BEGIN:VCARD",
+                "FN:Jane Doe",
+                "ORG:Acme\\, Inc.;Sales",
+                "TEL;TYPE=cell:06 06 06 06 06",
+                "TEL;TYPE=work:+33 1 23 45 67 89",
+                "END:VCARD",
+            ],
+        ),
+        card(
+            "john",
+            &[
+                "BEGIN:VCARD",
+                "FN:John",
+                "TEL;VALUE=uri:tel:+1-201-555-0123",
+                "TEL:+33 6 06 06 06 06",
+                "END:VCARD",
+            ],
+        ),
+        card(
+            "bob",
+            &["BEGIN:VCARD", "FN:Bob", "TEL:0201-555-0123", "END:VCARD"],
+        ),
+    ];
+    TelIndex::build(
+        &cards,
+        Some(Region::FR),
+        Utc.ymd(2021, 6, 1).and_hms(0, 0, 0),
+    )
+}
+
+#[test]
+fn test_tel_digits() {
+    assert_eq!(
+        tel_digits("06 06 06 06 06", Some(Region::FR)),
+        "33606060606"
+    );
+    assert_eq!(tel_digits("06 06 06 06 06", None), "606060606");
+    assert_eq!(
+        tel_digits("tel:+1-201-555-0123;ext=42", None),
+        "12015550123"
+    );
+    assert_eq!(tel_digits("unknown", None), "");
+}
+
+#[test]
+fn test_tel_index_lookup() {
+    let index = index();
+    assert_eq!(index.entries.len(), 5);
+
+    // Both cards share the number, once per card.
+    let found = index.lookup("+33606060606", None);
+    let ids: Vec<&str> = found.iter().map(|e| e.card_id.as_str()).collect();
+    assert_eq!(ids, vec!["jane", "john"]);
+    assert_eq!(found[0].name, "Jane Doe");
+    assert_eq!(found[0].org.as_deref(), Some("Acme, Inc."));
+    assert_eq!(found[1].org, None);
+
+    let found = index.lookup("01 23 45 67 89", Some(Region::FR));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].tel, "+33 1 23 45 67 89");
+
+    // Exact matches win over suffix ones.
+    let found = index.lookup("+1 (201) 555-0123", None);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].card_id, "john");
+
+    // Numbers that cannot be normalized match by their national digits.
+    let found = index.lookup("201-555-0123", None);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].card_id, "bob");
+
+    // Without exact match, numbers sharing enough trailing digits match.
+    let found = index.lookup("555-0123", None);
+    let ids: Vec<&str> = found.iter().map(|e| e.card_id.as_str()).collect();
+    assert_eq!(ids, vec!["john", "bob"]);
+    assert!(index.lookup("0123", None).is_empty());
+    assert!(index.lookup("+44 20 7946 0958", None).is_empty());
+}
+
+#[test]
+fn test_tel_index_file() {
+    let index = index();
+    let path = env::temp_dir()
+        .join("cardamom-tel-index-test")
+        .join("index.json");
+    index.write(&path).unwrap();
+    assert_eq!(TelIndex::read(&path).unwrap(), index);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    let built_at = index.built_at;
+    assert!(!index.is_older_than(Duration::hours(1), built_at + Duration::minutes(59)));
+    assert!(index.is_older_than(Duration::hours(1), built_at + Duration::minutes(61)));
+}
+
+#[test]
+fn test_cache_dir() {
+    env::set_var("XDG_CACHE_HOME", "/tmp/cache");
+    assert_eq!(
+        Config::cache_dir().unwrap().to_str(),
+        Some("/tmp/cache/cardamom")
+    );
+}
+
+#[test]
+fn test_tel_index_invalidation() {
+    let path = env::temp_dir()
+        .join("cardamom-tel-index-invalidation-test")
+        .join("index.json");
+    let sqlite = SqliteCardRepository::open_in_memory().unwrap();
+    let repository = TelIndexInvalidatingCardRepository::new(&sqlite, path.clone());
+
+    index().write(&path).unwrap();
+    repository.read_all().unwrap();
+    assert!(path.exists());
+
+    let mut jane = card("jane", &["BEGIN:VCARD", "FN:Jane", "END:VCARD"]);
+    repository.create(&mut jane).unwrap();
+    assert!(!path.exists());
+
+    index().write(&path).unwrap();
+    jane.raw = jane.raw.replace("FN:Jane", "FN:Jane Doe");
+    repository.update(&mut jane).unwrap();
+    assert!(!path.exists());
+
+    index().write(&path).unwrap();
+    repository.delete(&jane).unwrap();
+    assert!(!path.exists());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_lookup_max_age_arg() {
+    let app = clap::App::new("cardamom").subcommands(lookup_arg::subcmds());
+    let matches = |args: &[&str]| {
+        app.clone()
+            .get_matches_from_safe([&["cardamom", "lookup-tel", "0606"], args].concat())
+    };
+
+    let m = matches(&[]).unwrap();
+    assert_eq!(
+        lookup_arg::matches(&m).unwrap(),
+        Some(Cmd::Tel("0606", false, 86400))
+    );
+    let m = matches(&["--max-age", "60"]).unwrap();
+    assert_eq!(
+        lookup_arg::matches(&m).unwrap(),
+        Some(Cmd::Tel("0606", false, 60))
+    );
+
+    assert!(matches(&["--max-age", "99999999999999999"]).is_err());
+    assert!(matches(&["--max-age", "-1"]).is_err());
+    assert!(matches(&["--max-age", "soon"]).is_err());
+}
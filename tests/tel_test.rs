@@ -0,0 +1,110 @@
+use cardamom::{
+    config::Config,
+    domain::{normalize_tel, parse_region, Region, TelChange},
+};
+
+mod common;
+use common::card;
+
+#[test]
+fn test_parse_region() {
+    assert_eq!(parse_region("fr").unwrap(), Region::FR);
+    assert_eq!(parse_region(" US ").unwrap(), Region::US);
+    assert!(parse_region("France").is_err());
+}
+
+#[test]
+fn test_normalize_tel() {
+    let fr = Some(Region::FR);
+    let us = Some(Region::US);
+
+    assert_eq!(
+        normalize_tel("06 06 06 06 06", fr),
+        Ok(Some("+33606060606".into()))
+    );
+    assert_eq!(
+        normalize_tel("+33 6 06 06 06 06", None),
+        Ok(Some("+33606060606".into()))
+    );
+    assert_eq!(
+        normalize_tel("(201) 555-0123", us),
+        Ok(Some("+12015550123".into()))
+    );
+    assert_eq!(
+        normalize_tel("tel:+1-201-555-0123", fr),
+        Ok(Some("tel:+12015550123".into()))
+    );
+    assert_eq!(normalize_tel("+33606060606", fr), Ok(None));
+
+    assert!(normalize_tel("06 06 06 06 06", None).is_err());
+    assert!(normalize_tel("06 06", fr).is_err());
+    assert!(normalize_tel("not a number", fr).is_err());
+    assert!(normalize_tel("tel:+1-201-555-0123;ext=42", fr).is_err());
+}
+
+#[test]
+fn test_normalize_card_tels() {
+    let mut card = card(
+        "jane",
+        &[
+            "BEGIN:VCARD",
+            "VERSION:3.0",
+            "FN:Jane",
+            "TEL;TYPE=pref:06 06 06 06 06",
+            "item1.TEL;TYPE=work:+33606060607",
+            "TEL;TYPE=home:12",
+            "END:VCARD",
+        ],
+    );
+
+    let report = card.normalize_tels(Some(Region::FR));
+    assert_eq!(
+        report.changes,
+        vec![TelChange {
+            old: "06 06 06 06 06".into(),
+            new: "+33606060606".into(),
+        }]
+    );
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].value, "12");
+    assert_eq!(
+        card.raw,
+        [
+            "BEGIN:VCARD",
+            "VERSION:3.0",
+            "FN:Jane",
+            "TEL;TYPE=pref:+33606060606",
+            "item1.TEL;TYPE=work:+33606060607",
+            "TEL;TYPE=home:12",
+            "END:VCARD",
+            "",
+        ]
+        .join("\r\n")
+    );
+
+    assert!(card.normalize_tels(Some(Region::FR)).changes.is_empty());
+}
+
+#[test]
+fn test_config_region() {
+    let (config, issues) = Config::parse(
+        r#"
+        [home.local]
+        path = "/tmp"
+        default-region = "fr"
+        normalize-tel = true
+
+        [work.local]
+        path = "/tmp"
+        default-region = "France"
+        "#,
+    )
+    .unwrap();
+    assert!(issues.is_empty());
+    let issues = config.validate();
+    assert_eq!(issues.len(), 2);
+    assert_eq!(
+        issues[1].to_string(),
+        r#"invalid phone region "France" in account "work" at line 9, column 9: expected a country code like FR"#
+    );
+}
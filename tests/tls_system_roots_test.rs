@@ -0,0 +1,37 @@
+//! Runs in its own process since it makes the test CA a system root for the whole process.
+
+mod common;
+use common::tls::{account, capabilities, capabilities_with, fixture, pin, recording_tls_server};
+
+use cardamom::{config::TlsConfig, domain::card_repositories::Auth};
+use std::env;
+
+#[test]
+/// Tests that once a certificate is pinned, a server presenting another certificate valid for
+/// the system roots fails the handshake before receiving any credentials.
+fn test_tls_pinned_ignores_system_roots() {
+    env::set_var("SSL_CERT_FILE", fixture("ca.pem"));
+
+    let (url, _) = recording_tls_server(&["server"], None);
+    let unpinned = account(url, TlsConfig::default());
+    assert!(capabilities(&unpinned).is_ok());
+
+    let (url, requests) = recording_tls_server(&["self-signed", "server"], None);
+    let pinned = account(
+        url,
+        TlsConfig {
+            pinned_fingerprints: vec![pin("self-signed")],
+            ..TlsConfig::default()
+        },
+    );
+    let auth = Auth::Basic {
+        login: "user".into(),
+        passwd: "secret".into(),
+    };
+    assert!(capabilities_with(&pinned, auth).is_err());
+    assert!(!requests
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|req| req.to_lowercase().contains("authorization:")));
+}
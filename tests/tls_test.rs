@@ -0,0 +1,128 @@
+mod common;
+use common::tls::{account, capabilities, fixture, pin, tls_server};
+
+use cardamom::{config::TlsConfig, domain::CardError};
+
+#[test]
+fn test_tls_ca_file() {
+    let url = tls_server(&["server"], None);
+
+    let trusted = account(
+        url.clone(),
+        TlsConfig {
+            ca_file: Some(fixture("ca.pem")),
+            ..TlsConfig::default()
+        },
+    );
+    assert_eq!(capabilities(&trusted).unwrap(), vec!["1", "addressbook"]);
+
+    let untrusted = account(url, TlsConfig::default());
+    assert!(untrusted
+        .client()
+        .unwrap()
+        .head(&untrusted.url)
+        .send()
+        .is_err());
+}
+
+#[test]
+fn test_tls_client_cert() {
+    let url = tls_server(&["server"], Some("ca.pem"));
+
+    let pem = account(
+        url.clone(),
+        TlsConfig {
+            ca_file: Some(fixture("ca.pem")),
+            client_cert: Some(fixture("client.pem")),
+            client_key: Some(fixture("client.key")),
+            ..TlsConfig::default()
+        },
+    );
+    assert!(capabilities(&pem).is_ok());
+
+    let pkcs12 = account(
+        url.clone(),
+        TlsConfig {
+            ca_file: Some(fixture("ca.pem")),
+            client_cert: Some(fixture("client.p12")),
+            client_cert_passwd_cmd: Some("echo secret".into()),
+            ..TlsConfig::default()
+        },
+    );
+    assert!(capabilities(&pkcs12).is_ok());
+
+    let wrong_passwd = TlsConfig {
+        client_cert_passwd_cmd: Some("echo wrong".into()),
+        ..pkcs12.tls.clone()
+    };
+    assert!(wrong_passwd.connector().is_err());
+
+    let anonymous = account(
+        url,
+        TlsConfig {
+            ca_file: Some(fixture("ca.pem")),
+            ..TlsConfig::default()
+        },
+    );
+    assert!(anonymous
+        .client()
+        .unwrap()
+        .head(&anonymous.url)
+        .send()
+        .is_err());
+}
+
+#[test]
+/// Tests that a pinned self-signed certificate is accepted without any CA.
+fn test_tls_pinned_self_signed() {
+    let url = tls_server(&["self-signed"], None);
+
+    let pinned = account(
+        url.clone(),
+        TlsConfig {
+            pinned_fingerprints: vec![pin("self-signed")],
+            ..TlsConfig::default()
+        },
+    );
+    assert!(capabilities(&pinned).is_ok());
+
+    let other = account(
+        url,
+        TlsConfig {
+            pinned_fingerprints: vec![pin("self-signed-other")],
+            ..TlsConfig::default()
+        },
+    );
+    let err = other.client().unwrap_err();
+    assert!(format!("{:#}", err).contains("does not match any pinned fingerprint"));
+}
+
+#[test]
+/// Tests that the pin is checked on the connections actually used, against a server changing
+/// its certificate after the first connection.
+fn test_tls_pinned_certificate_changed() {
+    let self_signed = account(
+        tls_server(&["self-signed", "self-signed-other"], None),
+        TlsConfig {
+            pinned_fingerprints: vec![pin("self-signed")],
+            ..TlsConfig::default()
+        },
+    );
+    assert!(matches!(
+        capabilities(&self_signed),
+        Err(CardError::Network(_))
+    ));
+
+    let ca_signed = account(
+        tls_server(&["server", "server-other"], None),
+        TlsConfig {
+            ca_file: Some(fixture("ca.pem")),
+            pinned_fingerprints: vec![pin("server")],
+            ..TlsConfig::default()
+        },
+    );
+    assert!(matches!(
+        capabilities(&ca_signed),
+        Err(CardError::UntrustedCertificate(_))
+    ));
+}
@@ -0,0 +1,82 @@
+use chrono::Local;
+
+use cardamom::domain::{escape, unescape, unfold, Card, VcardProp};
+
+fn card(lines: &[&str]) -> Card {
+    Card {
+        id: "id".into(),
+        etag: None,
+        date: Local::now(),
+        raw: lines.join("\r\n"),
+    }
+}
+
+#[test]
+fn test_unfold() {
+    assert_eq!(
+        unfold("BEGIN:VCARD\r\nNOTE:a long\r\n  note\r\n\t continued\r\nEND:VCARD\r\n"),
+        vec!["BEGIN:VCARD", "NOTE:a long note continued", "END:VCARD"]
+    );
+}
+
+#[test]
+fn test_parse_prop() {
+    let prop = VcardProp::parse(r#"item1.tel;TYPE=cell,voice;X-LABEL="a:b;c":+33 6"#).unwrap();
+    assert_eq!(prop.group.as_deref(), Some("item1"));
+    assert_eq!(prop.name, "TEL");
+    assert_eq!(prop.param("x-label"), Some("a:b;c"));
+    assert!(prop.has_param("TYPE", "VOICE"));
+    assert!(!prop.has_param("TYPE", "work"));
+    assert_eq!(prop.value, "+33 6");
+
+    let prop = VcardProp::parse("TEL;CELL:0612").unwrap();
+    assert!(prop.has_param("TYPE", "cell"));
+
+    assert_eq!(VcardProp::parse("no colon"), None);
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(unescape(r"a\, b\; c\nd\\e"), "a, b; c\nd\\e");
+    assert_eq!(escape("a, b; c\nd\\e"), r"a\, b\; c\nd\\e");
+}
+
+#[test]
+fn test_full_name() {
+    assert_eq!(
+        card(&["FN:Jane Doe", "N:Doe;John;;;"]).full_name(),
+        "Jane Doe"
+    );
+    assert_eq!(
+        card(&["N:Doe;John;Q;Dr.;Jr."]).full_name(),
+        "Dr. John Q Doe Jr."
+    );
+    assert_eq!(card(&["EMAIL:a@b.c"]).full_name(), "id");
+}
+
+#[test]
+fn test_org() {
+    assert_eq!(
+        card(&[r"ORG:Acme\, Inc.;Sales"]).org().as_deref(),
+        Some("Acme, Inc.")
+    );
+    assert_eq!(card(&["ORG: ;Sales"]).org(), None);
+    assert_eq!(card(&["FN:Jane Doe"]).org(), None);
+}
+
+#[test]
+fn test_edit_props() {
+    let mut card = Card {
+        id: "jane".into(),
+        etag: None,
+        date: Local::now(),
+        raw: "BEGIN:VCARD\nFN:Jane\nNOTE:a long\n  note\nEND:VCARD\n".into(),
+    };
+    assert_eq!(card.remove_props(|prop| prop.name == "NOTE"), 1);
+    assert_eq!(card.remove_props(|prop| prop.name == "NOTE"), 0);
+    card.push_prop("TEL:+33612345678");
+    assert_eq!(
+        card.raw,
+        "BEGIN:VCARD\nFN:Jane\nTEL:+33612345678\nEND:VCARD\n"
+    );
+}
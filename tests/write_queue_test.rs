@@ -0,0 +1,127 @@
+use chrono::Local;
+use std::{fs, path::PathBuf};
+
+use cardamom::domain::{
+    card_repositories::QueueingCardRepository, Card, CardRepository, WriteOp, WRITE_QUEUE_FILE,
+};
+
+mod common;
+use common::{temp_dir, FakeRepository};
+
+fn card(id: &str, raw: &str) -> Card {
+    Card {
+        id: id.into(),
+        etag: None,
+        date: Local::now(),
+        raw: raw.into(),
+    }
+}
+
+fn queue_path(name: &str) -> PathBuf {
+    temp_dir(&format!("queue-{}", name)).join(WRITE_QUEUE_FILE)
+}
+
+#[test]
+fn test_queue_and_flush() {
+    let path = queue_path("flush");
+    let remote = FakeRepository::default();
+    remote.create(&mut card("john", "FN:John")).unwrap();
+    let repository = QueueingCardRepository::new(Some(&remote), path.clone());
+
+    // Online writes are not queued.
+    let mut jane = card("jane", "FN:Jane");
+    repository.create(&mut jane).unwrap();
+    assert!(repository.pending().unwrap().is_empty());
+    assert!(!path.exists());
+
+    remote.offline.set(true);
+    jane.raw = "FN:Jane Doe".into();
+    repository.update(&mut jane).unwrap();
+    jane.raw = "FN:Jane D.".into();
+    repository.update(&mut jane).unwrap();
+    let mut james = card("james", "FN:James");
+    repository.create(&mut james).unwrap();
+
+    let pending = repository.pending().unwrap();
+    assert_eq!(pending.len(), 3);
+    assert_eq!(pending[0].op, WriteOp::Update);
+    assert_eq!(pending[0].base_etag.as_deref(), Some("\"2\""));
+    assert_eq!(pending[2].op, WriteOp::Create);
+
+    // Still offline: nothing replayed.
+    let report = repository.flush().unwrap();
+    assert!(report.flushed.is_empty());
+    assert_eq!(report.pending.len(), 3);
+
+    // Writes made after the server came back wait for the queued ones.
+    remote.offline.set(false);
+    let john = remote.read("john").unwrap();
+    repository.delete(&john).unwrap();
+    assert_eq!(repository.pending().unwrap().len(), 4);
+    assert!(remote.raw("john").is_some());
+
+    let report = repository.flush().unwrap();
+    assert_eq!(report.flushed.len(), 4);
+    assert!(report.conflicts.is_empty());
+    assert!(report.pending.is_empty());
+    assert_eq!(remote.raw("jane").as_deref(), Some("FN:Jane D."));
+    assert_eq!(remote.raw("james").as_deref(), Some("FN:James"));
+    assert!(remote.raw("john").is_none());
+    assert!(!path.exists());
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_flush_conflict() {
+    let path = queue_path("conflict");
+    let remote = FakeRepository::default();
+    let mut jane = card("jane", "FN:Jane");
+    remote.create(&mut jane).unwrap();
+    let repository = QueueingCardRepository::new(None, path.clone());
+
+    jane.raw = "FN:Jane Doe".into();
+    repository.update(&mut jane).unwrap();
+
+    // Modified by another client in the meantime.
+    let mut other = remote.read("jane").unwrap();
+    other.raw = "FN:Jane Smith".into();
+    remote.update(&mut other).unwrap();
+
+    let repository = QueueingCardRepository::new(Some(&remote), path.clone());
+    let report = repository.flush().unwrap();
+    assert!(report.flushed.is_empty());
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(remote.raw("jane").as_deref(), Some("FN:Jane Smith"));
+
+    let saved_to = report.conflicts[0].saved_to.as_ref().unwrap();
+    assert_eq!(fs::read_to_string(saved_to).unwrap(), "FN:Jane Doe");
+    assert!(repository.pending().unwrap().is_empty());
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_flush_conflict_unsafe_id() {
+    let path = queue_path("conflict-unsafe-id");
+    let conflicts = path.parent().unwrap().join("conflicts");
+    let remote = FakeRepository::default();
+    let mut card = card("../../escaped", "FN:Jane");
+    remote.create(&mut card).unwrap();
+    let repository = QueueingCardRepository::new(None, path.clone());
+
+    card.raw = "FN:Jane Doe".into();
+    repository.update(&mut card).unwrap();
+    let mut other = remote.read(&card.id).unwrap();
+    other.raw = "FN:Jane Smith".into();
+    remote.update(&mut other).unwrap();
+
+    // The id is hashed so that the local version stays in the conflicts directory.
+    let repository = QueueingCardRepository::new(Some(&remote), path.clone());
+    let report = repository.flush().unwrap();
+    let saved_to = PathBuf::from(report.conflicts[0].saved_to.as_ref().unwrap());
+    assert_eq!(saved_to.parent(), Some(conflicts.as_path()));
+    assert_eq!(fs::read_to_string(&saved_to).unwrap(), "FN:Jane Doe");
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}